@@ -1,10 +1,30 @@
+use crate::bookmarks;
+use crate::calibration::Calibration;
+use crate::clipboard::{self, Copied};
+use crate::cli::{CliArgs, DEFAULT_SAVE_DIR};
+use crate::csv_utils;
+use crate::csi_packet::ValueFormat;
+use crate::dsp;
 use crate::esp_port;
-use crate::parse_data;
-use crate::read_data;
-use crate::heatmap::Heatmap;
+use crate::events::{self, EVENT_LABELS};
+use crate::parse_data::{self, Probe, RecordError, RecordingConfig, RecordingStats, RerunSink};
+use crate::presets::{self, Preset};
+use crate::quality::QualityScore;
+use crate::schedule::{self, Schedule, ScheduleProgress};
+use crate::read_data::{self, AmplitudeMetric, Quantity, ReadError};
+use crate::heatmap::{Colormap, Heatmap, PNG_CELL_PX};
+use crate::input_source::InputSource;
+use crate::manifest::{Manifest, manifest_path_for};
+use crate::number_format::display;
+use crate::parquet_capture::CaptureFormat;
+use crate::spectrogram::Spectrogram;
+use crate::stuck_frames::StuckFrameDetector;
+use crate::subcarrier_layout;
+use crate::theme::Theme;
 //use crate::wifi_mode::WifiConfig;
-use crate::wifi_mode::WifiMode;
-use chrono::{DateTime, Local};
+use crate::trigger::TriggerMode;
+use crate::wifi_mode::{CsiConfig, WifiMode};
+use chrono::{DateTime, Local, TimeDelta};
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
@@ -13,28 +33,106 @@ use ratatui::{
     prelude::Buffer,
     prelude::Rect,
     style::Stylize,
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Axis, Block, Chart, Dataset, GraphType, Paragraph, Widget},
+    widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Clear, Dataset, GraphType, Paragraph, Widget},
 };
 use std::fs::{self};
+use std::path::Path;
 use std::{
-    sync::mpsc,
+    collections::{BTreeSet, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
     thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-const SAVE_DIR: &str = "saved_data";
+/// Height of the status log panel, including borders.
+const STATUS_LOG_HEIGHT: u16 = 6;
+/// Number of entries in the Options pane.
+const CONTROLS_LEN: usize = 11;
+/// Packets kept from before a trigger fires.
+const PRE_TRIGGER: Duration = Duration::from_secs(3);
+/// How long an armed recording waits for its trigger before giving up.
+const TRIGGER_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Width of the amplitude stats box next to the chart.
+const PLOT_STATS_WIDTH: u16 = 18;
+/// Height of the mean-spectrum strip under the heatmap: one row of cells
+/// plus borders.
+const SPECTRUM_HEIGHT: u16 = 3;
+/// RX antennas `n` cycles through (ESP32 reports antenna 0 or 1).
+const ANTENNA_COUNT: u8 = 2;
+/// Estimated capture size above which starting needs a second press.
+const SIZE_CONFIRM_BYTES: u64 = 1 << 30;
+/// Default and allowed range of histogram bins (`[` / `]` step by 5).
+const DEFAULT_HISTOGRAM_BINS: usize = 20;
+const HISTOGRAM_BINS: std::ops::RangeInclusive<usize> = 5..=100;
+/// Minimum time between background serial port scans.
+const PORT_SCAN_INTERVAL: Duration = Duration::from_millis(500);
+/// Number of status log entries kept in memory.
+const STATUS_LOG_CAPACITY: usize = 100;
+/// Input poll timeout; the default loop also redraws once per poll.
+const INPUT_POLL: Duration = Duration::from_millis(50);
+/// Input poll timeout in low-latency mode, which redraws separately.
+const LOW_LATENCY_POLL: Duration = Duration::from_millis(4);
+/// Low-latency mode redraws at most this often while data streams in...
+const LOW_LATENCY_FRAME: Duration = Duration::from_millis(16);
+/// ...and at least this often when idle, to keep clocks and timers current.
+const IDLE_REDRAW: Duration = Duration::from_millis(250);
 
-#[derive(Debug)]
-struct RecordingStats {
-    lines_written: u64,
-    frames_logged: u64,
+/// Time from an event (a key press, or live data arriving) to the end of
+/// the redraw showing it.
+#[derive(Debug, Default)]
+struct RedrawLatency {
+    samples: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl RedrawLatency {
+    fn record(&mut self, latency: Duration) {
+        self.samples += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    fn mean(&self) -> Duration {
+        self.total.checked_div(self.samples).unwrap_or_default()
+    }
 }
 
 /// Heatmap widget that renders a 2D grid of values with color-coded cells.
 
 
+/// Time range of a loaded capture marked with `<` and `>`, in plot seconds.
+#[derive(Debug, Clone)]
+struct Region {
+    capture: String,
+    start: f64,
+    end: Option<f64>,
+}
+
+impl Region {
+    /// `(from, to)` in order; just the start until the end is marked.
+    fn bounds(&self) -> (f64, f64) {
+        let end = self.end.unwrap_or(self.start);
+        (self.start.min(end), self.start.max(end))
+    }
+}
+
+/// The second board's worker when recording in dual-board mode.
+#[derive(Debug)]
+struct SecondaryWorker {
+    port: String,
+    done_rx: Option<mpsc::Receiver<std::result::Result<RecordingStats, RecordError>>>,
+    stats_rx: Option<mpsc::Receiver<RecordingStats>>,
+    stats: RecordingStats,
+    result: Option<std::result::Result<RecordingStats, RecordError>>,
+}
+
 /// Which step of input / recording we are in.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Step {
@@ -45,6 +143,57 @@ enum Step {
     Finished,
 }
 
+/// Open serial port picker (`i`): the ports found when it was opened and the
+/// highlighted row. Row 0 is "Auto-detect"; row `n` is `ports[n - 1]`.
+#[derive(Debug)]
+struct PortPicker {
+    ports: Vec<esp_port::PortInfo>,
+    selected: usize,
+}
+
+/// The plotted series and its overlays in view, in plot units, as drawn by
+/// both the full-screen and the dashboard chart.
+struct PlotSeries {
+    visible: Vec<(f64, f64)>,
+    compare: Vec<(f64, f64)>,
+    shading: Vec<(f64, f64)>,
+    markers: Vec<(String, Vec<(f64, f64)>)>,
+    /// Single point marking the newest sample.
+    latest: Vec<(f64, f64)>,
+    last_label: String,
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+/// Order of the Saved Files list, cycled with `o`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum FileSort {
+    /// Most recently modified first, so a new capture is on top.
+    #[default]
+    Newest,
+    Name,
+    /// Largest first.
+    Size,
+}
+
+impl FileSort {
+    fn next(self) -> Self {
+        match self {
+            FileSort::Newest => FileSort::Name,
+            FileSort::Name => FileSort::Size,
+            FileSort::Size => FileSort::Newest,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            FileSort::Newest => "newest",
+            FileSort::Name => "name",
+            FileSort::Size => "size",
+        }
+    }
+}
+
 /// The main application which holds the state and logic of the application.
 #[derive(Debug)]
 pub struct App {
@@ -57,28 +206,203 @@ pub struct App {
     wifi_mode: WifiMode,
     ssid: String,
     password: String,
-    worker_done_rx: Option<mpsc::Receiver<std::result::Result<(), String>>>,
+    csi_config: CsiConfig,
+    /// Set to stop the running recording(s) early.
+    recording_cancel: Option<Arc<AtomicBool>>,
+    worker_done_rx: Option<mpsc::Receiver<std::result::Result<RecordingStats, RecordError>>>,
+    /// Primary worker result held back until the secondary board finishes.
+    pending_result: Option<std::result::Result<RecordingStats, RecordError>>,
+    /// Record from the first two detected ports at once (`<name>_a` / `<name>_b`).
+    dual_board: bool,
+    secondary: Option<SecondaryWorker>,
+    /// Base name of the primary capture being recorded.
+    recording_name: String,
+    /// Trigger threshold as typed; empty disables the trigger.
+    trigger_threshold_input: String,
+    trigger_subcarrier_input: String,
+    /// Keep every Nth packet while recording.
+    decimation_input: String,
+    /// Inclusive subcarrier range shown in the heatmap; empty ends are open.
+    heatmap_lo_input: String,
+    heatmap_hi_input: String,
+    /// Cursor of each Options text field as a char index; `None` keeps it
+    /// after the last character.
+    field_cursors: [Option<usize>; CONTROLS_LEN],
+    /// Options field whose value was last rejected, drawn in the alert
+    /// colour until it becomes valid.
+    invalid_field: Option<usize>,
+    /// Index into `EVENT_LABELS` used for the next annotation.
+    event_label_idx: usize,
+    /// Annotations shown as vertical markers on the chart.
+    events: Vec<events::Event>,
+    /// Up/Down wrap around at the ends of the focused list.
+    wrap_navigation: bool,
+    /// Order of the Saved Files list.
+    file_sort: FileSort,
+    /// Index of the first Saved Files entry shown in the pane.
+    files_scroll: usize,
+    /// Capture that failed to load because `subcarrier` was out of range,
+    /// with the highest valid subcarrier `k` would clamp to.
+    clamp_offer: Option<(String, usize)>,
+    stats_rx: Option<mpsc::Receiver<RecordingStats>>,
+    /// Parser warnings from the recorder(s), shown in the status log.
+    log_rx: Option<mpsc::Receiver<String>>,
+    recording_stats: RecordingStats,
     plot_points: Vec<(f64, f64)>,
+    /// Second capture overlaid on the plot, loaded with the same subcarrier,
+    /// view and metric as the main one.
+    compare_name: Option<String>,
+    compare_points: Vec<(f64, f64)>,
+    /// Zoomed/panned x-axis window; `None` shows the full data extent.
+    view_bounds: Option<(f64, f64)>,
+    /// Region of interest marked on the loaded capture, exported with Ctrl+E.
+    region: Option<Region>,
+    /// Plot/heatmap loaded captures as per-subcarrier z-scores.
+    zscore: bool,
+    /// On load, plot the subcarrier whose amplitude varies the most.
+    auto_subcarrier: bool,
+    /// Show the heatmap minus each subcarrier's mean, on a diverging scale.
+    background_subtract: bool,
     nav_selected: usize,
     nav_item_selected: usize,
     subcarrier: usize,
     esp_port: Option<String>,
     plot_rx: Option<mpsc::Receiver<(f64, f64)>>,
-    heatmap_rx: Option<mpsc::Receiver<Vec<Vec<u8>>>>, // Add this
+    heatmap_rx: Option<mpsc::Receiver<Vec<Vec<f32>>>>, // Add this
+    /// Newest live heatmap rows, oldest first; scrolls as batches arrive.
+    live_heatmap: VecDeque<Vec<f32>>,
     recording_start: Option<SystemTime>,
+    /// Stay in the full-screen plot when a recording finishes.
+    keep_full_screen: bool,
+    /// Poll input on a short timeout and redraw on key presses and incoming
+    /// data instead of once per 50 ms poll.
+    low_latency: bool,
+    redraw_latency: RedrawLatency,
+    /// Time from live data arriving to the redraw showing it.
+    data_latency: RedrawLatency,
+    /// Colours used across the UI, cycled with `t`.
+    theme: Theme,
+    /// Save the raw serial stream next to each capture.
+    raw_log: bool,
+    /// How decimal CSI arrays from the firmware are read.
+    value_format: ValueFormat,
+    /// Add a leading host wall-clock column to recorded CSVs.
+    host_time: bool,
+    /// File format new captures are written in.
+    capture_format: CaptureFormat,
+    /// Stop a recording early after this long without a packet.
+    idle_timeout: Option<Duration>,
+    /// Packets discarded at the start of each recording (`--warmup`).
+    warmup_packets: u64,
+    /// Serial control lines on opening the port; see `--no-dtr`/`--no-rts`.
+    assert_dtr: bool,
+    release_rts: bool,
+    /// Record from the serial port or from UDP datagrams (`--udp`).
+    source: InputSource,
+    /// Reboot the board after each recording (`--restart-after`).
+    restart_after: bool,
+    /// Subcarrier probe running on the board (on connect, or Ctrl+D).
+    probe_rx: Option<mpsc::Receiver<std::result::Result<Probe, RecordError>>>,
+    /// Subcarriers per frame found by the last probe; bounds the subcarrier
+    /// selectors.
+    detected_subcarriers: Option<usize>,
+    /// Ctrl+S starts this schedule of recordings instead of one (`--schedule`).
+    schedule: Option<Schedule>,
+    /// The schedule being run, if any.
+    schedule_run: Option<ScheduleProgress>,
+    /// When the scheduled recording in progress started.
+    schedule_run_started: Option<SystemTime>,
+    /// Requested duration of the current recording.
+    recording_secs: u64,
+    /// The current recording waits for a trigger, so its end isn't known.
+    recording_armed: bool,
     auto_switched: bool,
     full_screen_plot: bool,
+    /// Live plot frozen: incoming points are drained and discarded.
+    plot_paused: bool,
+    /// Quantity plotted for the selected subcarrier, cycled with `v`.
+    view: Quantity,
+    /// Plot amplitude on a log10 y-axis.
+    log_y: bool,
+    /// RX antenna shown in the plot/heatmap; `None` shows every frame.
+    antenna_filter: Option<u8>,
     heatmap_data: Heatmap,
+    /// Mean power per subcarrier over the loaded capture, one row per
+    /// subcarrier so it renders as a vertical strip.
+    spectrum_data: Heatmap,
+    /// Terminal columns per heatmap cell (1 or 2), toggled with `h`.
+    heatmap_cell_width: u16,
+    /// Scrolling FFT of the live amplitude feed.
+    spectrogram: Spectrogram,
+    /// Show the spectrogram instead of the heatmap in the bottom panel.
+    show_spectrogram: bool,
+    /// Show the value histogram of the plotted series instead of the chart.
+    show_histogram: bool,
+    histogram_bins: usize,
+    /// How amplitude is derived from I/Q in the plot, heatmap and frame view.
+    metric: AmplitudeMetric,
+    /// Per-subcarrier amplitude correction applied before `metric`.
+    calibration: Option<Calibration>,
+    /// Per-frame subcarrier amplitudes of the loaded capture.
+    frame_amplitudes: Vec<Vec<f32>>,
+    /// Show amplitude vs subcarrier for `frame_index` instead of the chart.
+    show_frame: bool,
+    frame_index: usize,
+    /// Secondary messages that shouldn't overwrite the primary `status`.
+    status_log: Vec<String>,
+    save_dir: String,
+    rerun_sink: RerunSink,
+    rerun_entities: parse_data::RerunEntities,
+    /// Log Rerun tensors only on every Nth frame.
+    rerun_tensor_every: u64,
+    /// Minimum time between live plot points sent by the recorder.
+    plot_interval: Duration,
+    /// Minimum time between live heatmap snapshots sent by the recorder.
+    heatmap_interval: Duration,
+    /// Packets per second assumed by the pre-flight output size estimate.
+    expected_rate_hz: u32,
+    /// When the serial ports were last scanned.
+    last_port_scan: Option<Instant>,
+    /// Port picker, while it is open.
+    port_picker: Option<PortPicker>,
+    /// `esp_port` was chosen in the picker; automatic rescans leave it alone.
+    port_pinned: bool,
+    /// Text typed into the "jump to time" prompt while it is open.
+    jump_input: Option<String>,
+    /// Name typed at the "Save preset as" prompt (`u`), while it is open.
+    preset_input: Option<String>,
+    /// Saved preset names, loaded with `1`–`9` in this order.
+    presets: Vec<String>,
+    /// Subcarriers bookmarked with `*`, cycled with `.`.
+    bookmarks: BTreeSet<usize>,
+    /// Duration whose large estimated output the user was warned about;
+    /// starting again with the same duration goes ahead.
+    size_confirm: Option<u64>,
 }
 
+
 impl Default for App {
     fn default() -> Self {
+        Self::with_save_dir(DEFAULT_SAVE_DIR)
+    }
+}
+
+impl App {
+    /// Construct a new instance of [`App`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct an [`App`] that saves and lists captures in `save_dir`
+    /// instead of the default directory. The directory is created if missing.
+    pub fn with_save_dir(save_dir: impl Into<String>) -> Self {
+        let save_dir = save_dir.into();
         let detected_port = esp_port::find_esp_port();
-        let status = match &detected_port {
-            Some(p) => format!("Detected port: {p}. Type filename (without extension) and press Enter."),
-            None => "No ESP port detected. Type filename anyway, then duration.".to_string(),
+        let status = match (csv_utils::ensure_writable_dir(Path::new(&save_dir)), &detected_port) {
+            (Err(msg), _) => format!("{msg}. Recording is disabled until it is writable."),
+            (Ok(()), Some(p)) => format!("Detected port: {p}. Type filename (without extension) and press Enter."),
+            (Ok(()), None) => "No ESP port detected. Type filename anyway, then duration.".to_string(),
         };
-        let _ = fs::create_dir_all(SAVE_DIR);
         Self {
             running: false,
             step: Step::EnterFilename,
@@ -86,99 +410,248 @@ impl Default for App {
             filename: String::new(),
             duration_input: String::new(),
             status,
+            recording_cancel: None,
             worker_done_rx: None,
+            pending_result: None,
+            dual_board: false,
+            secondary: None,
+            recording_name: String::new(),
+            trigger_threshold_input: String::new(),
+            trigger_subcarrier_input: "20".to_string(),
+            decimation_input: "1".to_string(),
+            heatmap_lo_input: String::new(),
+            heatmap_hi_input: String::new(),
+            field_cursors: [None; CONTROLS_LEN],
+            invalid_field: None,
+            event_label_idx: 0,
+            events: Vec::new(),
+            wrap_navigation: false,
+            file_sort: FileSort::default(),
+            files_scroll: 0,
+            clamp_offer: None,
+            stats_rx: None,
+            log_rx: None,
+            recording_stats: RecordingStats::default(),
             plot_points: Vec::new(),
+            compare_name: None,
+            compare_points: Vec::new(),
+            view_bounds: None,
+            region: None,
+            zscore: false,
+            auto_subcarrier: false,
+            background_subtract: false,
             subcarrier: 20,
             wifi_mode: WifiMode::Sniffer,
             ssid: String::new(),
             password: String::new(),
+            csi_config: CsiConfig::default(),
             esp_port: esp_port::find_esp_port(),
             plot_rx: None,
             heatmap_rx: None, // Add this
+            live_heatmap: VecDeque::new(),
             nav_selected: 0,
             nav_item_selected: 0,
             recording_start: None,
+            keep_full_screen: false,
+            low_latency: false,
+            redraw_latency: RedrawLatency::default(),
+            data_latency: RedrawLatency::default(),
+            theme: Theme::DEFAULT,
+            raw_log: false,
+            value_format: ValueFormat::default(),
+            host_time: false,
+            capture_format: CaptureFormat::default(),
+            idle_timeout: None,
+            warmup_packets: 0,
+            assert_dtr: true,
+            release_rts: false,
+            source: InputSource::default(),
+            restart_after: false,
+            probe_rx: None,
+            detected_subcarriers: None,
+            schedule: None,
+            schedule_run: None,
+            schedule_run_started: None,
+            recording_secs: 0,
+            recording_armed: false,
             auto_switched: false,
             full_screen_plot: false,
-            heatmap_data: Heatmap { values: vec![] },
+            plot_paused: false,
+            view: Quantity::default(),
+            log_y: false,
+            antenna_filter: None,
+            heatmap_data: Heatmap::new(vec![]),
+            spectrum_data: Heatmap::new(vec![]),
+            heatmap_cell_width: 2,
+            spectrogram: Spectrogram::default(),
+            show_spectrogram: false,
+            show_histogram: false,
+            histogram_bins: DEFAULT_HISTOGRAM_BINS,
+            metric: AmplitudeMetric::default(),
+            calibration: None,
+            frame_amplitudes: Vec::new(),
+            show_frame: false,
+            frame_index: 0,
+            status_log: Vec::new(),
+            save_dir,
+            rerun_sink: RerunSink::default(),
+            rerun_entities: parse_data::RerunEntities::ALL,
+            rerun_tensor_every: 1,
+            plot_interval: parse_data::DEFAULT_PLOT_INTERVAL,
+            heatmap_interval: parse_data::DEFAULT_HEATMAP_INTERVAL,
+            expected_rate_hz: parse_data::DEFAULT_EXPECTED_RATE_HZ,
+            size_confirm: None,
+            jump_input: None,
+            preset_input: None,
+            presets: Vec::new(),
+            bookmarks: BTreeSet::new(),
+            last_port_scan: None,
+            port_picker: None,
+            port_pinned: false,
         }
     }
-}
 
-impl App {
-    /// Construct a new instance of [`App`].
-    pub fn new() -> Self {
-        Self::default()
+    /// Construct an [`App`] configured from command-line arguments.
+    pub fn from_args(args: CliArgs) -> Self {
+        let mut app = Self::with_save_dir(args.data_dir);
+        app.rerun_sink = args.rerun_sink;
+        app.rerun_entities = args.rerun_entities;
+        app.rerun_tensor_every = args.rerun_tensor_every;
+        app.plot_interval = args.plot_interval;
+        app.heatmap_interval = args.heatmap_interval;
+        app.expected_rate_hz = args.expected_rate_hz;
+        app.keep_full_screen = args.keep_full_screen;
+        app.low_latency = args.low_latency;
+        app.theme = args.theme;
+        app.raw_log = args.raw_log;
+        app.value_format = args.value_format;
+        app.host_time = args.host_time;
+        app.capture_format = args.capture_format;
+        app.idle_timeout = args.idle_timeout;
+        app.warmup_packets = args.warmup_packets;
+        app.assert_dtr = args.assert_dtr;
+        app.release_rts = args.release_rts;
+        app.source = args.source;
+        app.restart_after = args.restart_after;
+        app.schedule = args.schedule;
+        app.calibration = args.calibration;
+        app.presets = presets::list_presets(&app.save_dir);
+        app.bookmarks = bookmarks::load_bookmarks(&app.save_dir);
+        app
     }
 
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
+        let mut last_draw: Option<Instant> = None;
+        // When the oldest input not yet on screen was read.
+        let mut input_at: Option<Instant> = None;
+        // And when the oldest live data not yet on screen was drained.
+        let mut data_at: Option<Instant> = None;
+        // A board plugged in before start counts as connected now.
+        if self.esp_port.is_some() {
+            self.probe_on_connect();
+        }
         while self.running {
-            self.refresh_esp();
-            self.poll_plot_data();
-            self.poll_heatmap_data(); // Add this
+            self.refresh_esp(false);
+            let plot_data = self.poll_plot_data();
+            let heatmap_data = self.poll_heatmap_data();
+            self.poll_recording_stats();
             // Check whether we should auto-switch the UI into the full-screen
             // live-plot mode after a short delay while recording.
             self.check_auto_switch();
-            terminal.draw(|frame| self.render(frame))?;
-            self.handle_crossterm_events()?;
+            // Undrawn data stays pending until a draw shows it, even when
+            // the next polls bring none.
+            if plot_data || heatmap_data {
+                data_at.get_or_insert_with(Instant::now);
+            }
+            // The default loop draws once per poll.
+            let draw = !self.low_latency
+                || low_latency_draw_due(input_at.is_some(), data_at.is_some(), last_draw.map(|t| t.elapsed()));
+            if draw {
+                terminal.draw(|frame| self.render(frame))?;
+                last_draw = Some(Instant::now());
+                if let Some(at) = input_at.take() {
+                    let latency = at.elapsed();
+                    tracing::debug!(latency_us = latency.as_micros() as u64, "input-to-redraw");
+                    self.redraw_latency.record(latency);
+                }
+                if let Some(at) = data_at.take() {
+                    let latency = at.elapsed();
+                    tracing::trace!(latency_us = latency.as_micros() as u64, "data-to-redraw");
+                    self.data_latency.record(latency);
+                }
+            }
+            let timeout = if self.low_latency { LOW_LATENCY_POLL } else { INPUT_POLL };
+            if let Some(at) = self.handle_crossterm_events(timeout)? {
+                input_at.get_or_insert(at);
+            }
             self.check_worker();
+            self.check_probe();
+            self.check_schedule();
+        }
+        for (latency, what) in [(&self.redraw_latency, "input"), (&self.data_latency, "data")] {
+            tracing::info!(
+                low_latency = self.low_latency,
+                samples = latency.samples,
+                mean_us = latency.mean().as_micros() as u64,
+                max_us = latency.max.as_micros() as u64,
+                "{}-to-redraw latency",
+                what
+            );
         }
         Ok(())
     }
 
     /// Renders the user interface.
     fn render(&mut self, frame: &mut Frame) {
-        let area = frame.area();
+        // Status bar along the bottom in every view.
+        let screen = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+            .split(frame.area());
+        frame.render_widget(Paragraph::new(self.status_bar()), screen[1]);
+        let area = screen[0];
         // If we've switched to a dedicated full-screen plot view, render
         // only the chart to occupy the whole terminal area.
         if self.full_screen_plot {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+            frame.render_widget(Paragraph::new(self.live_header()), split[0]);
+            let fs_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Min(0), Constraint::Length(PLOT_STATS_WIDTH)])
+                .split(split[1]);
+            self.render_plot_stats(frame, fs_layout[1]);
+            let mut area = fs_layout[0];
+            if !self.spectrogram.is_empty() {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(vec![Constraint::Percentage(65), Constraint::Percentage(35)])
+                    .split(area);
+                area = split[0];
+                self.render_spectrogram(frame, split[1]);
+            }
             if !self.plot_points.is_empty() {
-                let (t_min, t_max) = self
-                    .plot_points
-                    .iter()
-                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), (t, _)| {
-                        (mn.min(*t), mx.max(*t))
-                    });
-                let (_, a_max) = self
-                    .plot_points
-                    .iter()
-                    .fold((0.0f64, 0.0f64), |(mn, mx), (_, a)| {
-                        (mn.min(*a), mx.max(*a))
-                    });
-                let dataset = Dataset::default()
-                    .name(format!("Subcarrier {}", self.subcarrier))
-                    .marker(ratatui::symbols::Marker::Braille)
-                    .graph_type(GraphType::Line)
-                    .style(Color::Cyan)
-                    .data(&self.plot_points);
-                let last_label = self.format_last_label().unwrap_or_default();
-
-                let chart = Chart::new(vec![dataset])
-                    .block(Block::bordered().title(format!(
-                        "Live Amplitude{}",
-                        if last_label.is_empty() {
-                            "".to_string()
-                        } else {
-                            format!(" — {}", last_label)
-                        }
-                    )))
-                    .x_axis(
-                        Axis::default()
-                            .title("time (s)")
-                            .bounds([t_min, t_max.max(t_min + 0.1)]),
-                    )
-                    .y_axis(
-                        Axis::default()
-                            .title("amplitude")
-                            .bounds([0.0, a_max.max(1.0)]),
-                    );
+                let series = self.plot_series();
+                let title = format!(
+                    "Live {}{}{}",
+                    self.view.label(),
+                    if series.last_label.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!(" — {}", series.last_label)
+                    },
+                    if self.plot_paused { " [PAUSED]" } else { "" }
+                );
+                let chart = self.plot_chart(&series, title);
                 frame.render_widget(chart, area);
             } else {
                 frame.render_widget(
                     Paragraph::new("Waiting for live data...")
-                        .block(Block::bordered().title("Live Amplitude")),
+                        .block(Block::bordered().title(format!("Live {}", self.view.label()))),
                     area,
                 );
             }
@@ -196,7 +669,11 @@ impl App {
 
         let body_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Percentage(10), Constraint::Percentage(90)])
+            .constraints(vec![
+                Constraint::Length(5),
+                Constraint::Min(0),
+                Constraint::Length(STATUS_LOG_HEIGHT),
+            ])
             .split(layout[1]);
 
         // --- Left nav: top (controls) ---
@@ -213,171 +690,512 @@ impl App {
             format!("Password: {}", "*".repeat(self.password.len())),
             format!("Duration (s): {}", self.duration_input),
             format!("Filename: {}", self.filename),
+            format!(
+                "Trigger amp: {}",
+                if self.trigger_threshold_input.is_empty() { "off" } else { &self.trigger_threshold_input }
+            ),
+            match self.detected_subcarriers {
+                Some(n) => format!("Trigger SC: {} (0-{})", self.trigger_subcarrier_input, n.saturating_sub(1)),
+                None => format!("Trigger SC: {}", self.trigger_subcarrier_input),
+            },
+            format!("Keep every: {}", self.decimation_input),
+            format!(
+                "Heatmap SC from: {}",
+                if self.heatmap_lo_input.is_empty() { "first" } else { &self.heatmap_lo_input }
+            ),
+            format!(
+                "Heatmap SC to: {}",
+                if self.heatmap_hi_input.is_empty() { "last" } else { &self.heatmap_hi_input }
+            ),
         ];
 
         let mut nav_top = Text::default();
         for (i, line) in controls.iter().enumerate() {
+            let invalid = self.invalid_field == Some(i);
             if self.nav_selected == 0 && self.nav_item_selected == i {
-                nav_top.extend([Line::from(Span::styled(
-                    line.clone(),
-                    Style::default().fg(Color::Cyan),
-                ))]);
+                let focused = if invalid { self.theme.focused().bg(self.theme.alert) } else { self.theme.focused() };
+                let mut spans = vec![Span::styled("> ", focused)];
+                // Text fields: show the raw value with the cursor where typed
+                // characters will go.
+                let value = self.text_field(i).map(|v| {
+                    if i == 3 { "*".repeat(v.chars().count()) } else { v.clone() }
+                });
+                match value.filter(|_| self.editing_text_field()) {
+                    Some(value) => {
+                        let label = line.split_once(": ").map_or(line.as_str(), |(label, _)| label);
+                        let chars: Vec<char> = value.chars().collect();
+                        let cursor = self.field_cursors[i].map_or(chars.len(), |c| c.min(chars.len()));
+                        let before: String = chars[..cursor].iter().collect();
+                        let at = chars.get(cursor).map_or(" ".to_string(), |c| c.to_string());
+                        let after: String = chars.get(cursor + 1..).unwrap_or_default().iter().collect();
+                        spans.push(Span::styled(format!("{}: {}", label, before), focused));
+                        spans.push(Span::styled(at, Style::default().add_modifier(Modifier::REVERSED)));
+                        spans.push(Span::styled(after, focused));
+                    }
+                    None => spans.push(Span::styled(line.clone(), focused)),
+                }
+                nav_top.extend([Line::from(spans)]);
             } else {
-                nav_top.extend([Line::from(Span::styled(
-                    line.clone(),
-                    Style::default().fg(Color::White),
-                ))]);
+                let color = if invalid { self.theme.alert } else { self.theme.text };
+                nav_top.extend([Line::from(Span::styled(format!("  {}", line), Style::default().fg(color)))]);
             }
         }
 
+        // Quick-select numbers for the first nine presets.
+        let mut options_title = "Options".to_string();
+        if !self.presets.is_empty() {
+            options_title.push_str(" | presets:");
+            for (i, name) in self.presets.iter().take(9).enumerate() {
+                options_title.push_str(&format!(" {}:{}", i + 1, name));
+            }
+        }
         let options_block = if self.nav_selected == 0 {
             Block::bordered()
-                .title("Options")
-                .style(Style::default().fg(Color::Cyan))
+                .title(options_title)
+                .style(Style::default().fg(self.theme.accent))
         } else {
-            Block::bordered().title("Options")
+            Block::bordered().title(options_title)
         };
 
         frame.render_widget(Paragraph::new(nav_top).block(options_block), nav_layout[0]);
 
         // --- Left nav: bottom (saved files list) ---
         let mut files_text = Text::default();
-        files_text.extend([Line::from("Files in saved_data:")]);
-        let mut files_vec = Self::list_saved_files();
-        files_vec.sort();
+        files_text.extend([Line::from(format!("Files in {}:", self.save_dir))]);
+        let files_vec = self.list_saved_files();
         if files_vec.is_empty() {
             files_text.extend([Line::from(Span::styled(
-                "<no saved .csv/.rrd files>".to_string(),
-                Style::default().fg(Color::White),
+                "<no saved .csv/.parquet/.rrd files>".to_string(),
+                Style::default().fg(self.theme.text),
             ))]);
         } else {
-            for (i, name) in files_vec.iter().enumerate() {
+            // Inside the borders, below the "Files in" line; the last row is
+            // kept for the overflow indicator when the list doesn't fit.
+            let mut rows = nav_layout[1].height.saturating_sub(3) as usize;
+            if files_vec.len() > rows {
+                rows = rows.saturating_sub(1).max(1);
+            }
+            let selected = if self.nav_selected == 1 { self.nav_item_selected } else { self.files_scroll };
+            self.files_scroll = scroll_to_show(self.files_scroll, selected, rows, files_vec.len());
+            let end = (self.files_scroll + rows).min(files_vec.len());
+            for (i, name) in files_vec.iter().enumerate().take(end).skip(self.files_scroll) {
                 if self.nav_selected == 1 && self.nav_item_selected == i {
                     files_text.extend([Line::from(Span::styled(
-                        name.clone(),
-                        Style::default().fg(Color::Cyan),
+                        format!("> {}", name),
+                        self.theme.focused(),
                     ))]);
                 } else {
                     files_text.extend([Line::from(Span::styled(
-                        name.clone(),
-                        Style::default().fg(Color::White),
+                        format!("  {}", name),
+                        Style::default().fg(self.theme.text),
                     ))]);
                 }
             }
+            let (above, below) = (self.files_scroll, files_vec.len() - end);
+            let hidden = match (above, below) {
+                (0, 0) => None,
+                (0, below) => Some(format!("({} more)", below)),
+                (above, 0) => Some(format!("({} above)", above)),
+                (above, below) => Some(format!("({} above, {} more)", above, below)),
+            };
+            if let Some(hidden) = hidden {
+                files_text.extend([Line::from(Span::styled(
+                    format!("  {}", hidden),
+                    Style::default().fg(self.theme.muted),
+                ))]);
+            }
         }
 
+        let files_title = format!("Saved Files (by {})", self.file_sort.label());
         let files_block = if self.nav_selected == 1 {
             Block::bordered()
-                .title("Saved Files")
-                .style(Style::default().fg(Color::Cyan))
+                .title(files_title)
+                .style(Style::default().fg(self.theme.accent))
         } else {
-            Block::bordered().title("Saved Files")
+            Block::bordered().title(files_title)
         };
 
         frame.render_widget(Paragraph::new(files_text).block(files_block), nav_layout[1]);
         let mut status_text = Text::default();
         let port_line = match &self.esp_port {
+            _ if self.source != InputSource::Serial => format!("Input: {}", self.source),
+            Some(p) if self.port_pinned => format!("Port: {p} (chosen; i to change)"),
             Some(p) => format!("Detected port: {p}"),
             None => "Detected port: <none>".to_string(),
         };
         status_text.extend([Line::from(port_line)]);
+        if let Some(sec) = &self.secondary {
+            let sec_line = match &sec.result {
+                None => format!(
+                    "Board B ({}): {} packets, ~{} dropped",
+                    sec.port, sec.stats.frames_logged, sec.stats.dropped_packets
+                ),
+                Some(Ok(stats)) => format!("Board B ({}): done, {} packets", sec.port, stats.frames_logged),
+                Some(Err(err)) => format!("Board B ({}): failed: {}", sec.port, err),
+            };
+            status_text.extend([Line::from(sec_line)]);
+        } else if self.dual_board {
+            status_text.extend([Line::from("Dual-board mode: on")]);
+        }
+        let prompt = match (&self.jump_input, &self.preset_input) {
+            (Some(input), _) => Some(format!("Jump to (s or HH:MM:SS): {}", input)),
+            (None, Some(name)) => Some(format!("Save preset as: {}", name)),
+            (None, None) => None,
+        };
+        match prompt {
+            Some(prompt) => status_text.extend([Line::from(vec![
+                Span::styled(prompt, self.theme.focused()),
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ])]),
+            None => status_text.extend([Line::from(self.status.clone())]),
+        }
         frame.render_widget(
             Paragraph::new(status_text).block(Block::bordered().title("Connection Status")),
             body_layout[0],
         );
 
+        // --- Status log (most recent entries at the bottom) ---
+        let visible_log = (STATUS_LOG_HEIGHT as usize).saturating_sub(2);
+        let log_start = self.status_log.len().saturating_sub(visible_log);
+        let log_text: Vec<Line> = self.status_log[log_start..]
+            .iter()
+            .map(|entry| Line::from(entry.clone()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(log_text).block(Block::bordered().title("Log")),
+            body_layout[2],
+        );
+
         // --- Body bottom: split into wireframe (top) and heatmap (bottom) ---
         let plot_and_heat = Layout::default()
             .direction(Direction::Vertical)
             .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(body_layout[1]);
 
-        // --- Wireframe plot (top half) ---
-        if !self.plot_points.is_empty() {
-            let (t_min, t_max) = self
-                .plot_points
-                .iter()
-                .fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), (t, _)| {
-                    (mn.min(*t), mx.max(*t))
-                });
-            let (_, a_max) = self
-                .plot_points
-                .iter()
-                .fold((0.0f64, 0.0f64), |(mn, mx), (_, a)| {
-                    (mn.min(*a), mx.max(*a))
-                });
-            let (_, a_max) = self
-                .plot_points
-                .iter()
-                .fold((0.0f64, 0.0f64), |(mn, mx), (_, a)| {
-                    (mn.min(*a as f64), mx.max(*a as f64))
-                });
-            let dataset = Dataset::default()
-                .name(format!("Subcarrier {}", self.subcarrier))
-                .marker(ratatui::symbols::Marker::Braille)
-                .graph_type(GraphType::Line)
-                .style(Color::Cyan)
-                .data(&self.plot_points);
-            let last_label = self.format_last_label().unwrap_or_default();
-            let chart = Chart::new(vec![dataset])
-                .block(Block::bordered().title(if last_label.is_empty() {
-                    "Amplitude over time".to_string()
-                } else {
-                    format!("Amplitude over time — {}", last_label)
-                }))
-                .x_axis(
-                    Axis::default()
-                        .title("time (s)")
-                        .bounds([t_min, t_max.max(t_min + 0.1)]),
-                )
-                .y_axis(
-                    Axis::default()
-                        .title("amplitude")
-                        .bounds([0.0, a_max.max(1.0)]),
-                );
-            frame.render_widget(chart, plot_and_heat[0]);
+        // --- Wireframe plot (top half) with stats box on the right ---
+        let plot_row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(PLOT_STATS_WIDTH)])
+            .split(plot_and_heat[0]);
+        self.render_plot_stats(frame, plot_row[1]);
+        if self.show_frame {
+            self.render_frame_response(frame, plot_row[0]);
+        } else if self.show_histogram {
+            self.render_histogram(frame, plot_row[0]);
+        } else if !self.plot_points.is_empty() {
+            let series = self.plot_series();
+            let title = if series.last_label.is_empty() {
+                format!("{} over time", self.view.label())
+            } else {
+                format!("{} over time — {}", self.view.label(), series.last_label)
+            };
+            let chart = self.plot_chart(&series, title);
+            frame.render_widget(chart, plot_row[0]);
         } else {
             let mut placeholder = Text::default();
             placeholder.extend([Line::from("Plot area (no data)")]);
             placeholder.extend([Line::from("")]);
             placeholder.extend([Line::from("Recorded and loaded files will appear here.")]);
             frame.render_widget(
-                Paragraph::new(placeholder).block(Block::bordered().title("Amplitude over time")),
-                plot_and_heat[0],
+                Paragraph::new(placeholder)
+                    .block(Block::bordered().title(format!("{} over time", self.view.label()))),
+                plot_row[0],
             );
         }
 
-        // --- Heatmap (bottom half) ---
-        if !self.heatmap_data.values.is_empty() {
+        // --- Heatmap (bottom half) with the mean-spectrum strip below, one
+        // cell per subcarrier in line with the heatmap's columns ---
+        let heat_row = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(SPECTRUM_HEIGHT)])
+            .split(plot_and_heat[1]);
+        if self.show_spectrogram {
+            self.render_spectrogram(frame, heat_row[0]);
+        } else if !self.heatmap_data.values.is_empty() {
             // Render the block border
-            let heatmap_block = Block::bordered().title("Heatmap");
-            let inner_area = heatmap_block.inner(plot_and_heat[1]);
-            heatmap_block.render(plot_and_heat[1], frame.buffer_mut());
+            let title = if self.background_subtract { "Heatmap (minus mean)" } else { "Heatmap" };
+            let heatmap_block = Block::bordered().title(title);
+            let inner_area = heatmap_block.inner(heat_row[0]);
+            heatmap_block.render(heat_row[0], frame.buffer_mut());
             // Render the heatmap inside the block
+            self.heatmap_data.colormap = if self.background_subtract {
+                Colormap::Diverging
+            } else {
+                self.theme.colormap
+            };
             frame.render_widget(&self.heatmap_data, inner_area);
         } else {
             frame.render_widget(
                 Paragraph::new("Heatmap (no data)").block(Block::bordered().title("Heatmap")),
-                plot_and_heat[1],
+                heat_row[0],
+            );
+        }
+        let spectrum_block = Block::bordered().title("Mean");
+        let inner_area = spectrum_block.inner(heat_row[1]);
+        spectrum_block.render(heat_row[1], frame.buffer_mut());
+        self.spectrum_data.colormap = self.theme.colormap;
+        frame.render_widget(&self.spectrum_data, inner_area);
+
+        if let Some(picker) = &self.port_picker {
+            self.render_port_picker(frame, picker, area);
+        }
+    }
+
+    /// The port picker as a box centred over the dashboard.
+    fn render_port_picker(&self, frame: &mut Frame, picker: &PortPicker, area: Rect) {
+        let rows = std::iter::once(("Auto-detect", "first ESP-looking port, rescanned every 500 ms"))
+            .chain(picker.ports.iter().map(|p| (p.name.as_str(), p.description.as_str())));
+        let lines: Vec<Line> = rows
+            .enumerate()
+            .map(|(i, (name, description))| {
+                let text = format!("{} {}  {}", if i == picker.selected { ">" } else { " " }, name, description);
+                if i == picker.selected {
+                    Line::from(Span::styled(text, self.theme.focused()))
+                } else {
+                    Line::from(Span::styled(text, Style::default().fg(self.theme.text)))
+                }
+            })
+            .collect();
+        let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4).max(30).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title("Serial port (Enter selects, Esc cancels)")
+                    .style(Style::default().fg(self.theme.accent)),
+            ),
+            popup,
+        );
+    }
+
+    /// One-line header for the full-screen view: while recording, a
+    /// blinking live dot, elapsed / total time, packets and port; after
+    /// that, how to get back to the dashboard.
+    fn live_header(&self) -> Line<'static> {
+        if self.step != Step::Recording {
+            return Line::from(Span::styled(
+                "Recording finished. Enter: back to the dashboard",
+                Style::default().fg(self.theme.muted),
+            ));
+        }
+        let elapsed = self
+            .recording_start
+            .and_then(|start| start.elapsed().ok())
+            .unwrap_or_default();
+        let dot = if (elapsed.as_millis() / 500).is_multiple_of(2) { "●" } else { " " };
+        let mmss = |secs: u64| format!("{:02}:{:02}", secs / 60, secs % 60);
+        let port = self.esp_port.as_deref().unwrap_or("-");
+        Line::from(vec![
+            Span::styled(
+                format!("{} LIVE ", dot),
+                Style::default().fg(self.theme.alert).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " {} / {}   {} packets   {}",
+                mmss(elapsed.as_secs()),
+                mmss(self.recording_secs),
+                self.recording_stats.frames_logged,
+                port
+            )),
+        ])
+    }
+
+    /// Bottom bar: wall-clock time and, while recording, elapsed and
+    /// remaining time with the expected end, and the packet count. A running
+    /// schedule shows its progress and when the next recording starts.
+    fn status_bar(&self) -> Line<'static> {
+        let now = Local::now();
+        let mut spans = vec![Span::styled(
+            format!(" {} ", now.format("%H:%M:%S")),
+            Style::default().fg(self.theme.on_accent).bg(self.theme.accent),
+        )];
+        let hhmmss = |secs: u64| format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+        let info = if self.step == Step::Recording {
+            let elapsed = self
+                .recording_start
+                .and_then(|start| start.elapsed().ok())
+                .unwrap_or_default()
+                .as_secs();
+            let remaining = if self.recording_armed {
+                format!(
+                    "armed, ends a set time after the trigger (gives up after {} min)",
+                    TRIGGER_TIMEOUT.as_secs() / 60
+                )
+            } else {
+                let left = self.recording_secs.saturating_sub(elapsed);
+                // Durations can be typed far past what a date can hold.
+                let ends = i64::try_from(left)
+                    .ok()
+                    .and_then(TimeDelta::try_seconds)
+                    .and_then(|left| now.checked_add_signed(left));
+                match ends {
+                    Some(ends) => format!("{} left, ends ~{}", hhmmss(left), ends.format("%H:%M:%S")),
+                    None => format!("{} left", hhmmss(left)),
+                }
+            };
+            let mut info = format!(
+                " REC {}   elapsed {}   {}   {} packets",
+                self.recording_name,
+                hhmmss(elapsed),
+                remaining,
+                self.recording_stats.frames_logged
+            );
+            if let Some(progress) = &self.schedule_run {
+                info.push_str(&format!(
+                    "   schedule {}/{} (q cancels)",
+                    progress.completed + 1,
+                    progress.schedule.count
+                ));
+            }
+            info
+        } else if let Some(progress) = &self.schedule_run {
+            let next: DateTime<Local> = progress.next_start.into();
+            format!(
+                " Schedule {}: {}/{} done, next at {} (in {})   q cancels",
+                progress.name,
+                progress.completed,
+                progress.schedule.count,
+                next.format("%H:%M:%S"),
+                hhmmss(progress.wait().as_secs())
+            )
+        } else if !self.plot_points.is_empty() && !self.filename.trim().is_empty() {
+            format!(" {}   {} points", self.filename.trim(), self.plot_points.len())
+        } else {
+            " Idle".to_string()
+        };
+        spans.push(Span::styled(info, Style::default().fg(self.theme.muted)));
+        Line::from(spans)
+    }
+
+    /// Min/max/mean of the visible plot window plus the latest RSSI.
+    fn render_plot_stats(&self, frame: &mut Frame, area: Rect) {
+        let (visible, _) = self.visible_plot_points();
+        let mut text = Text::default();
+        if visible.is_empty() {
+            text.extend([Line::from("min  -"), Line::from("max  -"), Line::from("mean -")]);
+        } else {
+            let (mn, mx, sum) = visible.iter().fold(
+                (f64::INFINITY, f64::NEG_INFINITY, 0.0f64),
+                |(mn, mx, sum), (_, a)| (mn.min(*a), mx.max(*a), sum + a),
             );
+            let mean = sum / visible.len() as f64;
+            text.extend([
+                Line::from(format!("min  {}", display(mn))),
+                Line::from(format!("max  {}", display(mx))),
+                Line::from(format!("mean {}", display(mean))),
+            ]);
+        }
+        text.extend([Line::from(match self.recording_stats.last_rssi {
+            Some(rssi) => format!("rssi {} dBm", rssi),
+            None => "rssi -".to_string(),
+        })]);
+        if !self.bookmarks.is_empty() {
+            let marks: Vec<String> = self.bookmarks.iter().map(|sc| sc.to_string()).collect();
+            text.extend([Line::from(Span::styled(
+                format!("bm {}", marks.join(",")),
+                Style::default().fg(self.theme.muted),
+            ))]);
         }
+        let marked = if self.bookmarks.contains(&self.subcarrier) { " *" } else { "" };
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(format!("SC {}{}", self.subcarrier, marked))),
+            area,
+        );
     }
 
     /// Reads the crossterm events and updates the state of [`App`].
-    fn handle_crossterm_events(&mut self) -> Result<()> {
-        if event::poll(Duration::from_millis(50))? {
-            match event::read()? {
+    ///
+    /// Waits up to `timeout` for the first event, then drains any queued
+    /// behind it. Returns when the first event was read, if any.
+    fn handle_crossterm_events(&mut self, timeout: Duration) -> Result<Option<Instant>> {
+        let mut read_at = None;
+        let mut timeout = timeout;
+        while event::poll(timeout)? {
+            let event = event::read()?;
+            read_at.get_or_insert_with(Instant::now);
+            match event {
                 Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
                 Event::Mouse(_) => {}
                 Event::Resize(_, _) => {}
                 _ => {}
             }
+            timeout = Duration::ZERO;
         }
-        Ok(())
+        Ok(read_at)
     }
 
     /// Handles the key events and updates the state of [`App`].
     fn on_key_event(&mut self, key: KeyEvent) {
+        tracing::trace!(code = ?key.code, modifiers = ?key.modifiers, "key");
+        // The jump prompt takes every key until it is confirmed or cancelled.
+        if let Some(input) = &mut self.jump_input {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() || c == ':' || c == '.' => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let input = self.jump_input.take().unwrap_or_default();
+                    self.jump_to(&input);
+                }
+                KeyCode::Esc => self.jump_input = None,
+                _ => {}
+            }
+            return;
+        }
+        // And the port picker.
+        if let Some(picker) = &mut self.port_picker {
+            match key.code {
+                KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+                KeyCode::Down => picker.selected = (picker.selected + 1).min(picker.ports.len()),
+                KeyCode::Enter => {
+                    let picker = self.port_picker.take().expect("picker is open");
+                    match picker.selected.checked_sub(1).and_then(|i| picker.ports.get(i)) {
+                        Some(port) => {
+                            self.esp_port = Some(port.name.clone());
+                            self.port_pinned = true;
+                            self.status = format!("Recording from {}.", port.name);
+                            self.probe_on_connect();
+                        }
+                        None => {
+                            self.port_pinned = false;
+                            self.refresh_esp(true);
+                            self.status = match &self.esp_port {
+                                Some(p) => format!("Auto-detecting the port: ESP on {p}."),
+                                None => "Auto-detecting the port: no ESP found.".into(),
+                            };
+                        }
+                    }
+                }
+                KeyCode::Esc => self.port_picker = None,
+                _ => {}
+            }
+            return;
+        }
+        // Likewise the preset name prompt.
+        if let Some(name) = &mut self.preset_input {
+            match key.code {
+                KeyCode::Char(c) if c != '/' && c != '\\' => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter => {
+                    let name = self.preset_input.take().unwrap_or_default();
+                    self.save_preset(name.trim());
+                }
+                KeyCode::Esc => self.preset_input = None,
+                _ => {}
+            }
+            return;
+        }
+
         // Global quit shortcuts
         if matches!(
             (key.modifiers, key.code),
@@ -395,96 +1213,85 @@ impl App {
         if key.modifiers == KeyModifiers::CONTROL {
             if let KeyCode::Char('s') | KeyCode::Char('S') = key.code {
                 // Validate filename and duration
-                if self.filename.trim().is_empty() {
-                    self.status = "Filename cannot be empty.".into();
+                if let Some(msg) = self.field_error(5) {
+                    self.reject_field(5, msg);
                     return;
                 }
-                if self.duration_input.trim().is_empty() {
-                    self.status = "Duration cannot be empty.".into();
+                if let Some(schedule) = self.schedule {
+                    if self.schedule_run.is_none() && self.step != Step::Recording {
+                        self.start_schedule(schedule);
+                    }
                     return;
                 }
-                let secs: u64 = match self.duration_input.parse() {
-                    Ok(v) if v > 0 => v,
-                    _ => {
-                        self.status = "Duration must be a positive integer.".into();
+                let secs = match self.duration_secs() {
+                    Ok(secs) => secs,
+                    Err(msg) => {
+                        self.reject_field(4, msg);
                         return;
                     }
                 };
                 self.start_recording(secs);
                 return;
             }
+            if let KeyCode::Char('e') | KeyCode::Char('E') = key.code {
+                self.export_region();
+                return;
+            }
+            if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+                self.copy_to_clipboard();
+                return;
+            }
+            if let KeyCode::Char('d') | KeyCode::Char('D') = key.code {
+                self.start_probe();
+                return;
+            }
         }
 
-        // Navigation: Tab switches nav panels, Up/Down move within active panel,
-        // Space toggles checkboxes (or loads a file when on files list).
-        // If the controls pane is focused, route typing/backspace/enter to the active field.
-        match key.code {
-            KeyCode::Char(c) => {
-                if self.nav_selected == 0 {
-                    match self.nav_item_selected {
-                        2 => {
-                            self.ssid.push(c);
-                            return;
-                        }
-                        3 => {
-                            self.password.push(c);
-                            return;
-                        }
-                        4 => {
-                            if c.is_ascii_digit() {
-                                self.duration_input.push(c);
-                            }
-                            return;
-                        }
-                        5 => {
-                            self.filename.push(c);
-                            return;
-                        }
-                        _ => {}
-                    }
-                }
+        // Enter leaves a full-screen view kept up after recording.
+        if key.code == KeyCode::Enter && self.full_screen_plot && self.step != Step::Recording {
+            self.full_screen_plot = false;
+            return;
+        }
+
+        // Space freezes/unfreezes the live chart when it has the screen.
+        if key.code == KeyCode::Char(' ') && self.full_screen_plot {
+            self.plot_paused = !self.plot_paused;
+            if !self.plot_paused {
+                // Back to following the newest data.
+                self.view_bounds = None;
             }
-            KeyCode::Backspace => {
-                if self.nav_selected == 0 {
-                    match self.nav_item_selected {
-                        2 => {
-                            self.ssid.pop();
-                            return;
-                        }
-                        3 => {
-                            self.password.pop();
-                            return;
-                        }
-                        4 => {
-                            self.duration_input.pop();
-                            return;
-                        }
-                        5 => {
-                            self.filename.pop();
-                            return;
-                        }
-                        _ => {}
-                    }
+            return;
+        }
+
+        // If the controls pane is focused, typing edits the active field and
+        // Enter confirms it.
+        if self.edit_focused_field(key.code) {
+            return;
+        }
+        if key.code == KeyCode::Enter && self.nav_selected == 0 {
+            if self.nav_item_selected == 5 {
+                if self.filename.is_empty() {
+                    self.reject_field(5, "Filename cannot be empty.".into());
+                } else {
+                    self.step = Step::ChooseAction;
+                    self.status =
+                        "Press R to record new data, or O to open existing .csv file".into();
+                    self.load_file_for_plot();
                 }
+                return;
             }
-            KeyCode::Enter => {
-                if self.nav_selected == 0 && self.nav_item_selected == 5 {
-                    if self.filename.is_empty() {
-                        self.status = "Filename cannot be empty.".into();
-                    } else {
-                        self.step = Step::ChooseAction;
-                        self.status =
-                            "Press R to record new data, or O to open existing .csv file".into();
-                        self.load_file_for_plot();
-                    }
-                    return;
-                }
+            // Apply a new heatmap range to the loaded capture.
+            if matches!(self.nav_item_selected, 9 | 10)
+                && self.step != Step::Recording
+                && !self.filename.trim().is_empty()
+            {
+                self.load_file_for_plot();
+                return;
             }
-
-            _ => {}
         }
 
-        // Navigation keys and space handling
+        // Navigation: Tab switches nav panels, Up/Down move within active panel,
+        // Space toggles checkboxes (or loads a file when on files list).
         match key.code {
             KeyCode::Tab => {
                 self.nav_selected = (self.nav_selected + 1) % 2;
@@ -492,28 +1299,23 @@ impl App {
                 return;
             }
             KeyCode::Up => {
-                if self.nav_selected == 0 {
+                let len = self.focused_list_len();
+                if len > 0 {
                     if self.nav_item_selected > 0 {
                         self.nav_item_selected -= 1;
-                    }
-                } else {
-                    let files_len = Self::list_saved_files().len();
-                    if files_len > 0 && self.nav_item_selected > 0 {
-                        self.nav_item_selected -= 1;
+                    } else if self.wrap_navigation {
+                        self.nav_item_selected = len - 1;
                     }
                 }
                 return;
             }
             KeyCode::Down => {
-                if self.nav_selected == 0 {
-                    let controls_len = 6;
-                    if self.nav_item_selected + 1 < controls_len {
-                        self.nav_item_selected += 1;
-                    }
-                } else {
-                    let files_len = Self::list_saved_files().len();
-                    if files_len > 0 && self.nav_item_selected + 1 < files_len {
+                let len = self.focused_list_len();
+                if len > 0 {
+                    if self.nav_item_selected + 1 < len {
                         self.nav_item_selected += 1;
+                    } else if self.wrap_navigation {
+                        self.nav_item_selected = 0;
                     }
                 }
                 return;
@@ -532,15 +1334,9 @@ impl App {
                         _ => {}
                     }
                 } else {
-                    let files_vec = Self::list_saved_files();
+                    let files_vec = self.list_saved_files();
                     if !files_vec.is_empty() && self.nav_item_selected < files_vec.len() {
-                        let selected = files_vec[self.nav_item_selected].clone();
-                        // strip extension for filename state
-                        if let Some(pos) = selected.rfind('.') {
-                            self.filename = selected[..pos].to_string();
-                        } else {
-                            self.filename = selected;
-                        }
+                        self.filename = capture_name(&files_vec[self.nav_item_selected]).to_string();
                         self.load_file_for_plot();
                     }
                 }
@@ -549,94 +1345,914 @@ impl App {
             _ => {}
         }
 
-        // If the controls pane is focused, let typing/backspace modify the active field.
-        match key.code {
-            KeyCode::Char(c) => {
-                if self.nav_selected == 0 {
-                    match self.nav_item_selected {
-                        2 => {
-                            self.ssid.push(c);
-                            return;
-                        }
-                        3 => {
-                            self.password.push(c);
-                            return;
-                        }
-                        4 => {
-                            if c.is_ascii_digit() {
-                                self.duration_input.push(c);
-                            }
-                            return;
-                        }
-                        5 => {
-                            self.filename.push(c);
-                            return;
-                        }
-                        _ => {}
-                    }
+        // Chart zoom/pan, only when not typing into a text field.
+        if !self.editing_text_field() {
+            match key.code {
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    self.zoom_x(0.5);
+                    return;
                 }
-            }
-            KeyCode::Backspace => {
-                if self.nav_selected == 0 {
-                    match self.nav_item_selected {
-                        2 => {
-                            self.ssid.pop();
-                            return;
-                        }
-                        3 => {
-                            self.password.pop();
-                            return;
-                        }
-                        4 => {
-                            self.duration_input.pop();
-                            return;
-                        }
-                        5 => {
-                            self.filename.pop();
-                            return;
-                        }
-                        _ => {}
-                    }
+                KeyCode::Char('-') => {
+                    self.zoom_x(2.0);
+                    return;
                 }
-            }
-            KeyCode::Enter => {
-                // If Enter on Filename when controls focused, behave like filename Enter.
-                if self.nav_selected == 0 && self.nav_item_selected == 5 {
-                    if self.filename.is_empty() {
-                        self.status = "Filename cannot be empty.".into();
-                    } else {
-                        self.step = Step::ChooseAction;
-                        self.status =
-                            "Press R to record new data, or O to open existing .csv file".into();
-                        self.load_file_for_plot();
-                    }
+                KeyCode::Left if self.show_frame => {
+                    self.frame_index = self.frame_index.saturating_sub(1);
                     return;
                 }
-            }
-            _ => {}
-        }
-
-        match self.step {
-            Step::EnterFilename => self.handle_filename_input(key),
-            Step::ChooseAction => self.handle_duration_input(key),
-            Step::EnterDuration => self.handle_duration_input(key),
-            Step::Recording | Step::Finished => {
-                // No extra handling here, q/Esc handled above.
-            }
-        }
-    }
+                KeyCode::Right if self.show_frame => {
+                    self.frame_index = (self.frame_index + 1).min(self.frame_amplitudes.len().saturating_sub(1));
+                    return;
+                }
+                KeyCode::Left => {
+                    self.pan_x(-0.1);
+                    return;
+                }
+                KeyCode::Right => {
+                    self.pan_x(0.1);
+                    return;
+                }
+                KeyCode::Char('0') => {
+                    self.view_bounds = None;
+                    return;
+                }
+                KeyCode::Char('<') if self.step != Step::Recording => {
+                    self.mark_region(true);
+                    return;
+                }
+                KeyCode::Char('>') if self.step != Step::Recording => {
+                    self.mark_region(false);
+                    return;
+                }
+                KeyCode::Char('q') | KeyCode::Char('Q') if self.schedule_run.is_some() => {
+                    self.cancel_schedule();
+                    return;
+                }
+                KeyCode::Char('q') | KeyCode::Char('Q') if self.step == Step::Recording => {
+                    if let Some(cancel) = &self.recording_cancel {
+                        cancel.store(true, Ordering::Relaxed);
+                        self.push_log("Stopping the recording...".into());
+                    }
+                    return;
+                }
+                KeyCode::Char('*') => {
+                    self.toggle_bookmark();
+                    return;
+                }
+                KeyCode::Char('.') if self.step != Step::Recording => {
+                    self.next_bookmark();
+                    return;
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') if self.step != Step::Recording => {
+                    self.refresh_esp(true);
+                    self.status = match &self.esp_port {
+                        Some(p) if self.port_pinned => {
+                            format!("Using the chosen port {p}; pick Auto-detect with i to rescan.")
+                        }
+                        Some(p) => format!("Rescanned serial ports: ESP on {p}."),
+                        None => "Rescanned serial ports: no ESP found.".into(),
+                    };
+                    return;
+                }
+                KeyCode::Char('u') | KeyCode::Char('U') if self.step != Step::Recording => {
+                    self.preset_input = Some(String::new());
+                    return;
+                }
+                KeyCode::Char('i') | KeyCode::Char('I') if self.step != Step::Recording && !self.full_screen_plot => {
+                    let ports = esp_port::list_ports();
+                    // Start on the port in use, if it's still there.
+                    let selected = self
+                        .esp_port
+                        .as_ref()
+                        .filter(|_| self.port_pinned)
+                        .and_then(|current| ports.iter().position(|p| &p.name == current))
+                        .map_or(0, |i| i + 1);
+                    self.port_picker = Some(PortPicker { ports, selected });
+                    return;
+                }
+                KeyCode::Char(c @ '1'..='9') if self.step != Step::Recording => {
+                    let idx = c as usize - '1' as usize;
+                    match self.presets.get(idx).cloned() {
+                        Some(name) => self.load_preset(&name),
+                        None => self.status = format!("No preset {}; press u to save one.", c),
+                    }
+                    return;
+                }
+                KeyCode::Char('g') | KeyCode::Char('G') => {
+                    if self.plot_points.is_empty() {
+                        self.status = "Load a capture before jumping to a time.".into();
+                    } else {
+                        self.jump_input = Some(String::new());
+                    }
+                    return;
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') if self.step != Step::Recording => {
+                    self.dual_board = !self.dual_board;
+                    self.status = if self.dual_board {
+                        "Dual-board mode on: recording will use the first two detected ports.".into()
+                    } else {
+                        "Dual-board mode off.".into()
+                    };
+                    return;
+                }
+                KeyCode::Char('a') | KeyCode::Char('A') if self.step == Step::Recording => {
+                    self.annotate_event();
+                    return;
+                }
+                KeyCode::Char('a') | KeyCode::Char('A') => {
+                    self.auto_subcarrier = !self.auto_subcarrier;
+                    self.push_log(format!(
+                        "Auto subcarrier {}",
+                        if self.auto_subcarrier { "on: most variable on load" } else { "off" }
+                    ));
+                    if self.auto_subcarrier && !self.filename.trim().is_empty() {
+                        self.load_file_for_plot();
+                    }
+                    return;
+                }
+                KeyCode::Char('l') | KeyCode::Char('L') if self.step == Step::Recording => {
+                    self.event_label_idx = (self.event_label_idx + 1) % EVENT_LABELS.len();
+                    self.status = format!(
+                        "Annotation label: {} (press a to mark)",
+                        EVENT_LABELS[self.event_label_idx]
+                    );
+                    return;
+                }
+                KeyCode::Char('k') | KeyCode::Char('K') if self.step != Step::Recording => {
+                    if let Some((name, max_sc)) = self.clamp_offer.take() {
+                        self.subcarrier = max_sc;
+                        self.load_capture(&name);
+                    }
+                    return;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.step != Step::Recording => {
+                    self.antenna_filter = match self.antenna_filter {
+                        None => Some(0),
+                        Some(a) if a + 1 < ANTENNA_COUNT => Some(a + 1),
+                        Some(_) => None,
+                    };
+                    self.push_log(match self.antenna_filter {
+                        Some(a) => format!("Showing antenna {} only.", a),
+                        None => "Showing all antennas.".to_string(),
+                    });
+                    if !self.filename.trim().is_empty() {
+                        self.load_file_for_plot();
+                    }
+                    return;
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    self.export_heatmap_png();
+                    return;
+                }
+                KeyCode::Char('e') | KeyCode::Char('E') => {
+                    self.open_in_rerun();
+                    return;
+                }
+                KeyCode::Char('v') | KeyCode::Char('V') if self.step != Step::Recording => {
+                    self.view = self.view.next();
+                    if !self.filename.trim().is_empty() {
+                        self.load_file_for_plot();
+                    }
+                    return;
+                }
+                KeyCode::Char('m') | KeyCode::Char('M') if self.step != Step::Recording => {
+                    self.metric = self.metric.next();
+                    if !self.filename.trim().is_empty() {
+                        self.load_file_for_plot();
+                    }
+                    self.push_log(format!("Amplitude metric: {}", self.metric.label()));
+                    return;
+                }
+                KeyCode::Char('t') | KeyCode::Char('T') => {
+                    self.theme = self.theme.next();
+                    self.push_log(format!("Theme: {}", self.theme.name));
+                    return;
+                }
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    self.keep_full_screen = !self.keep_full_screen;
+                    self.push_log(format!(
+                        "After recording: {}",
+                        if self.keep_full_screen { "stay in full-screen" } else { "return to the dashboard" }
+                    ));
+                    return;
+                }
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.log_y = !self.log_y;
+                    return;
+                }
+                KeyCode::Char('h') | KeyCode::Char('H') => {
+                    self.heatmap_cell_width = if self.heatmap_cell_width == 2 { 1 } else { 2 };
+                    self.heatmap_data.cell_width = self.heatmap_cell_width;
+                    self.spectrum_data.cell_width = self.heatmap_cell_width;
+                    return;
+                }
+                KeyCode::Char('f') | KeyCode::Char('F') => {
+                    self.show_spectrogram = !self.show_spectrogram;
+                    return;
+                }
+                KeyCode::Char('c') | KeyCode::Char('C') => {
+                    self.show_frame = !self.show_frame;
+                    return;
+                }
+                KeyCode::Char('b') | KeyCode::Char('B') => {
+                    self.show_histogram = !self.show_histogram;
+                    return;
+                }
+                KeyCode::Char('[') => {
+                    self.histogram_bins = self.histogram_bins.saturating_sub(5).max(*HISTOGRAM_BINS.start());
+                    return;
+                }
+                KeyCode::Char(']') => {
+                    self.histogram_bins = (self.histogram_bins + 5).min(*HISTOGRAM_BINS.end());
+                    return;
+                }
+                KeyCode::Char('j') | KeyCode::Char('J') => {
+                    self.background_subtract = !self.background_subtract;
+                    if !self.filename.trim().is_empty() && self.step != Step::Recording {
+                        self.load_file_for_plot();
+                    }
+                    self.push_log(format!(
+                        "Background subtraction {}.",
+                        if self.background_subtract { "on" } else { "off" }
+                    ));
+                    return;
+                }
+                KeyCode::Char('x') | KeyCode::Char('X') if self.step != Step::Recording => {
+                    self.toggle_compare();
+                    return;
+                }
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    self.file_sort = self.file_sort.next();
+                    self.nav_item_selected = 0;
+                    return;
+                }
+                KeyCode::Char('w') | KeyCode::Char('W') => {
+                    self.wrap_navigation = !self.wrap_navigation;
+                    self.status = format!(
+                        "Wrap-around navigation {}.",
+                        if self.wrap_navigation { "on" } else { "off" }
+                    );
+                    return;
+                }
+                KeyCode::Char('z') | KeyCode::Char('Z') => {
+                    self.zscore = !self.zscore;
+                    if !self.filename.trim().is_empty() && self.step != Step::Recording {
+                        self.load_file_for_plot();
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match self.step {
+            Step::EnterFilename => self.handle_filename_input(key),
+            Step::ChooseAction => self.handle_duration_input(key),
+            Step::EnterDuration => self.handle_duration_input(key),
+            Step::Recording | Step::Finished => {
+                // No extra handling here, q/Esc handled above.
+            }
+        }
+    }
+
+    fn y_axis_title(&self) -> String {
+        let base = if self.zscore {
+            format!("{} (z-score)", self.view.label())
+        } else {
+            format!("{} ({})", self.view.label(), self.view.units(self.metric))
+        };
+        if self.log_y {
+            format!("log10({} + 1)", base)
+        } else {
+            base
+        }
+    }
+
+    /// Apply the y-axis scale to plot points. Log mode plots
+    /// `log10(a + 1)`, with negative values (z-scores) clamped to 0 first.
+    fn scale_y(&self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        if self.log_y {
+            points.iter().map(|&(t, a)| (t, (a.max(0.0) + 1.0).log10())).collect()
+        } else {
+            points.to_vec()
+        }
+    }
+
+    /// Amplitude across subcarriers for one frame of the loaded capture: the
+    /// instantaneous channel frequency response.
+    fn render_frame_response(&self, frame: &mut Frame, area: Rect) {
+        let Some(amps) = self.frame_amplitudes.get(self.frame_index) else {
+            frame.render_widget(
+                Paragraph::new("Frame view (load a capture)")
+                    .block(Block::bordered().title("Amplitude vs subcarrier")),
+                area,
+            );
+            return;
+        };
+        let points: Vec<(f64, f64)> = amps
+            .iter()
+            .enumerate()
+            .map(|(sc, a)| (sc as f64, *a as f64))
+            .collect();
+        let a_max = points.iter().fold(1.0f64, |mx, (_, a)| mx.max(*a));
+        let chart = Chart::new(vec![
+            Dataset::default()
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(self.theme.accent)
+                .data(&points),
+        ])
+        .block(Block::bordered().title(format!(
+            "Amplitude vs subcarrier — frame {} / {} (Left/Right)",
+            self.frame_index,
+            self.frame_amplitudes.len() - 1
+        )))
+        .x_axis(
+            Axis::default()
+                .title("subcarrier")
+                .bounds([0.0, (points.len().max(2) - 1) as f64]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(self.metric.units())
+                .bounds([0.0, a_max])
+                .labels([display(0.0), display(a_max)]),
+        );
+        frame.render_widget(chart, area);
+    }
+
+    /// Distribution of the visible plot values in `histogram_bins` bins,
+    /// labelled with each bin's lower edge.
+    fn render_histogram(&self, frame: &mut Frame, area: Rect) {
+        let title = format!("{} histogram ({} bins)", self.view.label(), self.histogram_bins);
+        let (visible, _) = self.visible_plot_points();
+        if visible.is_empty() {
+            frame.render_widget(Paragraph::new("Histogram (no data)").block(Block::bordered().title(title)), area);
+            return;
+        }
+        let hist = dsp::histogram(visible.iter().map(|(_, v)| *v), self.histogram_bins);
+        let inner_width = area.width.saturating_sub(2);
+        let bar_width = (inner_width / hist.counts.len() as u16).max(1);
+        // Only label as many bars as there is room for.
+        let label_every = (6 / bar_width).max(1) as usize;
+        let bars: Vec<Bar> = hist
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let label = if i % label_every == 0 {
+                    display(hist.min + i as f64 * hist.bin_width())
+                } else {
+                    String::new()
+                };
+                Bar::default().value(count).label(Line::from(label)).text_value(String::new())
+            })
+            .collect();
+        let chart = BarChart::default()
+            .block(Block::bordered().title(title))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(0)
+            .bar_style(Style::default().fg(self.theme.accent));
+        frame.render_widget(chart, area);
+    }
+
+    /// Live spectrogram of the selected subcarrier, newest column on the right.
+    fn render_spectrogram(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(format!(
+            "Spectrogram SC {} (low freq at bottom)",
+            self.subcarrier
+        ));
+        if self.spectrogram.is_empty() {
+            frame.render_widget(Paragraph::new("Spectrogram (waiting for live data)").block(block), area);
+            return;
+        }
+        let inner_area = block.inner(area);
+        block.render(area, frame.buffer_mut());
+        frame.render_widget(&self
+                .spectrogram
+                .to_heatmap(inner_area.width as usize, inner_area.height as usize)
+                .with_colormap(self.theme.colormap), inner_area);
+    }
+
+    /// Save the heatmap as `<save_dir>/<name>.heatmap.png`, named after the
+    /// loaded file (or the current recording).
+    fn export_heatmap_png(&mut self) {
+        let name = if self.step == Step::Recording || self.filename.trim().is_empty() {
+            self.recording_name.trim()
+        } else {
+            self.filename.trim()
+        };
+        let name = if name.is_empty() { "heatmap" } else { name };
+        let path = format!("{}/{}.heatmap.png", self.save_dir, name);
+        match self.heatmap_data.write_png(&path, PNG_CELL_PX) {
+            Ok(()) => self.push_log(format!("Saved heatmap to {}", path)),
+            Err(e) => self.push_log(format!("Heatmap export failed: {}", e)),
+        }
+    }
+
+    /// Overlay the capture selected in Saved Files on the plot, or clear the
+    /// overlay when no file is selected or the same one is picked again.
+    fn toggle_compare(&mut self) {
+        let files = if self.nav_selected == 1 { self.list_saved_files() } else { Vec::new() };
+        let name = files.get(self.nav_item_selected).map(|f| capture_name(f).to_string());
+        if name.is_none() || name == self.compare_name {
+            if self.compare_name.take().is_some() {
+                self.compare_points.clear();
+                self.status = "Comparison overlay cleared.".into();
+            } else {
+                self.status = "Select a file in Saved Files and press x to compare.".into();
+            }
+            return;
+        }
+        self.compare_name = name;
+        self.load_compare();
+        if let Some(name) = &self.compare_name {
+            self.status = format!("Comparing with {} ({} samples).", name, self.compare_points.len());
+        }
+    }
+
+    /// (Re)load the comparison capture with the current plot settings. Both
+    /// series start at t = 0, so their time axes share an origin.
+    fn load_compare(&mut self) {
+        let Some(name) = self.compare_name.clone() else {
+            return;
+        };
+        let path = self.capture_path(&name);
+        let loaded = read_data::load_csv_packets(&path).map_err(|e| e.to_string()).and_then(|packets| {
+            read_data::series_from_packets(
+                &packets,
+                self.subcarrier,
+                self.antenna_filter,
+                self.view,
+                self.metric,
+                self.calibration.as_ref(),
+            )
+            .map_err(|e| e.to_string())
+        });
+        match loaded {
+            Ok(mut points) => {
+                if self.zscore {
+                    dsp::zscore_series(&mut points);
+                }
+                self.compare_points = points;
+            }
+            Err(e) => {
+                self.status = format!("Failed to load {} for comparison: {}", path, e);
+                self.compare_name = None;
+                self.compare_points.clear();
+            }
+        }
+    }
+
+    /// Launch the Rerun viewer on the `.rrd` of the file selected in the
+    /// Saved Files pane, or else of the loaded or just-recorded capture.
+    fn open_in_rerun(&mut self) {
+        let files = if self.nav_selected == 1 { self.list_saved_files() } else { Vec::new() };
+        let name = match files.get(self.nav_item_selected) {
+            Some(file) => capture_name(file).to_string(),
+            None if self.step == Step::Recording || self.filename.trim().is_empty() => {
+                self.recording_name.trim().to_string()
+            }
+            None => self.filename.trim().to_string(),
+        };
+        if name.is_empty() {
+            self.status = "No capture selected to open in Rerun.".into();
+            return;
+        }
+        let path = format!("{}/{}.rrd", self.save_dir, name);
+        if !Path::new(&path).exists() {
+            self.status = format!("No {} to open; it is only written with Rerun file output.", path);
+            return;
+        }
+        let spawned = std::process::Command::new("rerun")
+            .arg(&path)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Reap the viewer when it exits so it doesn't linger as a zombie.
+                thread::spawn(move || child.wait());
+                self.push_log(format!("Opened {} in the Rerun viewer", path));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.status = "Rerun viewer not found on PATH; install it with `cargo install rerun-cli`.".into();
+            }
+            Err(e) => self.status = format!("Failed to launch the Rerun viewer: {}", e),
+        }
+    }
+
+    /// Legend name of the plotted series, e.g. "Subcarrier 20 (ant 1)".
+    fn series_name(&self) -> String {
+        // RSSI is per frame, not per subcarrier.
+        let base = if self.view == Quantity::Rssi {
+            "RSSI".to_string()
+        } else {
+            format!("Subcarrier {}", self.subcarrier)
+        };
+        match self.antenna_filter {
+            Some(a) => format!("{} (ant {})", base, a),
+            None => base,
+        }
+    }
+
+    /// Number of items in the focused nav pane.
+    fn focused_list_len(&self) -> usize {
+        if self.nav_selected == 0 {
+            CONTROLS_LEN
+        } else {
+            self.list_saved_files().len()
+        }
+    }
+
+    /// The Options text field at `idx`, if that control is one.
+    fn text_field(&mut self, idx: usize) -> Option<&mut String> {
+        match idx {
+            2 => Some(&mut self.ssid),
+            3 => Some(&mut self.password),
+            4 => Some(&mut self.duration_input),
+            5 => Some(&mut self.filename),
+            6 => Some(&mut self.trigger_threshold_input),
+            7 => Some(&mut self.trigger_subcarrier_input),
+            8 => Some(&mut self.decimation_input),
+            9 => Some(&mut self.heatmap_lo_input),
+            10 => Some(&mut self.heatmap_hi_input),
+            _ => None,
+        }
+    }
+
+    /// Apply an editing key (typing, Backspace/Delete, Left/Right,
+    /// Home/End) to the focused text field at its cursor. Returns whether
+    /// the key was consumed.
+    fn edit_focused_field(&mut self, code: KeyCode) -> bool {
+        if !self.editing_text_field() {
+            return false;
+        }
+        let idx = self.nav_item_selected;
+        let stored = self.field_cursors[idx];
+        let Some(field) = self.text_field(idx) else {
+            return false;
+        };
+        let len = field.chars().count();
+        let cursor = stored.map_or(len, |c| c.min(len));
+        let byte_at = |field: &String, i: usize| field.char_indices().nth(i).map_or(field.len(), |(b, _)| b);
+        let cursor = match code {
+            KeyCode::Char(c) => {
+                if field_accepts(idx, c) {
+                    field.insert(byte_at(field, cursor), c);
+                    cursor + 1
+                } else {
+                    cursor
+                }
+            }
+            KeyCode::Backspace if cursor > 0 => {
+                field.remove(byte_at(field, cursor - 1));
+                cursor - 1
+            }
+            KeyCode::Delete if cursor < len => {
+                field.remove(byte_at(field, cursor));
+                cursor
+            }
+            KeyCode::Backspace | KeyCode::Delete => cursor,
+            KeyCode::Left => cursor.saturating_sub(1),
+            KeyCode::Right => (cursor + 1).min(len),
+            KeyCode::Home => 0,
+            KeyCode::End => len,
+            _ => return false,
+        };
+        let len = field.chars().count();
+        self.field_cursors[idx] = (cursor < len).then_some(cursor);
+        self.recheck_invalid_field();
+        true
+    }
+
+    /// Why the Options field at `idx` can't be used as is, if it can't.
+    fn field_error(&self, idx: usize) -> Option<String> {
+        match idx {
+            4 => self.duration_secs().err(),
+            5 => self.filename.trim().is_empty().then(|| "Filename cannot be empty.".to_string()),
+            6 => {
+                let threshold = self.trigger_threshold_input.trim();
+                (!threshold.is_empty() && threshold.parse::<f32>().is_err())
+                    .then(|| format!("Trigger amplitude must be a number, got '{}'.", threshold))
+            }
+            7 => self.trigger_mode().err().filter(|_| self.field_error(6).is_none()),
+            8 => self.decimation().err(),
+            _ => None,
+        }
+    }
+
+    /// Report `msg` and highlight the Options field at `idx`.
+    fn reject_field(&mut self, idx: usize, msg: String) {
+        self.status = msg;
+        self.invalid_field = Some(idx);
+    }
+
+    /// Drop the highlight once the rejected field holds a valid value.
+    fn recheck_invalid_field(&mut self) {
+        if let Some(idx) = self.invalid_field
+            && self.field_error(idx).is_none()
+        {
+            self.invalid_field = None;
+        }
+    }
+
+    /// Whether the focused control is a free-text/number field that should
+    /// receive typed characters.
+    fn editing_text_field(&self) -> bool {
+        !self.full_screen_plot
+            && self.nav_selected == 0
+            && (2..CONTROLS_LEN).contains(&self.nav_item_selected)
+    }
+
+    /// Time range covered by `plot_points` (assumed sorted by time).
+    fn full_x_extent(&self) -> Option<(f64, f64)> {
+        let first = self.plot_points.first()?.0;
+        let last = self.plot_points.last()?.0;
+        Some((first, last.max(first + 0.1)))
+    }
+
+    /// The points inside the current view window, plus the x-axis bounds.
+    fn visible_plot_points(&self) -> (&[(f64, f64)], [f64; 2]) {
+        let Some((full_min, full_max)) = self.full_x_extent() else {
+            return (&[], [0.0, 0.1]);
+        };
+        let (lo, hi) = self.view_bounds.unwrap_or((full_min, full_max));
+        let start = self.plot_points.partition_point(|(t, _)| *t < lo);
+        let end = self.plot_points.partition_point(|(t, _)| *t <= hi);
+        (&self.plot_points[start..end.max(start)], [lo, hi])
+    }
+
+    /// The series in view with its compare overlay, event markers, region
+    /// shading and newest-sample dot.
+    fn plot_series(&self) -> PlotSeries {
+        let (visible, x) = self.visible_plot_points();
+        let visible = self.scale_y(visible);
+        let compare = self.scale_y(self.visible_compare_points(x));
+        let (a_min, a_max) = visible
+            .iter()
+            .chain(&compare)
+            .fold((0.0f64, 0.0f64), |(mn, mx), (_, a)| (mn.min(*a), mx.max(*a)));
+        let y = [a_min, a_max.max(a_min + 1.0)];
+        PlotSeries {
+            shading: self.region_shading(x, y),
+            markers: self.event_markers(x, y),
+            latest: self.scale_y(&self.plot_points[self.plot_points.len().saturating_sub(1)..]),
+            last_label: self.format_last_label().unwrap_or_default(),
+            visible,
+            compare,
+            x,
+            y,
+        }
+    }
+
+    /// Line chart of `series` and its overlays, titled `title`.
+    fn plot_chart<'a>(&'a self, series: &'a PlotSeries, title: String) -> Chart<'a> {
+        let mut datasets = Vec::new();
+        // Region of interest first, so the series draws over its shading.
+        if !series.shading.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .marker(ratatui::symbols::Marker::Braille)
+                    .graph_type(GraphType::Scatter)
+                    .style(self.theme.region)
+                    .data(&series.shading),
+            );
+        }
+        datasets.push(
+            Dataset::default()
+                .name(self.series_name())
+                .marker(ratatui::symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(self.theme.accent)
+                .data(&series.visible),
+        );
+        if let Some(name) = &self.compare_name {
+            datasets.push(
+                Dataset::default()
+                    .name(name.as_str())
+                    .marker(ratatui::symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(self.theme.compare)
+                    .data(&series.compare),
+            );
+        }
+        for (i, (label, points)) in series.markers.iter().enumerate() {
+            datasets.push(
+                Dataset::default()
+                    .name(label.as_str())
+                    .marker(ratatui::symbols::Marker::Braille)
+                    .graph_type(GraphType::Scatter)
+                    .style(self.theme.events[i % self.theme.events.len()])
+                    .data(points),
+            );
+        }
+        datasets.push(
+            Dataset::default()
+                .name(series.last_label.as_str())
+                .marker(ratatui::symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(self.theme.alert).add_modifier(Modifier::BOLD))
+                .data(&series.latest),
+        );
+        let [t_min, t_max] = series.x;
+        let [a_min, a_max] = series.y;
+        Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .title("time (s)")
+                    .bounds([t_min, t_max.max(t_min + 0.1)]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title(self.y_axis_title())
+                    .bounds([a_min, a_max])
+                    .labels([display(a_min), display(a_max)]),
+            )
+    }
+
+    /// The comparison capture's points inside the x-range `x`.
+    fn visible_compare_points(&self, x: [f64; 2]) -> &[(f64, f64)] {
+        let start = self.compare_points.partition_point(|(t, _)| *t < x[0]);
+        let end = self.compare_points.partition_point(|(t, _)| *t <= x[1]);
+        &self.compare_points[start..end.max(start)]
+    }
+
+    /// Vertical marker lines for annotations inside the x-range, grouped by
+    /// label so each label gets one legend entry and colour.
+    fn event_markers(&self, x: [f64; 2], y: [f64; 2]) -> Vec<(String, Vec<(f64, f64)>)> {
+        const MARKER_POINTS: usize = 40;
+        let mut groups: Vec<(String, Vec<(f64, f64)>)> = Vec::new();
+        for event in self.events.iter().filter(|e| e.t >= x[0] && e.t <= x[1]) {
+            let idx = match groups.iter().position(|(label, _)| *label == event.label) {
+                Some(idx) => idx,
+                None => {
+                    groups.push((event.label.clone(), Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            let points = &mut groups[idx].1;
+            for k in 0..=MARKER_POINTS {
+                let y_k = y[0] + (y[1] - y[0]) * k as f64 / MARKER_POINTS as f64;
+                points.push((event.t, y_k));
+            }
+        }
+        groups
+    }
+
+    /// Dots filling the marked region inside the x-range, drawn behind the
+    /// series as shading.
+    fn region_shading(&self, x: [f64; 2], y: [f64; 2]) -> Vec<(f64, f64)> {
+        const COLUMNS: usize = 80;
+        const ROWS: usize = 12;
+        let Some(region) = self.region.as_ref().filter(|r| r.capture == self.filename.trim()) else {
+            return Vec::new();
+        };
+        let (from, to) = region.bounds();
+        let (from, to) = (from.max(x[0]), to.min(x[1]));
+        if from > to {
+            return Vec::new();
+        }
+        // Columns as dense as the full view would have, so the shading
+        // looks the same at any zoom.
+        let columns = (((to - from) / (x[1] - x[0]).max(1e-9)) * COLUMNS as f64).ceil() as usize;
+        let mut points = Vec::with_capacity((columns + 1) * (ROWS + 1));
+        for c in 0..=columns {
+            let t = from + (to - from) * c as f64 / columns.max(1) as f64;
+            for r in 0..=ROWS {
+                points.push((t, y[0] + (y[1] - y[0]) * r as f64 / ROWS as f64));
+            }
+        }
+        points
+    }
+
+    /// Mark the start (`<`) or end (`>`) of the region of interest at the
+    /// left or right edge of the visible plot.
+    fn mark_region(&mut self, start: bool) {
+        let Some((full_min, full_max)) = self.full_x_extent() else {
+            self.status = "Load a capture before marking a region.".into();
+            return;
+        };
+        let (lo, hi) = self.view_bounds.unwrap_or((full_min, full_max));
+        let capture = self.filename.trim().to_string();
+        let region = match self.region.take() {
+            Some(region) if region.capture == capture => region,
+            _ => Region { capture, start: lo, end: None },
+        };
+        self.region = Some(if start {
+            Region { start: lo, ..region }
+        } else {
+            Region { end: Some(hi), ..region }
+        });
+        let (from, to) = self.region.as_ref().map(Region::bounds).unwrap_or_default();
+        self.status = if start {
+            format!("Region starts at {:.2}s; pan to its end and press >.", from)
+        } else {
+            format!("Region {:.2}s to {:.2}s; Ctrl+E exports it.", from, to)
+        };
+    }
+
+    /// Write the marked region of the loaded capture to
+    /// `<save_dir>/<name>_roi_<from>-<to>ms.csv`.
+    fn export_region(&mut self) {
+        let Some(region) = self.region.clone().filter(|r| r.capture == self.filename.trim() && r.end.is_some())
+        else {
+            self.status = "Mark a region with < and > on a loaded capture first.".into();
+            return;
+        };
+        let (from, to) = region.bounds();
+        let input = self.capture_path(&region.capture);
+        let output = format!(
+            "{}/{}_roi_{}-{}ms.csv",
+            self.save_dir,
+            region.capture,
+            (from * 1000.0).round() as u64,
+            (to * 1000.0).round() as u64
+        );
+        match csv_utils::slice_capture_by_time(&input, &output, from, to) {
+            Ok(rows) => self.push_log(format!("Exported {} rows ({:.2}s to {:.2}s) to {}", rows, from, to, output)),
+            Err(e) => self.status = format!("Failed to export region: {}", e),
+        }
+    }
+
+    /// Scale the visible x-range by `factor` around its centre (<1 zooms in).
+    fn zoom_x(&mut self, factor: f64) {
+        let Some((full_min, full_max)) = self.full_x_extent() else {
+            return;
+        };
+        let (lo, hi) = self.view_bounds.unwrap_or((full_min, full_max));
+        let centre = (lo + hi) / 2.0;
+        let width = ((hi - lo) * factor).max(0.01);
+        if width >= full_max - full_min {
+            self.view_bounds = None;
+            return;
+        }
+        let lo = (centre - width / 2.0).clamp(full_min, full_max - width);
+        self.view_bounds = Some((lo, lo + width));
+    }
+
+    /// Centre the view on the time typed into the jump prompt, keeping the
+    /// current zoom (or zooming in 4x from the full range).
+    fn jump_to(&mut self, input: &str) {
+        let Some((full_min, full_max)) = self.full_x_extent() else {
+            return;
+        };
+        let Some(t) = parse_time_input(input) else {
+            self.status = format!("Invalid time '{}': use seconds or HH:MM:SS.", input);
+            return;
+        };
+        if t < full_min || t > full_max {
+            self.status = format!(
+                "Time {:.2}s is outside the capture ({:.2}s to {:.2}s).",
+                t, full_min, full_max
+            );
+            return;
+        }
+        let width = match self.view_bounds {
+            Some((lo, hi)) => hi - lo,
+            None => (full_max - full_min) / 4.0,
+        };
+        if width >= full_max - full_min {
+            // The whole plot is in view already.
+            self.view_bounds = None;
+        } else {
+            let lo = (t - width / 2.0).clamp(full_min, full_max - width);
+            self.view_bounds = Some((lo, lo + width));
+        }
+        self.status = format!("Jumped to {:.2}s.", t);
+    }
+
+    /// Shift the visible x-range by `fraction` of its width.
+    fn pan_x(&mut self, fraction: f64) {
+        let (Some((full_min, full_max)), Some((lo, hi))) = (self.full_x_extent(), self.view_bounds)
+        else {
+            return;
+        };
+        let width = hi - lo;
+        // A live plot's extent can shrink below the zoomed width.
+        if width >= full_max - full_min {
+            self.view_bounds = None;
+            return;
+        }
+        let lo = (lo + width * fraction).clamp(full_min, full_max - width);
+        self.view_bounds = Some((lo, lo + width));
+    }
 
     fn handle_filename_input(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Char(c) => {
                 self.filename.push(c);
+                self.recheck_invalid_field();
             }
             KeyCode::Backspace => {
                 self.filename.pop();
             }
             KeyCode::Enter => {
                 if self.filename.is_empty() {
-                    self.status = "Filename cannot be empty.".into();
+                    self.reject_field(5, "Filename cannot be empty.".into());
                 } else {
                     self.step = Step::ChooseAction;
                     self.status =
@@ -654,19 +2270,16 @@ impl App {
         match key.code {
             KeyCode::Char(c) if c.is_ascii_digit() => {
                 self.duration_input.push(c);
+                self.recheck_invalid_field();
             }
             KeyCode::Backspace => {
                 self.duration_input.pop();
             }
             KeyCode::Enter => {
-                if self.duration_input.is_empty() {
-                    self.status = "Duration cannot be empty.".into();
-                    return;
-                }
-                let secs: u64 = match self.duration_input.parse() {
-                    Ok(v) if v > 0 => v,
-                    _ => {
-                        self.status = "Duration must be a positive integer.".into();
+                let secs = match self.duration_secs() {
+                    Ok(secs) => secs,
+                    Err(msg) => {
+                        self.reject_field(4, msg);
                         return;
                     }
                 };
@@ -676,58 +2289,388 @@ impl App {
         }
     }
 
-    fn start_recording(&mut self, secs: u64) {
-        let Some(port) = self.esp_port.clone() else {
-            self.status = "No serial port detected; cannot start recording.".into();
-            self.step = Step::Finished;
-            return;
-        };
-        let _ = fs::create_dir_all(SAVE_DIR);
-        let base_filename = self.filename.clone();
-        let csv_filename = format!("{}/{}.csv", SAVE_DIR, base_filename);
-        let rrd_filename = format!("{}/{}.rrd", SAVE_DIR, base_filename);
-        self.status = format!(
-            "Recording to {}/{}.csv and {}/{}.rrd for {}s on port {}...",
-            SAVE_DIR, base_filename, SAVE_DIR, base_filename, secs, port
-        );
-        self.step = Step::Recording;
-        self.recording_start = Some(SystemTime::now());
-        self.auto_switched = false;
-        self.full_screen_plot = false;
-        self.plot_points.clear();
-        self.heatmap_data = Heatmap { values: vec![] }; // Clear heatmap
-        self.plot_rx = None;
-        self.heatmap_rx = None; // Reset heatmap receiver
-        
-        let (tx, rx) = mpsc::channel();
-        self.worker_done_rx = Some(rx);
-        
-        let (plot_tx, plot_rx) = mpsc::channel();
-        self.plot_rx = Some(plot_rx);
-        
-        let (heatmap_tx, heatmap_rx) = mpsc::channel(); // Create heatmap channel
-        self.heatmap_rx = Some(heatmap_rx);
-        
-        let wifi_mode = self.wifi_mode;
-        let ssid = self.ssid.clone();
-        let password = self.password.clone();
-        let subcarrier = self.subcarrier;
-        thread::spawn(move || {
-            let res = parse_data::record_csi_to_file(
-                &port,
-                &csv_filename,
-                &rrd_filename,
-                wifi_mode,
-                ssid,
-                password,
-                secs,
-                subcarrier,
-                Some(plot_tx),
-                Some(heatmap_tx), // Pass heatmap sender
-            )
-            .map_err(|e| e.to_string());
-            let _ = tx.send(res);
-        });
+    fn start_recording(&mut self, secs: u64) {
+        if self.probe_rx.is_some() {
+            self.status = "Still detecting subcarriers; start again in a moment.".into();
+            return;
+        }
+        let trigger_mode = match self.trigger_mode() {
+            Ok(mode) => mode,
+            Err(msg) => {
+                let idx = if self.field_error(6).is_some() { 6 } else { 7 };
+                self.reject_field(idx, msg);
+                return;
+            }
+        };
+        let decimation = match self.decimation() {
+            Ok(n) => n,
+            Err(msg) => {
+                self.reject_field(8, msg);
+                return;
+            }
+        };
+        let mut estimate = parse_data::estimate_output_bytes(secs, self.expected_rate_hz, &self.rerun_sink)
+            / decimation as u64;
+        if self.dual_board {
+            estimate *= 2;
+        }
+        if estimate > SIZE_CONFIRM_BYTES && self.size_confirm.take() != Some(secs) {
+            self.size_confirm = Some(secs);
+            self.status = format!(
+                "A {}s capture at ~{} packets/s may write ~{}. Start again to confirm.",
+                secs,
+                self.expected_rate_hz,
+                format_bytes(estimate)
+            );
+            return;
+        }
+        let (port, secondary_port) = if let InputSource::Udp(_) = self.source {
+            if self.dual_board {
+                self.status = "Dual-board mode needs serial boards; turn it off (d) to record over UDP.".into();
+                self.step = Step::Finished;
+                return;
+            }
+            (self.source.to_string(), None)
+        } else if self.dual_board {
+            let ports = esp_port::find_esp_ports();
+            if ports.len() < 2 {
+                self.status = format!(
+                    "Dual-board mode needs two serial ports; detected {}.",
+                    ports.len()
+                );
+                self.step = Step::Finished;
+                return;
+            }
+            (ports[0].clone(), Some(ports[1].clone()))
+        } else {
+            let Some(port) = self.esp_port.clone() else {
+                self.status = "No serial port detected; cannot start recording.".into();
+                self.step = Step::Finished;
+                return;
+            };
+            (port, None)
+        };
+        let base_filename = if secondary_port.is_some() {
+            format!("{}_a", self.filename)
+        } else {
+            self.filename.clone()
+        };
+        // Names may include subfolders of the save dir, e.g. `walks/run1`.
+        let target = format!("{}/{}", self.save_dir, base_filename);
+        let target_dir = Path::new(&target).parent().unwrap_or(Path::new(&self.save_dir));
+        if let Err(msg) = csv_utils::ensure_writable_dir(target_dir) {
+            self.status = format!("{msg}; not recording.");
+            self.step = Step::Finished;
+            return;
+        }
+        let csv_filename = self
+            .capture_format
+            .path_for(&format!("{}/{}.csv", self.save_dir, base_filename));
+        let rrd_filename = format!("{}/{}.rrd", self.save_dir, base_filename);
+        let targets = match &self.rerun_sink {
+            RerunSink::File => format!("{} and {}", csv_filename, rrd_filename),
+            RerunSink::Connect(Some(url)) => format!("{} and Rerun viewer at {}", csv_filename, url),
+            RerunSink::Connect(None) => format!("{} and Rerun viewer", csv_filename),
+            RerunSink::Spawn => format!("{} and a spawned Rerun viewer", csv_filename),
+            RerunSink::Disabled => csv_filename.clone(),
+        };
+        self.status = format!(
+            "Recording to {} for {}s on port {}...",
+            targets, secs, port
+        );
+        if let TriggerMode::Armed { subcarrier, threshold, .. } = trigger_mode {
+            self.status.push_str(&format!(
+                " Armed: waiting for subcarrier {} amplitude >= {}.",
+                subcarrier, threshold
+            ));
+        }
+        self.step = Step::Recording;
+        self.recording_name = base_filename;
+        self.recording_start = Some(SystemTime::now());
+        self.recording_secs = secs;
+        self.recording_armed = trigger_mode != TriggerMode::Off;
+        self.auto_switched = false;
+        self.full_screen_plot = false;
+        self.plot_paused = false;
+        self.plot_points.clear();
+        self.view_bounds = None;
+        self.events.clear();
+        self.region = None;
+        self.heatmap_data = Heatmap::new(vec![]); // Clear heatmap
+        self.spectrum_data = Heatmap::new(vec![]);
+        self.frame_amplitudes.clear();
+        self.spectrogram.clear();
+        self.plot_rx = None;
+        self.heatmap_rx = None; // Reset heatmap receiver
+        self.live_heatmap.clear();
+        self.recording_stats = RecordingStats::default();
+        self.pending_result = None;
+        self.secondary = None;
+        
+        let (tx, rx) = mpsc::channel();
+        self.worker_done_rx = Some(rx);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.recording_cancel = Some(cancel.clone());
+        
+        let (plot_tx, plot_rx) = mpsc::channel();
+        self.plot_rx = Some(plot_rx);
+        
+        let (heatmap_tx, heatmap_rx) = mpsc::channel(); // Create heatmap channel
+        self.heatmap_rx = Some(heatmap_rx);
+
+        let (stats_tx, stats_rx) = mpsc::channel();
+        self.stats_rx = Some(stats_rx);
+
+        // Parser warnings from either board end up in the status log.
+        let (log_tx, log_rx) = mpsc::channel();
+        self.log_rx = Some(log_rx);
+
+        // Both boards wait for the same wall-clock instant before starting,
+        // far enough ahead that a board resetting slowly still makes it.
+        let start_at = secondary_port
+            .as_ref()
+            .map(|_| SystemTime::now() + parse_data::BOARD_SETUP_TIME);
+
+        if let Some(sec_port) = secondary_port {
+            let (sec_tx, sec_rx) = mpsc::channel();
+            let (sec_stats_tx, sec_stats_rx) = mpsc::channel();
+            let sec_name = format!("{}_b", self.filename);
+            self.status.push_str(&format!(
+                " Board B: {}/{}.csv on port {}.",
+                self.save_dir, sec_name, sec_port
+            ));
+            let config = self
+                .recording_config(sec_port.clone(), &sec_name, secs)
+                .decimation(decimation)
+                .stats_tx(sec_stats_tx)
+                .log_tx(log_tx.clone())
+                .start_at(start_at)
+                .trigger_mode(trigger_mode)
+                .cancel(cancel.clone());
+            spawn_recorder(config, sec_tx);
+            self.secondary = Some(SecondaryWorker {
+                port: sec_port,
+                done_rx: Some(sec_rx),
+                stats_rx: Some(sec_stats_rx),
+                stats: RecordingStats::default(),
+                result: None,
+            });
+        }
+
+        let config = self
+            .recording_config(port, &self.recording_name, secs)
+            .plot_tx(plot_tx)
+            .heatmap_tx(heatmap_tx)
+            .decimation(decimation)
+            .stats_tx(stats_tx)
+            .log_tx(log_tx)
+            .start_at(start_at)
+            .trigger_mode(trigger_mode)
+            .cancel(cancel);
+        spawn_recorder(config, tx);
+    }
+
+    /// Mark the current moment of the recording with the selected label in
+    /// `<name>.events.csv`. The time uses the same basis as the live series.
+    fn annotate_event(&mut self) {
+        let t = match (self.plot_points.last(), self.recording_start) {
+            (Some((t, _)), _) => *t,
+            (None, Some(start)) => SystemTime::now()
+                .duration_since(start)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0),
+            (None, None) => return,
+        };
+        let unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let event = events::Event {
+            t,
+            unix_ms,
+            label: EVENT_LABELS[self.event_label_idx].to_string(),
+        };
+        let csv_filename = format!("{}/{}.csv", self.save_dir, self.recording_name);
+        let path = events::events_path_for(&csv_filename);
+        match events::append_event(&path, &event) {
+            Ok(()) => {
+                self.status = format!("Annotated '{}' at {:.2}s", event.label, event.t);
+                self.events.push(event);
+            }
+            Err(e) => {
+                self.status = format!("Failed to write annotation to {}: {}", path, e);
+            }
+        }
+    }
+
+    /// Parse the trigger controls; an empty threshold means no trigger.
+    fn trigger_mode(&self) -> std::result::Result<TriggerMode, String> {
+        let threshold = self.trigger_threshold_input.trim();
+        if threshold.is_empty() {
+            return Ok(TriggerMode::Off);
+        }
+        let threshold: f32 = threshold
+            .parse()
+            .map_err(|_| format!("Trigger amplitude must be a number, got '{}'.", threshold))?;
+        let subcarrier: usize = self
+            .trigger_subcarrier_input
+            .trim()
+            .parse()
+            .map_err(|_| "Trigger subcarrier must be a non-negative integer.".to_string())?;
+        if let Some(n) = self.detected_subcarriers
+            && subcarrier >= n
+        {
+            return Err(format!("Trigger subcarrier must be below {}, the board's subcarrier count.", n));
+        }
+        Ok(TriggerMode::Armed {
+            subcarrier,
+            threshold,
+            pre_trigger: PRE_TRIGGER,
+            timeout: Some(TRIGGER_TIMEOUT),
+        })
+    }
+
+    /// Parse the "Duration (s)" control; must be at least 1.
+    fn duration_secs(&self) -> std::result::Result<u64, String> {
+        if self.duration_input.trim().is_empty() {
+            return Err("Duration cannot be empty.".to_string());
+        }
+        match self.duration_input.parse::<u64>() {
+            Ok(v) if v > 0 => Ok(v),
+            _ => Err("Duration must be a positive integer.".to_string()),
+        }
+    }
+
+    /// Parse the "Keep every" control; must be at least 1.
+    fn decimation(&self) -> std::result::Result<usize, String> {
+        match self.decimation_input.trim().parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err("Keep every must be a positive integer.".to_string()),
+        }
+    }
+
+    /// The controls a preset captures.
+    fn current_preset(&self) -> Preset {
+        Preset {
+            wifi_mode: self.wifi_mode,
+            ssid: self.ssid.clone(),
+            duration_secs: self.duration_input.parse().unwrap_or(0),
+            subcarrier: self.subcarrier,
+            view: self.view,
+            metric: self.metric,
+        }
+    }
+
+    /// Add the plotted subcarrier to the bookmarks, or remove it, and save
+    /// them to the data directory.
+    fn toggle_bookmark(&mut self) {
+        let sc = self.subcarrier;
+        let added = self.bookmarks.insert(sc);
+        if !added {
+            self.bookmarks.remove(&sc);
+        }
+        match bookmarks::save_bookmarks(&self.save_dir, &self.bookmarks) {
+            Ok(()) => self.push_log(format!(
+                "{} subcarrier {}",
+                if added { "Bookmarked" } else { "Removed bookmark for" },
+                sc
+            )),
+            Err(e) => self.status = format!("Failed to save bookmarks: {}", e),
+        }
+    }
+
+    /// Plot the next bookmarked subcarrier, reloading the current capture.
+    fn next_bookmark(&mut self) {
+        let Some(sc) = bookmarks::next_bookmark(&self.bookmarks, self.subcarrier) else {
+            self.status = "No bookmarked subcarriers; press * to bookmark the plotted one.".into();
+            return;
+        };
+        self.subcarrier = sc;
+        // Auto subcarrier would pick its own on reload.
+        self.auto_subcarrier = false;
+        if self.filename.trim().is_empty() {
+            self.push_log(format!("Subcarrier {} (bookmark)", sc));
+        } else {
+            self.load_file_for_plot();
+        }
+    }
+
+    /// Save the current controls as preset `name`.
+    fn save_preset(&mut self, name: &str) {
+        match presets::save_preset(&self.save_dir, name, &self.current_preset()) {
+            Ok(()) => {
+                self.presets = presets::list_presets(&self.save_dir);
+                self.push_log(format!("Saved preset {}", name));
+            }
+            Err(e) => self.status = format!("Failed to save preset {:?}: {}", name, e),
+        }
+    }
+
+    /// Apply preset `name` to the controls.
+    fn load_preset(&mut self, name: &str) {
+        let preset = match presets::load_preset(&self.save_dir, name, &self.current_preset()) {
+            Ok(preset) => preset,
+            Err(e) => {
+                self.status = format!("Failed to load preset {}: {}", name, e);
+                return;
+            }
+        };
+        self.wifi_mode = preset.wifi_mode;
+        self.ssid = preset.ssid;
+        if preset.duration_secs > 0 {
+            self.duration_input = preset.duration_secs.to_string();
+        }
+        self.subcarrier = preset.subcarrier;
+        self.view = preset.view;
+        self.metric = preset.metric;
+        self.field_cursors = [None; CONTROLS_LEN];
+        self.recheck_invalid_field();
+        if !self.filename.trim().is_empty() {
+            self.load_file_for_plot();
+        }
+        self.push_log(format!("Loaded preset {}", name));
+    }
+
+    /// Subcarrier range from the "Heatmap SC" controls; `None` when both
+    /// are empty. Clamping and `from > to` are handled where it is applied.
+    fn heatmap_subcarriers(&self) -> Option<(usize, usize)> {
+        if self.heatmap_lo_input.is_empty() && self.heatmap_hi_input.is_empty() {
+            return None;
+        }
+        let lo = self.heatmap_lo_input.parse().unwrap_or(0);
+        let hi = self.heatmap_hi_input.parse().unwrap_or(usize::MAX);
+        Some((lo, hi))
+    }
+
+    /// Capture settings for `<save_dir>/<name>.csv` (and `.rrd`) from the
+    /// current controls; live channels and timing are added by the caller.
+    fn recording_config(&self, port: String, name: &str, secs: u64) -> RecordingConfig {
+        RecordingConfig::new(port, format!("{}/{}.csv", self.save_dir, name))
+            .wifi_mode(self.wifi_mode)
+            .credentials(self.ssid.clone(), self.password.clone())
+            .duration_secs(secs)
+            .subcarrier(self.subcarrier)
+            .antenna(self.antenna_filter)
+            .quantity(self.view)
+            .metric(self.metric)
+            .plot_interval(self.plot_interval)
+            .heatmap_interval(self.heatmap_interval)
+            .heatmap_subcarriers(self.heatmap_subcarriers())
+            .calibration(self.calibration.clone())
+            .rerun_sink(self.rerun_sink.clone())
+            .rerun_entities(self.rerun_entities)
+            .rerun_tensor_every(self.rerun_tensor_every)
+            .raw_log(self.raw_log)
+            .value_format(self.value_format)
+            .host_time(self.host_time)
+            .format(self.capture_format)
+            .idle_timeout(self.idle_timeout)
+            .warmup_packets(self.warmup_packets)
+            .assert_dtr(self.assert_dtr)
+            .release_rts(self.release_rts)
+            .source(self.source)
+            .restart_after(self.restart_after)
+            .csi_config(self.csi_config)
     }
 
     /// If recording has been running for longer than the threshold, switch
@@ -758,23 +2701,33 @@ impl App {
                         dt.format("%Y-%m-%d %H:%M:%S"),
                         dt.timestamp_subsec_millis()
                     );
-                    return Some(format!("last {} | amp {:.3}", ts_str, a_last));
+                    return Some(format!(
+                        "last {} | {} {}",
+                        ts_str,
+                        display(*a_last),
+                        self.view.units(self.metric)
+                    ));
                 }
             }
-            return Some(format!("t {:.3}s | amp {:.3}", t_last, a_last));
+            return Some(format!("t {:.3}s | {} {}", t_last, display(*a_last), self.view.units(self.metric)));
         }
         None
     }
 
     /// Drain any pending plot points from the recording thread and append
     /// them to the in-memory buffer used for the chart. This is designed to
-    /// be called each UI loop so incoming data appears live.
-    fn poll_plot_data(&mut self) {
+    /// be called each UI loop so incoming data appears live. Returns whether
+    /// any point was added.
+    fn poll_plot_data(&mut self) -> bool {
+        let mut added = false;
         if let Some(rx) = &self.plot_rx {
             loop {
                 match rx.try_recv() {
+                    Ok(_) if self.plot_paused => {}
                     Ok(pt) => {
+                        added = true;
                         self.plot_points.push(pt);
+                        self.spectrogram.push(pt.1);
                         // Keep buffer bounded to avoid unbounded memory growth.
                         if self.plot_points.len() > 2000 {
                             // remove oldest
@@ -790,104 +2743,584 @@ impl App {
                 }
             }
         }
+        added
     }
 
-    /// Poll heatmap data from the recording thread
-    fn poll_heatmap_data(&mut self) {
-        if let Some(rx) = &self.heatmap_rx {
+    /// Poll heatmap rows from the recording thread, appending them to the
+    /// rolling window and dropping the oldest. Returns whether any arrived.
+    fn poll_heatmap_data(&mut self) -> bool {
+        let Some(rx) = &self.heatmap_rx else {
+            return false;
+        };
+        let mut added = false;
+        loop {
             match rx.try_recv() {
-                Ok(grid) => {
-                    self.heatmap_data = Heatmap { values: grid };
+                Ok(rows) => {
+                    for row in rows {
+                        // A new subcarrier count doesn't line up with the old rows.
+                        if self.live_heatmap.front().is_some_and(|first| first.len() != row.len()) {
+                            self.live_heatmap.clear();
+                        }
+                        self.live_heatmap.push_back(row);
+                    }
+                    added = true;
                 }
-                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Empty) => break,
                 Err(mpsc::TryRecvError::Disconnected) => {
                     self.heatmap_rx = None;
+                    break;
+                }
+            }
+        }
+        if !added {
+            return false;
+        }
+        let excess = self.live_heatmap.len().saturating_sub(parse_data::LIVE_HEATMAP_ROWS);
+        self.live_heatmap.drain(..excess);
+        let mut rows: Vec<Vec<f32>> = self.live_heatmap.iter().cloned().collect();
+        // Scaled over the frames on screen, as a loaded capture is over all of its own.
+        let grid = if self.background_subtract {
+            // Relative to the mean of the frames on screen.
+            dsp::subtract_column_means(&mut rows);
+            read_data::normalize_heatmap_centered(&rows)
+        } else {
+            read_data::normalize_heatmap(&rows)
+        };
+        // The worker clamps the range to the frame; the start only moves
+        // when it lies past the last subcarrier.
+        let first = self.heatmap_subcarriers().map_or(0, |(lo, hi)| lo.min(hi));
+        let columns = grid.first().map_or(0, Vec::len);
+        self.heatmap_data = Heatmap::new(grid)
+            .with_cell_width(self.heatmap_cell_width)
+            .with_column_kinds(subcarrier_layout::column_kinds(first, columns));
+        true
+    }
+
+    /// Poll live capture statistics from the recording thread and surface
+    /// the packet count and drop estimate in the status line.
+    fn poll_recording_stats(&mut self) {
+        if let Some(rx) = &self.log_rx {
+            let warnings: Vec<String> = rx.try_iter().collect();
+            for warning in warnings {
+                self.push_log(format!("Parse: {}", warning));
+            }
+        }
+        if let Some(rx) = &self.stats_rx {
+            let mut latest = None;
+            loop {
+                match rx.try_recv() {
+                    Ok(stats) => latest = Some(stats),
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        self.stats_rx = None;
+                        break;
+                    }
+                }
+            }
+            if let Some(stats) = latest {
+                self.recording_stats = stats;
+                // The recorder clamped an out-of-range subcarrier (and logged why).
+                if let Some(sc) = stats.plot_subcarrier {
+                    self.subcarrier = sc;
+                }
+                if self.step == Step::Recording {
+                    self.status = format!(
+                        "Recording... {} packets, ~{} dropped ({} gaps).{}",
+                        stats.frames_logged,
+                        stats.dropped_packets,
+                        stats.gaps_detected,
+                        stuck_warning(stats.stuck_runs)
+                    );
+                    if stats.malformed_lines > 0 {
+                        self.status
+                            .push_str(&format!(" {} malformed lines (see log).", stats.malformed_lines));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Poll the secondary board's progress and completion in dual-board mode.
+    fn poll_secondary(&mut self) {
+        let Some(sec) = &mut self.secondary else {
+            return;
+        };
+        if let Some(rx) = &sec.stats_rx {
+            while let Ok(stats) = rx.try_recv() {
+                sec.stats = stats;
+            }
+        }
+        if let Some(rx) = &sec.done_rx {
+            match rx.try_recv() {
+                Ok(res) => {
+                    sec.result = Some(res);
+                    sec.done_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    sec.result = Some(Err(RecordError::WorkerLost));
+                    sec.done_rx = None;
                 }
             }
         }
     }
 
-    /// Check if the worker thread has finished.
+    /// Check if the worker thread(s) have finished.
     fn check_worker(&mut self) {
+        self.poll_secondary();
         if let Some(rx) = &self.worker_done_rx {
             match rx.try_recv() {
-                Ok(Ok(())) => {
-                    self.status = "Recording finished successfully.".into();
-                    self.step = Step::Finished;
-                    // Try to load the recorded CSV into the plot area
-                    self.load_file_for_plot();
-                    // Reset UI auto-switch state
-                    self.recording_start = None;
-                    self.auto_switched = false;
-                    self.full_screen_plot = false;
-                    self.worker_done_rx = None;
-                }
-                Ok(Err(err)) => {
-                    self.status = format!("Recording failed: {err}");
-                    self.step = Step::Finished;
-                    self.recording_start = None;
-                    self.auto_switched = false;
-                    self.full_screen_plot = false;
+                Ok(res) => {
+                    self.pending_result = Some(res);
                     self.worker_done_rx = None;
                 }
                 Err(mpsc::TryRecvError::Empty) => {
                     // still running
                 }
                 Err(mpsc::TryRecvError::Disconnected) => {
-                    self.status = "Worker thread disconnected unexpectedly.".into();
-                    self.step = Step::Finished;
+                    self.pending_result = Some(Err(RecordError::WorkerLost));
                     self.worker_done_rx = None;
                 }
             }
         }
+        // In dual-board mode, wait until board B is done too.
+        if self.secondary.as_ref().is_some_and(|sec| sec.result.is_none()) {
+            return;
+        }
+        let Some(result) = self.pending_result.take() else {
+            return;
+        };
+        match result {
+            Ok(stats) => {
+                self.recording_stats = stats;
+                if let Some(sc) = stats.plot_subcarrier {
+                    self.subcarrier = sc;
+                }
+                self.step = Step::Finished;
+                // Try to load the recorded CSV into the plot area
+                let name = self.recording_name.clone();
+                self.load_capture(&name);
+                // The reload above already warns about identical frames.
+                self.status = format!(
+                    "Recording {}: quality {}. {} packets, ~{} dropped ({} gaps).{} {}",
+                    match self.idle_timeout {
+                        Some(idle) if stats.stopped_idle => {
+                            format!("stopped early, no packets for {} s", idle.as_secs())
+                        }
+                        _ => "finished".to_string(),
+                    },
+                    QualityScore::from_stats(&stats),
+                    stats.frames_logged,
+                    stats.dropped_packets,
+                    stats.gaps_detected,
+                    match stats.warmup_skipped {
+                        0 => String::new(),
+                        n => format!(" {} warm-up packets discarded.", n),
+                    },
+                    self.status
+                );
+            }
+            Err(RecordError::OpenPort { port, source }) => {
+                self.status = format!(
+                    "Could not open {}: {}. Rescanned ports; press i to pick another or Ctrl+S to retry.",
+                    port, source
+                );
+                self.step = Step::Finished;
+                self.refresh_esp(true);
+            }
+            Err(RecordError::NoPacketsReceived) => {
+                self.status = format!(
+                    "Recording finished but no packets arrived on {}. Check the board streams CSI (WiFi mode, channel, traffic nearby).",
+                    self.recording_name
+                );
+                self.step = Step::Finished;
+            }
+            Err(RecordError::TriggerTimedOut) => {
+                self.status = format!(
+                    "The trigger didn't fire within {} min; nothing was recorded. Lower Trigger amp or pick another Trigger SC.",
+                    TRIGGER_TIMEOUT.as_secs() / 60
+                );
+                self.step = Step::Finished;
+            }
+            Err(RecordError::Cancelled) => {
+                self.step = Step::Finished;
+                let name = self.recording_name.clone();
+                self.load_capture(&name);
+                self.status = format!("Recording stopped early; the rows so far are kept. {}", self.status);
+            }
+            Err(err) => {
+                self.status = format!("Recording failed: {err}");
+                self.step = Step::Finished;
+            }
+        }
+        self.recording_cancel = None;
+        if let Some(SecondaryWorker { result: Some(res), .. }) = &self.secondary {
+            match res {
+                Ok(stats) => self.status.push_str(&format!(
+                    " Board B: quality {}, {} packets, ~{} dropped.{}",
+                    QualityScore::from_stats(stats),
+                    stats.frames_logged,
+                    stats.dropped_packets,
+                    stuck_warning(stats.stuck_runs)
+                )),
+                Err(err) => self.status.push_str(&format!(" Board B failed: {err}")),
+            }
+        }
+        // Reset UI auto-switch state
+        self.recording_start = None;
+        self.auto_switched = false;
+        // With the preference on, a full-screen view stays up showing the
+        // reloaded capture until Enter.
+        if !self.keep_full_screen {
+            self.full_screen_plot = false;
+        }
+    }
+
+    /// Start (or resume, if `<save_dir>/<filename>.schedule` has an
+    /// unfinished run of the same schedule) recording on a schedule.
+    fn start_schedule(&mut self, schedule: Schedule) {
+        let name = self.filename.trim().to_string();
+        let progress = ScheduleProgress::resume_or_start(&self.save_dir, &name, schedule);
+        if progress.completed > 0 {
+            self.push_log(format!(
+                "Resuming schedule {}: {}/{} recordings done",
+                name, progress.completed, schedule.count
+            ));
+        }
+        let due = progress.wait().is_zero();
+        self.schedule_run = Some(progress);
+        // Otherwise check_schedule starts it once a running probe is done.
+        if due && self.probe_rx.is_none() {
+            self.start_scheduled_run();
+        }
+    }
+
+    /// Count a scheduled recording once it has ended, and start the next
+    /// one when it is due.
+    fn check_schedule(&mut self) {
+        if self.step == Step::Recording {
+            return;
+        }
+        let Some(progress) = &mut self.schedule_run else {
+            return;
+        };
+        if let Some(started) = self.schedule_run_started.take() {
+            progress.record_done(started);
+            let saved = progress.save(&self.save_dir);
+            let (done, count) = (progress.is_done(), progress.schedule.count);
+            if let Err(e) = saved {
+                self.push_log(format!("Failed to save schedule progress: {e}"));
+            }
+            if done {
+                self.status.push_str(&format!(" Schedule finished: {} recordings.", count));
+                self.end_schedule();
+            }
+            return;
+        }
+        // A probe holds the port for a few seconds at most.
+        if progress.wait().is_zero() && self.probe_rx.is_none() {
+            // The output size was confirmed when the schedule started.
+            self.size_confirm = Some(progress.schedule.on_secs);
+            self.start_scheduled_run();
+        }
+    }
+
+    fn start_scheduled_run(&mut self) {
+        let Some(progress) = &self.schedule_run else {
+            return;
+        };
+        let started = SystemTime::now();
+        let secs = progress.schedule.on_secs;
+        self.filename = schedule::run_name(&progress.name, started);
+        self.start_recording(secs);
+        if self.step == Step::Recording {
+            self.schedule_run_started = Some(started);
+        } else {
+            self.status.push_str(" Schedule stopped.");
+            self.end_schedule();
+        }
+    }
+
+    /// Stop scheduling further recordings; one in progress runs to its end.
+    /// The saved progress is kept, so Ctrl+S resumes the schedule.
+    fn cancel_schedule(&mut self) {
+        if let Some(progress) = &self.schedule_run {
+            self.push_log(format!(
+                "Schedule {} cancelled after {}/{} recordings",
+                progress.name, progress.completed, progress.schedule.count
+            ));
+        }
+        self.end_schedule();
+    }
+
+    fn end_schedule(&mut self) {
+        if let Some(progress) = self.schedule_run.take() {
+            self.filename = progress.name;
+        }
+        self.schedule_run_started = None;
+    }
+
+    /// Probe a newly connected serial board, unless a recording has it.
+    fn probe_on_connect(&mut self) {
+        if self.source == InputSource::Serial && self.step != Step::Recording {
+            self.start_probe();
+        }
+    }
+
+    /// Read a few packets from the board in the background to learn its
+    /// subcarrier count; [`Self::check_probe`] reports it.
+    fn start_probe(&mut self) {
+        if self.step == Step::Recording || self.probe_rx.is_some() {
+            self.status = "The board is busy; detect subcarriers once it is free.".into();
+            return;
+        }
+        let port = match self.source {
+            InputSource::Udp(_) => self.source.to_string(),
+            InputSource::Serial => {
+                let Some(port) = self.esp_port.clone() else {
+                    self.status = "No serial port detected; cannot detect subcarriers.".into();
+                    return;
+                };
+                port
+            }
+        };
+        let config = self.recording_config(port.clone(), "probe", parse_data::PROBE_SECS);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(parse_data::probe_subcarriers(&config));
+        });
+        self.probe_rx = Some(rx);
+        self.status = format!("Detecting subcarriers on {}...", port);
+    }
+
+    /// Report a finished probe and fit the selected subcarrier to its count.
+    fn check_probe(&mut self) {
+        let Some(rx) = &self.probe_rx else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err(RecordError::WorkerLost),
+        };
+        self.probe_rx = None;
+        match result {
+            Ok(probe) => {
+                self.detected_subcarriers = Some(probe.subcarriers);
+                self.status = format!(
+                    "Detected {} subcarriers ({} CSI values per frame, {} packets).",
+                    probe.subcarriers, probe.csi_values, probe.packets
+                );
+                if probe.subcarriers > 0 && self.subcarrier >= probe.subcarriers {
+                    self.subcarrier = probe.subcarriers - 1;
+                    self.status
+                        .push_str(&format!(" Plotting subcarrier {} instead.", self.subcarrier));
+                }
+                self.push_log(self.status.clone());
+            }
+            Err(RecordError::NoPacketsReceived) => {
+                self.status = format!(
+                    "No packets within {} s; could not detect subcarriers. Press Ctrl+D to retry.",
+                    parse_data::PROBE_SECS
+                );
+            }
+            Err(err) => self.status = format!("Subcarrier detection failed: {err}"),
+        }
+    }
+
+    /// Copy the full path of the recording or loaded capture to the
+    /// clipboard, or the status text when there is no capture file.
+    fn copy_to_clipboard(&mut self) {
+        let name = if self.step == Step::Recording || self.filename.trim().is_empty() {
+            self.recording_name.trim().to_string()
+        } else {
+            self.filename.trim().to_string()
+        };
+        let path = (!name.is_empty()).then(|| self.capture_path(&name));
+        let (what, text) = match path.and_then(|path| fs::canonicalize(path).ok()) {
+            Some(path) => ("capture path", path.display().to_string()),
+            None => ("status text", self.status.clone()),
+        };
+        self.status = match clipboard::copy(&text) {
+            Ok(Copied::Tool(tool)) => format!("Copied {} to the clipboard ({}): {}", what, tool, text),
+            Ok(Copied::Terminal) => format!(
+                "Sent {} to the terminal clipboard; if it isn't there, the terminal blocks OSC 52: {}",
+                what, text
+            ),
+            Err(e) => format!("Could not copy {} to the clipboard ({}): {}", what, e, text),
+        };
     }
 
     fn load_file_for_plot(&mut self) {
-        let filename = self.filename.trim();
+        let filename = self.filename.clone();
+        self.load_capture(&filename);
+    }
+
+    /// `<save_dir>/<name>.csv`, or `.csv.gz` or `.parquet` if only that exists.
+    fn capture_path(&self, name: &str) -> String {
+        let csv_path = format!("{}/{}.csv", self.save_dir, name);
+        let gz_path = format!("{}.gz", csv_path);
+        let parquet_path = CaptureFormat::Parquet.path_for(&csv_path);
+        if Path::new(&csv_path).exists() {
+            csv_path
+        } else if Path::new(&gz_path).exists() {
+            gz_path
+        } else if Path::new(&parquet_path).exists() {
+            parquet_path
+        } else {
+            csv_path
+        }
+    }
+
+    /// Load `<save_dir>/<filename>.csv` (or `.csv.gz` or `.parquet` if only
+    /// that exists) into the plot and heatmap.
+    fn load_capture(&mut self, filename: &str) {
+        let filename = filename.trim();
         if filename.is_empty() {
-            self.status = "Filename cannot be empty.".into();
+            self.reject_field(5, "Filename cannot be empty.".into());
             return;
         }
-        let path = format!("{}/{}.csv", SAVE_DIR, filename);
-        match read_data::load_csv_amplitude_series(&path, self.subcarrier) {
-            Ok(points) => {
-                if points.is_empty() {
-                    self.status = format!("File {} loaded but contained no valid data.", path);
-                } else {
-                    self.plot_points = points;
-                    self.status = format!(
-                        "Loaded {} samples from {} (subcarrier {}).",
-                        self.plot_points.len(),
-                        path,
-                        self.subcarrier
-                    );
+        let csv_path = format!("{}/{}.csv", self.save_dir, filename);
+        let path = self.capture_path(filename);
+        self.clamp_offer = None;
+        // Parsed once; the plot, heatmap and checks below all derive from it.
+        let packets = match read_data::load_csv_packets(&path) {
+            Ok(packets) => packets,
+            Err(e) => {
+                self.status = format!("Failed to load {}: {}", path, e);
+                // Don't step through the previous capture's frames.
+                self.frame_amplitudes.clear();
+                return;
+            }
+        };
+        // Every subcarrier's amplitude series, once: they feed the heatmap,
+        // and the auto subcarrier is the most variable one, plotted directly
+        // when plotting amplitude.
+        let calibration = self.calibration.as_ref();
+        let (times, series) = read_data::all_amplitudes_from_packets(&packets, self.antenna_filter, self.metric, calibration);
+        let mut auto_points = None;
+        if self.auto_subcarrier
+            && !times.is_empty()
+            && let Some(sc) = dsp::most_variable_series(&series)
+        {
+            self.subcarrier = sc;
+            if self.view == Quantity::Amplitude {
+                auto_points = Some(times.into_iter().zip(series[sc].iter().copied()).collect());
+            }
+        }
+        let loaded = match auto_points {
+            Some(points) => Ok(points),
+            None => read_data::series_from_packets(
+                &packets,
+                self.subcarrier,
+                self.antenna_filter,
+                self.view,
+                self.metric,
+                calibration,
+            ),
+        };
+        match loaded {
+            Ok(mut points) => {
+                if self.zscore {
+                    dsp::zscore_series(&mut points);
+                }
+                self.plot_points = points;
+                self.view_bounds = None;
+                self.status = format!(
+                    "Loaded {} {} samples from {} (subcarrier {}{}).",
+                    self.plot_points.len(),
+                    self.view.label().to_lowercase(),
+                    path,
+                    self.subcarrier,
+                    if self.auto_subcarrier { ", most variable" } else { "" }
+                );
+                let mut stuck = StuckFrameDetector::new();
+                for packet in &packets {
+                    stuck.observe(&packet.csi_values);
                 }
+                self.status.push_str(&stuck_warning(stuck.stuck_runs));
+                self.status.push_str(&row_count_warning(&csv_path, packets.len()));
+                self.step = Step::Finished;
+            }
+            Err(ReadError::NoValidRows) => {
+                self.status = format!("File {} loaded but contained no valid data.", path);
+                self.step = Step::Finished;
+            }
+            Err(ReadError::SubcarrierOutOfRange {
+                requested,
+                available,
+            }) => {
+                let max_sc = available.saturating_sub(1);
+                self.status = format!(
+                    "Subcarrier {} out of range for {} ({} subcarriers, valid 0..={}). Press k to use {}.",
+                    requested, path, available, max_sc, max_sc
+                );
+                self.clamp_offer = Some((filename.to_string(), max_sc));
                 self.step = Step::Finished;
             }
             Err(e) => {
                 self.status = format!("Failed to load {}: {}", path, e);
             }
         }
-        // Also try to load heatmap data from the same file
-        self.load_heatmap_data(&path);
+        // Overlay annotations from the matching `<name>.events.csv`, if any.
+        self.events = events::load_events(&events::events_path_for(&csv_path)).unwrap_or_default();
+        self.load_heatmap_data(read_data::frame_rows(&series));
+        self.load_compare();
     }
 
-    /// Load heatmap data from a CSV file. Expects a grid of 0–100 values.
-    fn load_heatmap_data(&mut self, path: &str) {
-        match read_data::load_csv_heatmap(path) {
-            Ok(values) if !values.is_empty() => {
-                self.heatmap_data = Heatmap { values };
+    /// Show a loaded capture's per-frame amplitudes (one row per frame,
+    /// per `metric`) in the frame view, spectrum strip and heatmap. Without
+    /// rows the frame view is emptied but the current heatmap is kept.
+    fn load_heatmap_data(&mut self, mut rows: Vec<Vec<f32>>) {
+        if rows.is_empty() {
+            self.frame_amplitudes.clear();
+            return;
+        }
+        self.frame_amplitudes = rows.clone();
+        self.frame_index = self.frame_index.min(rows.len() - 1);
+        let first = self
+            .heatmap_subcarriers()
+            .map_or(0, |(lo, hi)| read_data::clamp_subcarrier_range(lo, hi, rows[0].len()).start);
+        read_data::slice_subcarriers(&mut rows, self.heatmap_subcarriers());
+        let kinds = subcarrier_layout::column_kinds(first, rows[0].len());
+        // Averaged before z-scoring, which would flatten every mean to 0.
+        let means = dsp::column_means(&rows);
+        self.spectrum_data = Heatmap::new(read_data::normalize_heatmap(&[means]))
+            .with_cell_width(self.heatmap_cell_width)
+            .with_column_kinds(kinds.clone());
+        let grid = if self.background_subtract {
+            dsp::subtract_column_means(&mut rows);
+            read_data::normalize_heatmap_centered(&rows)
+        } else {
+            if self.zscore {
+                dsp::zscore_columns(&mut rows);
             }
-            Ok(_) => {
+            read_data::normalize_heatmap(&rows)
+        };
+        self.heatmap_data = Heatmap::new(grid)
+            .with_cell_width(self.heatmap_cell_width)
+            .with_column_kinds(kinds);
+    }
 
-            }
-            Err(e) => {
-                self.status = format!("Failed to load heatmap from {}: {}", path, e)
-            }
+    /// Append a message to the status log panel, keeping it bounded.
+    fn push_log(&mut self, msg: String) {
+        let ts = Local::now().format("%H:%M:%S");
+        self.status_log.push(format!("{} {}", ts, msg));
+        if self.status_log.len() > STATUS_LOG_CAPACITY {
+            self.status_log.remove(0);
         }
     }
 
-    fn refresh_esp(&mut self) {
+    /// Rescan for the ESP port, at most once per [`PORT_SCAN_INTERVAL`]
+    /// unless `force` is set.
+    fn refresh_esp(&mut self, force: bool) {
+        if self.port_pinned {
+            return;
+        }
+        if !force && self.last_port_scan.is_some_and(|at| at.elapsed() < PORT_SCAN_INTERVAL) {
+            return;
+        }
+        self.last_port_scan = Some(Instant::now());
         let old = self.esp_port.clone();
         let new = esp_port::find_esp_port();
 
@@ -896,6 +3329,7 @@ impl App {
             match (&old, &new) {
                 (None, Some(p)) => {
                     self.status = format!("ESP connected on {p}");
+                    self.probe_on_connect();
                 }
                 (Some(_), None) => {
                     self.status = "ESP disconnect".into();
@@ -903,30 +3337,324 @@ impl App {
                 _ => {}
             }
         }
-        self.esp_port = esp_port::find_esp_port();
     }
 
     fn quit(&mut self) {
         self.running = false;
     }
 
-    fn list_saved_files() -> Vec<String> {
-        fs::read_dir(SAVE_DIR)
-            .map(|entries| {
-                entries
-                    .flatten()
-                    .filter(|entry| entry.metadata().map(|m| m.is_file()).unwrap_or(false))
-                    .filter_map(|entry| {
-                        entry.file_name().into_string().ok().and_then(|name| {
-                            if name.ends_with(".csv") || name.ends_with(".rrd") {
-                                Some(name)
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                    .collect()
+    /// Saved `.csv`/`.csv.gz`/`.parquet`/`.rrd` files, including those in subfolders, as sorted
+    /// paths relative to the save dir (`/`-separated).
+    fn list_saved_files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        collect_saved_files(Path::new(&self.save_dir), "", &mut files);
+        match self.file_sort {
+            FileSort::Newest => files.sort_by(|a, b| {
+                b.1.modified().ok().cmp(&a.1.modified().ok()).then(a.0.cmp(&b.0))
+            }),
+            FileSort::Name => files.sort_by(|a, b| a.0.cmp(&b.0)),
+            FileSort::Size => files.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0))),
+        }
+        files.into_iter().map(|(name, _)| name).collect()
+    }
+}
+
+/// Capture name of a saved file: its path without `.csv.gz` or the last
+/// extension.
+fn capture_name(file: &str) -> &str {
+    if let Some(stem) = file.strip_suffix(".csv.gz") {
+        stem
+    } else if let Some(pos) = file.rfind('.') {
+        &file[..pos]
+    } else {
+        file
+    }
+}
+
+/// Scroll offset keeping `selected` inside a `rows`-tall window over `len`
+/// items, moving `offset` as little as possible.
+fn scroll_to_show(offset: usize, selected: usize, rows: usize, len: usize) -> usize {
+    let offset = if selected < offset {
+        selected
+    } else if selected >= offset + rows {
+        selected + 1 - rows
+    } else {
+        offset
+    };
+    offset.min(len.saturating_sub(rows))
+}
+
+/// Parse `90`, `90.5`, `1:30` or `0:01:30` into seconds.
+fn parse_time_input(input: &str) -> Option<f64> {
+    let mut secs = 0.0;
+    let mut fields = 0;
+    for field in input.trim().split(':') {
+        let value: f64 = field.parse().ok().filter(|v: &f64| *v >= 0.0)?;
+        secs = secs * 60.0 + value;
+        fields += 1;
+    }
+    (fields <= 3).then_some(secs)
+}
+
+/// `1536` -> `1.5 KiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Status suffix warning about runs of identical CSI frames, empty if none.
+fn stuck_warning(stuck_runs: u64) -> String {
+    if stuck_runs == 0 {
+        return String::new();
+    }
+    format!(" WARNING: {stuck_runs} run(s) of identical CSI frames; the ESP link may be stuck.")
+}
+
+/// Whether the Options text field at `idx` accepts the character `c`.
+fn field_accepts(idx: usize, c: char) -> bool {
+    match idx {
+        // Trigger amplitude threshold.
+        6 => c.is_ascii_digit() || c == '.',
+        // Duration and the other numeric fields.
+        4 | 7..=10 => c.is_ascii_digit(),
+        _ => true,
+    }
+}
+
+/// Warning when the manifest next to `csv_path` records more rows than
+/// were loaded, e.g. after a crash cut the capture short.
+fn row_count_warning(csv_path: &str, loaded: usize) -> String {
+    let written = Manifest::read(&manifest_path_for(csv_path))
+        .ok()
+        .and_then(|m| m.get("lines_written").and_then(|v| v.parse::<usize>().ok()));
+    match written {
+        Some(written) if written != loaded => format!(
+            " WARNING: manifest lists {written} rows but {loaded} were readable; the capture may be truncated."
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Whether low-latency mode should redraw now, `since_draw` after the last
+/// draw (`None` before the first): right away for input, within a frame
+/// for undrawn data, and every [`IDLE_REDRAW`] to keep clocks current.
+fn low_latency_draw_due(input_pending: bool, data_pending: bool, since_draw: Option<Duration>) -> bool {
+    let Some(since_draw) = since_draw else {
+        return true;
+    };
+    input_pending
+        || (data_pending && since_draw >= LOW_LATENCY_FRAME)
+        || since_draw >= IDLE_REDRAW
+}
+
+/// Run a capture on a worker thread, reporting the outcome on `done_tx`.
+fn spawn_recorder(
+    config: RecordingConfig,
+    done_tx: mpsc::Sender<std::result::Result<RecordingStats, RecordError>>,
+) {
+    thread::spawn(move || {
+        let res = parse_data::record_csi_to_file(config);
+        let _ = done_tx.send(res);
+    });
+}
+
+/// Recursively collect `.csv`/`.parquet`/`.rrd` files under `dir`, prefixing names
+/// with `prefix` (the path relative to the save dir), along with their
+/// metadata for sorting.
+fn collect_saved_files(dir: &Path, prefix: &str, out: &mut Vec<(String, fs::Metadata)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let relative = format!("{}{}", prefix, name);
+        // Symlinked directories aren't followed, so links can't loop.
+        if file_type.is_dir() {
+            collect_saved_files(&entry.path(), &format!("{}/", relative), out);
+        } else if let Ok(metadata) = entry.metadata()
+            && metadata.is_file()
+            && [".csv", ".csv.gz", ".parquet", ".rrd"].iter().any(|ext| name.ends_with(ext))
+            // Annotation sidecars aren't captures.
+            && !name.ends_with(events::EVENTS_SUFFIX)
+        {
+            out.push((relative, metadata));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_latency_draws_pending_data_within_a_frame() {
+        let ms = Duration::from_millis;
+        assert!(low_latency_draw_due(false, false, None));
+        assert!(low_latency_draw_due(true, false, Some(ms(1))));
+        assert!(!low_latency_draw_due(false, true, Some(ms(5))));
+        assert!(low_latency_draw_due(false, true, Some(ms(16))));
+        assert!(!low_latency_draw_due(false, false, Some(ms(100))));
+        assert!(low_latency_draw_due(false, false, Some(ms(250))));
+
+        // The run loop polling every LOW_LATENCY_POLL, with one batch of data
+        // drained at each offset after a draw: it must reach the screen
+        // within a frame, not wait for the idle redraw.
+        let poll = LOW_LATENCY_POLL.as_millis() as u64;
+        let worst = (0..IDLE_REDRAW.as_millis() as u64 / poll)
+            .map(|arrives| {
+                let (mut last_draw, mut pending) = (0, None);
+                for now in (poll..).step_by(poll as usize) {
+                    if now / poll == arrives + 1 {
+                        pending = Some(now);
+                    }
+                    if low_latency_draw_due(false, pending.is_some(), Some(ms(now - last_draw))) {
+                        if let Some(at) = pending {
+                            return now - at;
+                        }
+                        last_draw = now;
+                    }
+                }
+                unreachable!()
             })
-            .unwrap_or_default()
+            .max()
+            .unwrap();
+        assert!(ms(worst) <= LOW_LATENCY_FRAME, "worst data-to-draw {worst} ms");
+    }
+
+    #[test]
+    fn text_fields_edit_at_the_cursor() {
+        let mut app = App::with_save_dir(std::env::temp_dir().to_string_lossy());
+        app.nav_selected = 0;
+        app.nav_item_selected = 2;
+        app.ssid.clear();
+        for c in "ab".chars() {
+            assert!(app.edit_focused_field(KeyCode::Char(c)));
+        }
+        // Typing at the end leaves the cursor following the text.
+        assert_eq!(app.field_cursors[2], None);
+
+        // Multi-byte characters go in by character, not byte.
+        app.edit_focused_field(KeyCode::Left);
+        app.edit_focused_field(KeyCode::Char('é'));
+        assert_eq!((app.ssid.as_str(), app.field_cursors[2]), ("aéb", Some(2)));
+        app.edit_focused_field(KeyCode::Backspace);
+        assert_eq!((app.ssid.as_str(), app.field_cursors[2]), ("ab", Some(1)));
+        app.edit_focused_field(KeyCode::Home);
+        app.edit_focused_field(KeyCode::Backspace);
+        app.edit_focused_field(KeyCode::Delete);
+        assert_eq!((app.ssid.as_str(), app.field_cursors[2]), ("b", Some(0)));
+        app.edit_focused_field(KeyCode::Right);
+        app.edit_focused_field(KeyCode::Right);
+        app.edit_focused_field(KeyCode::Delete);
+        assert_eq!((app.ssid.as_str(), app.field_cursors[2]), ("b", None));
+        app.edit_focused_field(KeyCode::Home);
+        app.edit_focused_field(KeyCode::End);
+        assert_eq!(app.field_cursors[2], None);
+        assert!(!app.edit_focused_field(KeyCode::Up));
+
+        // Numeric fields swallow other characters; a now valid value clears
+        // the field's error highlight.
+        app.nav_item_selected = 4;
+        app.duration_input.clear();
+        app.reject_field(4, "Duration cannot be empty.".into());
+        assert!(app.edit_focused_field(KeyCode::Char('x')));
+        assert_eq!((app.duration_input.as_str(), app.invalid_field), ("", Some(4)));
+        app.edit_focused_field(KeyCode::Char('5'));
+        assert_eq!((app.duration_input.as_str(), app.invalid_field), ("5", None));
+
+        // Keys only edit while an Options text field has focus.
+        app.nav_item_selected = 1;
+        assert!(!app.edit_focused_field(KeyCode::Char('7')));
+        app.nav_item_selected = 4;
+        app.full_screen_plot = true;
+        assert!(!app.edit_focused_field(KeyCode::Char('7')));
+        assert_eq!(app.duration_input, "5");
+    }
+
+    #[test]
+    fn large_captures_need_a_second_start_with_the_same_duration() {
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 << 30), "3.0 GiB");
+
+        let mut app = App::with_save_dir(std::env::temp_dir().to_string_lossy());
+        app.rerun_sink = RerunSink::Disabled;
+        // 532 bytes per frame at 100 packets/s: just over 1 GiB.
+        app.start_recording(20_200);
+        assert_eq!(app.size_confirm, Some(20_200));
+        assert!(app.status.contains("~1.0 GiB"), "{}", app.status);
+        assert_ne!(app.step, Step::Recording);
+        // A different duration asks again instead of going ahead.
+        app.start_recording(40_000);
+        assert_eq!(app.size_confirm, Some(40_000));
+        assert!(app.status.contains("~2.0 GiB"), "{}", app.status);
+    }
+
+    #[test]
+    fn saved_files_skip_event_sidecars() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-app-files-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("day1")).unwrap();
+        for file in ["walk.csv", "walk.events.csv", "day1/sit.csv", "day1/sit.events.csv", "notes.txt"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let mut found = Vec::new();
+        collect_saved_files(&dir, "", &mut found);
+        let mut names: Vec<String> = found.into_iter().map(|(name, _)| name).collect();
+        names.sort();
+        assert_eq!(names, vec!["day1/sit.csv", "walk.csv"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plot_series_bounds_cover_the_compare_overlay() {
+        let mut app = App::with_save_dir(std::env::temp_dir().to_string_lossy());
+        app.plot_points = vec![(0.0, 2.0), (1.0, 4.0), (2.0, 3.0)];
+        app.compare_points = vec![(0.5, 9.0), (5.0, 20.0)];
+        let series = app.plot_series();
+        assert_eq!(series.x, [0.0, 2.0]);
+        // The compare point past the view doesn't stretch the y-axis.
+        assert_eq!(series.y, [0.0, 9.0]);
+        assert_eq!(series.compare, vec![(0.5, 9.0)]);
+        assert_eq!(series.latest, vec![(2.0, 3.0)]);
+
+        app.compare_points.clear();
+        app.plot_points = vec![(0.0, -0.5), (1.0, -0.25)];
+        assert_eq!(app.plot_series().y, [-0.5, 0.5]);
+    }
+
+    #[test]
+    fn failed_load_clears_the_frame_view() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-app-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("good.csv"), "esp_timestamp_us,rssi,i0,q0,i1,q1\n1000000,-40,3,4,1,0\n").unwrap();
+        std::fs::write(dir.join("empty.csv"), "esp_timestamp_us,rssi,i0,q0,i1,q1\n").unwrap();
+        let mut app = App::with_save_dir(dir.to_str().unwrap());
+
+        app.load_capture("good");
+        assert_eq!(app.frame_amplitudes, vec![vec![5.0, 1.0]]);
+        app.load_capture("missing");
+        assert!(app.frame_amplitudes.is_empty());
+
+        app.load_capture("good");
+        app.load_capture("empty");
+        assert!(app.frame_amplitudes.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
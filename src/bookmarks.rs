@@ -0,0 +1,61 @@
+use std::{collections::BTreeSet, fs, io, path::Path};
+
+/// File in the data directory holding the bookmarked subcarriers, as one
+/// comma-separated line. Shared by every capture in that directory.
+pub const BOOKMARKS_FILE: &str = "subcarrier_bookmarks";
+
+fn bookmarks_path(data_dir: &str) -> String {
+    format!("{}/{}", data_dir, BOOKMARKS_FILE)
+}
+
+/// Bookmarked subcarriers in `data_dir`; empty when there are none yet.
+/// Entries that aren't numbers are skipped.
+pub fn load_bookmarks(data_dir: &str) -> BTreeSet<usize> {
+    fs::read_to_string(bookmarks_path(data_dir))
+        .map(|content| content.split([',', '\n']).filter_map(|v| v.trim().parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Replace the bookmarks in `data_dir`, removing the file when none are left.
+pub fn save_bookmarks(data_dir: &str, bookmarks: &BTreeSet<usize>) -> io::Result<()> {
+    let path = bookmarks_path(data_dir);
+    if bookmarks.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(Path::new(data_dir))?;
+    let line: Vec<String> = bookmarks.iter().map(|sc| sc.to_string()).collect();
+    fs::write(path, line.join(",") + "\n")
+}
+
+/// The bookmark after `current`, wrapping around to the first.
+pub fn next_bookmark(bookmarks: &BTreeSet<usize>, current: usize) -> Option<usize> {
+    bookmarks.range(current + 1..).next().or_else(|| bookmarks.first()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_round_trip_and_cycle() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-bookmarks-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        assert!(load_bookmarks(dir).is_empty());
+
+        let bookmarks = BTreeSet::from([42, 3, 17]);
+        save_bookmarks(dir, &bookmarks).unwrap();
+        assert_eq!(load_bookmarks(dir), bookmarks);
+        save_bookmarks(dir, &BTreeSet::new()).unwrap();
+        assert!(load_bookmarks(dir).is_empty());
+
+        assert_eq!(next_bookmark(&bookmarks, 0), Some(3));
+        assert_eq!(next_bookmark(&bookmarks, 3), Some(17));
+        assert_eq!(next_bookmark(&bookmarks, 20), Some(42));
+        assert_eq!(next_bookmark(&bookmarks, 42), Some(3));
+        assert_eq!(next_bookmark(&BTreeSet::new(), 5), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+};
+
+use crate::csi_packet::CsiPacket;
+
+/// Per-subcarrier gain correction: `calibrated = (raw - offset) * scale`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub scale: Vec<f32>,
+    pub offset: Vec<f32>,
+}
+
+impl Calibration {
+    /// Identity calibration for `num_subcarriers` subcarriers.
+    pub fn identity(num_subcarriers: usize) -> Self {
+        Self {
+            scale: vec![1.0; num_subcarriers],
+            offset: vec![0.0; num_subcarriers],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scale.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scale.is_empty()
+    }
+
+    /// Apply the calibration to a row of amplitudes. Subcarriers beyond the
+    /// calibration vector are passed through unchanged.
+    pub fn apply(&self, amplitudes: &[f32]) -> Vec<f32> {
+        amplitudes
+            .iter()
+            .enumerate()
+            .map(|(k, &amp)| match (self.scale.get(k), self.offset.get(k)) {
+                (Some(s), Some(o)) => (amp - o) * s,
+                _ => amp,
+            })
+            .collect()
+    }
+
+    /// Calibrated power of subcarrier `k` from its raw power `I² + Q²`: the
+    /// amplitude is corrected as in [`Calibration::apply`], clamped at 0, and
+    /// squared back. Subcarriers beyond the calibration pass through.
+    pub fn apply_power(&self, k: usize, power: f32) -> f32 {
+        match (self.scale.get(k), self.offset.get(k)) {
+            (Some(s), Some(o)) => ((power.sqrt() - o) * s).max(0.0).powi(2),
+            _ => power,
+        }
+    }
+
+    /// [`Calibration::apply_power`] for a row of powers.
+    pub fn apply_to_powers(&self, powers: &[f32]) -> Vec<f32> {
+        powers.iter().enumerate().map(|(k, &p)| self.apply_power(k, p)).collect()
+    }
+
+    /// Estimate a calibration from a static reference capture.
+    ///
+    /// Each subcarrier's mean amplitude is measured and a scale is chosen so
+    /// that it matches the mean across all subcarriers, flattening the
+    /// per-subcarrier gain. Subcarriers with a zero mean keep a scale of 1.
+    pub fn estimate_from_packets(packets: &[CsiPacket]) -> Option<Self> {
+        let num_subcarriers = packets.iter().map(|p| p.csi_values.len() / 2).min()?;
+        if num_subcarriers == 0 {
+            return None;
+        }
+
+        let mut sums = vec![0.0f64; num_subcarriers];
+        for packet in packets {
+            for (k, amp) in packet.get_amplitudes().iter().take(num_subcarriers).enumerate() {
+                sums[k] += *amp as f64;
+            }
+        }
+        let means: Vec<f64> = sums.iter().map(|s| s / packets.len() as f64).collect();
+        let global_mean = means.iter().sum::<f64>() / num_subcarriers as f64;
+
+        let scale = means
+            .iter()
+            .map(|&m| if m > 0.0 { (global_mean / m) as f32 } else { 1.0 })
+            .collect();
+        Some(Self {
+            scale,
+            offset: vec![0.0; num_subcarriers],
+        })
+    }
+
+    /// Load a calibration file with one `subcarrier,scale,offset` line per
+    /// subcarrier. Lines starting with `#` and a `subcarrier,...` header are skipped.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut entries: Vec<(usize, f32, f32)> = Vec::new();
+
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("subcarrier") {
+                continue;
+            }
+            let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+            let parsed = match parts.as_slice() {
+                [k, scale, offset] => k
+                    .parse::<usize>()
+                    .ok()
+                    .zip(scale.parse::<f32>().ok())
+                    .zip(offset.parse::<f32>().ok())
+                    .map(|((k, s), o)| (k, s, o)),
+                _ => None,
+            };
+            match parsed {
+                Some(entry) => entries.push(entry),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}:{}: expected `subcarrier,scale,offset`", path, line_no + 1),
+                    ));
+                }
+            }
+        }
+
+        let len = entries.iter().map(|(k, _, _)| k + 1).max().unwrap_or(0);
+        let mut cal = Self::identity(len);
+        for (k, scale, offset) in entries {
+            cal.scale[k] = scale;
+            cal.offset[k] = offset;
+        }
+        Ok(cal)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut out = File::create(path)?;
+        writeln!(out, "subcarrier,scale,offset")?;
+        for (k, (scale, offset)) in self.scale.iter().zip(&self.offset).enumerate() {
+            writeln!(out, "{},{},{}", k, scale, offset)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(csi_values: Vec<i32>) -> CsiPacket {
+        CsiPacket {
+            esp_timestamp: 0,
+            rssi: -40,
+            csi_values,
+            antenna: None,
+            host_time_ms: None,
+        }
+    }
+
+    #[test]
+    fn scale_and_offset_are_applied_per_subcarrier() {
+        let cal = Calibration {
+            scale: vec![2.0, 0.5],
+            offset: vec![1.0, 0.0],
+        };
+        // The third subcarrier has no entry and passes through.
+        assert_eq!(cal.apply(&[3.0, 4.0, 7.0]), vec![4.0, 2.0, 7.0]);
+        assert_eq!(cal.apply_to_powers(&[9.0, 16.0, 49.0]), vec![16.0, 4.0, 49.0]);
+        // An amplitude below the offset clamps to 0 instead of squaring back up.
+        assert_eq!(cal.apply_power(0, 0.25), 0.0);
+        assert_eq!(Calibration::identity(2).apply(&[3.0, 4.0]), vec![3.0, 4.0]);
+    }
+
+    #[test]
+    fn estimate_flattens_the_mean_amplitude() {
+        // Amplitudes 5 and 10 on average (3-4-5 and 6-8-10 triangles), and a
+        // dead subcarrier.
+        let packets = [packet(vec![3, 4, 6, 8, 0, 0]), packet(vec![4, 3, 8, 6, 0, 0])];
+        let cal = Calibration::estimate_from_packets(&packets).unwrap();
+        assert_eq!(cal.len(), 3);
+        assert_eq!(cal.offset, vec![0.0; 3]);
+        // Global mean is (5 + 10 + 0) / 3 = 5.
+        assert_eq!(cal.scale, vec![1.0, 0.5, 1.0]);
+        assert_eq!(packets[0].get_calibrated_amplitudes(&cal), vec![5.0, 5.0, 0.0]);
+
+        assert_eq!(Calibration::estimate_from_packets(&[]), None);
+        assert_eq!(Calibration::estimate_from_packets(&[packet(vec![])]), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-cal-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cal.csv");
+        let path = path.to_str().unwrap();
+        let cal = Calibration {
+            scale: vec![1.5, 0.25],
+            offset: vec![0.0, 2.0],
+        };
+        cal.save(path).unwrap();
+        assert_eq!(Calibration::load(path).unwrap(), cal);
+
+        // Comments are skipped and missing subcarriers are identity.
+        fs::write(path, "# bench 3\n2,2.0,1.0\n").unwrap();
+        let sparse = Calibration::load(path).unwrap();
+        assert_eq!(sparse.scale, vec![1.0, 1.0, 2.0]);
+        assert_eq!(sparse.offset, vec![0.0, 0.0, 1.0]);
+
+        fs::write(path, "subcarrier,scale,offset\n0,abc,0\n").unwrap();
+        let err = Calibration::load(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().ends_with(":2: expected `subcarrier,scale,offset`"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
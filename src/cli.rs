@@ -0,0 +1,355 @@
+use color_eyre::{Result, eyre::eyre};
+use std::time::Duration;
+
+use crate::calibration::Calibration;
+use crate::parse_data::{
+    DEFAULT_EXPECTED_RATE_HZ, DEFAULT_HEATMAP_INTERVAL, DEFAULT_PLOT_INTERVAL, RerunEntities,
+    RerunSink,
+};
+use crate::csi_packet::ValueFormat;
+use crate::input_source::InputSource;
+use crate::parquet_capture::CaptureFormat;
+use crate::schedule::Schedule;
+use crate::theme::Theme;
+
+/// Default directory captures are saved to and listed from.
+pub const DEFAULT_SAVE_DIR: &str = "saved_data";
+
+/// One-shot commands that run instead of the TUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Concatenate CSV captures into `output`.
+    Merge { inputs: Vec<String>, output: String },
+    /// Write `<data_dir>/<name>.amp.csv` with per-subcarrier amplitudes
+    /// (and phases) computed from `<data_dir>/<name>.csv`.
+    ToAmplitude { name: String, with_phase: bool },
+    /// Write `<data_dir>/<name>.mat` (MATLAB v5) from the capture `<name>`.
+    ToMat { name: String },
+    /// Record `<data_dir>/<name>.csv` without the TUI. `port` defaults to the
+    /// first detected ESP; with `tail`, plot points are printed to stdout.
+    Record {
+        name: String,
+        port: Option<String>,
+        duration_secs: u64,
+        subcarrier: usize,
+        tail: bool,
+    },
+    /// Re-parse a `.raw.log` into `output` (default: `<name>.replay.csv`
+    /// next to it), plus `<output>.heatmap.png` with `png`.
+    Replay { input: String, output: String, png: bool },
+    /// Estimate a per-subcarrier calibration from the static reference
+    /// capture `input` and save it to `output` (default: `<name>.cal.csv`
+    /// next to it).
+    EstimateCalibration { input: String, output: String },
+}
+
+/// Command-line options.
+#[derive(Debug, Clone)]
+pub struct CliArgs {
+    pub data_dir: String,
+    pub rerun_sink: RerunSink,
+    /// Entities logged to Rerun per frame.
+    pub rerun_entities: RerunEntities,
+    /// Log Rerun tensors only on every Nth frame.
+    pub rerun_tensor_every: u64,
+    /// Minimum time between live plot points during recording.
+    pub plot_interval: Duration,
+    /// Minimum time between live heatmap refreshes during recording.
+    pub heatmap_interval: Duration,
+    /// Packets per second assumed when estimating a capture's output size.
+    pub expected_rate_hz: u32,
+    /// Stay in the full-screen plot after a recording finishes.
+    pub keep_full_screen: bool,
+    /// Redraw on input and incoming data instead of once per input poll.
+    pub low_latency: bool,
+    /// Colour preset for the TUI.
+    pub theme: Theme,
+    /// Also save the raw serial stream as `<name>.raw.log`.
+    pub raw_log: bool,
+    /// How decimal CSI arrays from the firmware are read.
+    pub value_format: ValueFormat,
+    /// Add a leading host wall-clock column to recorded CSVs.
+    pub host_time: bool,
+    /// File format captures are recorded in.
+    pub capture_format: CaptureFormat,
+    /// Stop a recording early after this long without a packet.
+    pub idle_timeout: Option<Duration>,
+    /// Packets discarded at the start of each recording.
+    pub warmup_packets: u64,
+    /// Assert DTR (resetting the board) when opening the port.
+    pub assert_dtr: bool,
+    /// Drive RTS low when opening the port.
+    pub release_rts: bool,
+    /// Read CSI from the serial port or from UDP datagrams.
+    pub source: InputSource,
+    /// Send `restart` after `stop` when a recording ends.
+    pub restart_after: bool,
+    /// Record repeatedly instead of once.
+    pub schedule: Option<Schedule>,
+    /// Per-subcarrier amplitude correction for the plot, heatmap and frame view.
+    pub calibration: Option<Calibration>,
+    pub command: Option<Command>,
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        Self {
+            data_dir: DEFAULT_SAVE_DIR.to_string(),
+            rerun_sink: RerunSink::default(),
+            rerun_entities: RerunEntities::ALL,
+            rerun_tensor_every: 1,
+            plot_interval: DEFAULT_PLOT_INTERVAL,
+            heatmap_interval: DEFAULT_HEATMAP_INTERVAL,
+            expected_rate_hz: DEFAULT_EXPECTED_RATE_HZ,
+            keep_full_screen: false,
+            low_latency: false,
+            theme: Theme::DEFAULT,
+            raw_log: false,
+            value_format: ValueFormat::default(),
+            host_time: false,
+            capture_format: CaptureFormat::default(),
+            idle_timeout: None,
+            warmup_packets: 0,
+            assert_dtr: true,
+            release_rts: false,
+            source: InputSource::default(),
+            restart_after: false,
+            schedule: None,
+            calibration: None,
+            command: None,
+        }
+    }
+}
+
+impl CliArgs {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut out = Self::default();
+        let mut args = args.into_iter().peekable();
+        let mut merge_inputs: Option<Vec<String>> = None;
+        let mut output: Option<String> = None;
+        let mut to_amplitude: Option<String> = None;
+        let mut with_phase = false;
+        let mut to_mat: Option<String> = None;
+        let mut record: Option<String> = None;
+        let mut port: Option<String> = None;
+        let mut duration_secs: u64 = 10;
+        let mut subcarrier: usize = 20;
+        let mut tail = false;
+        let mut replay: Option<String> = None;
+        let mut png = false;
+        let mut estimate_calibration: Option<String> = None;
+        while let Some(arg) = args.next() {
+            if let Some(dir) = arg.strip_prefix("--data-dir=") {
+                out.data_dir = dir.to_string();
+                continue;
+            }
+            if let Some(url) = arg.strip_prefix("--rerun-connect=") {
+                out.rerun_sink = RerunSink::Connect(Some(url.to_string()));
+                continue;
+            }
+            match arg.as_str() {
+                "--data-dir" => {
+                    out.data_dir = args
+                        .next()
+                        .ok_or_else(|| eyre!("--data-dir requires a directory argument"))?;
+                }
+                "--rerun-connect" => out.rerun_sink = RerunSink::Connect(None),
+                "--rerun-spawn" => out.rerun_sink = RerunSink::Spawn,
+                "--no-rrd" => out.rerun_sink = RerunSink::Disabled,
+                "--keep-full-screen" => out.keep_full_screen = true,
+                "--low-latency" => out.low_latency = true,
+                "--raw-log" => out.raw_log = true,
+                "--host-time" => out.host_time = true,
+                "--no-dtr" => out.assert_dtr = false,
+                "--no-rts" => out.release_rts = true,
+                "--restart-after" => out.restart_after = true,
+                "--theme" => {
+                    let name = args.next().ok_or_else(|| eyre!("--theme requires a name"))?;
+                    out.theme = Theme::by_name(&name).ok_or_else(|| {
+                        let names: Vec<&str> = Theme::ALL.iter().map(|t| t.name).collect();
+                        eyre!("unknown theme {:?}; expected one of {}", name, names.join(", "))
+                    })?;
+                }
+                "--format" => {
+                    let label = args.next().ok_or_else(|| eyre!("--format requires csv or parquet"))?;
+                    out.capture_format = CaptureFormat::by_label(&label)
+                        .ok_or_else(|| eyre!("unknown --format {:?}; expected csv or parquet", label))?;
+                }
+                "--csi-values" => {
+                    let label = args.next().ok_or_else(|| eyre!("--csi-values requires signed or u8"))?;
+                    out.value_format = ValueFormat::by_label(&label)
+                        .ok_or_else(|| eyre!("unknown --csi-values {:?}; expected signed or u8", label))?;
+                }
+                "--rerun-entities" => {
+                    let list = args
+                        .next()
+                        .ok_or_else(|| eyre!("--rerun-entities requires a comma-separated list"))?;
+                    out.rerun_entities = RerunEntities::parse(&list).map_err(|e| eyre!(e))?;
+                }
+                "--rerun-tensor-every" => {
+                    let n = args
+                        .next()
+                        .ok_or_else(|| eyre!("--rerun-tensor-every requires a frame count"))?;
+                    out.rerun_tensor_every = n
+                        .parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| eyre!("invalid --rerun-tensor-every value: {n}"))?;
+                }
+                "--udp" => {
+                    let addr = args.next().ok_or_else(|| eyre!("--udp requires a port or address"))?;
+                    out.source = InputSource::parse_udp(&addr)
+                        .ok_or_else(|| eyre!("invalid --udp value: {addr} (use <port> or <ip>:<port>)"))?;
+                }
+                "--schedule" => {
+                    let spec = args
+                        .next()
+                        .ok_or_else(|| eyre!("--schedule requires <on_s>,<off_s>,<count>"))?;
+                    out.schedule = Some(
+                        Schedule::parse(&spec)
+                            .ok_or_else(|| eyre!("invalid --schedule value: {spec} (use <on_s>,<off_s>,<count>)"))?,
+                    );
+                }
+                "--idle-timeout" => {
+                    let secs = args.next().ok_or_else(|| eyre!("--idle-timeout requires seconds"))?;
+                    let secs: u64 = secs
+                        .parse()
+                        .ok()
+                        .filter(|&s| s > 0)
+                        .ok_or_else(|| eyre!("invalid --idle-timeout value: {secs}"))?;
+                    out.idle_timeout = Some(Duration::from_secs(secs));
+                }
+                "--warmup" => {
+                    let n = args.next().ok_or_else(|| eyre!("--warmup requires a packet count"))?;
+                    out.warmup_packets = n.parse().map_err(|_| eyre!("invalid --warmup value: {n}"))?;
+                }
+                "--plot-interval-ms" => {
+                    let ms = args
+                        .next()
+                        .ok_or_else(|| eyre!("--plot-interval-ms requires a number"))?;
+                    let ms: u64 = ms
+                        .parse()
+                        .map_err(|_| eyre!("invalid --plot-interval-ms value: {ms}"))?;
+                    out.plot_interval = Duration::from_millis(ms);
+                }
+                "--heatmap-interval-ms" => {
+                    let ms = args
+                        .next()
+                        .ok_or_else(|| eyre!("--heatmap-interval-ms requires a number"))?;
+                    let ms: u64 = ms
+                        .parse()
+                        .map_err(|_| eyre!("invalid --heatmap-interval-ms value: {ms}"))?;
+                    out.heatmap_interval = Duration::from_millis(ms);
+                }
+                "--expected-rate" => {
+                    let hz = args
+                        .next()
+                        .ok_or_else(|| eyre!("--expected-rate requires packets per second"))?;
+                    out.expected_rate_hz = hz
+                        .parse()
+                        .map_err(|_| eyre!("invalid --expected-rate value: {hz}"))?;
+                }
+                "--merge" => {
+                    let mut inputs = Vec::new();
+                    while let Some(next) = args.next_if(|a| !a.starts_with('-')) {
+                        inputs.push(next);
+                    }
+                    merge_inputs = Some(inputs);
+                }
+                "--to-amplitude" => {
+                    to_amplitude = Some(
+                        args.next()
+                            .ok_or_else(|| eyre!("--to-amplitude requires a capture name"))?,
+                    );
+                }
+                "--with-phase" => with_phase = true,
+                "--to-mat" => {
+                    to_mat = Some(args.next().ok_or_else(|| eyre!("--to-mat requires a capture name"))?);
+                }
+                "--record" => {
+                    record = Some(args.next().ok_or_else(|| eyre!("--record requires a capture name"))?);
+                }
+                "--port" => {
+                    port = Some(args.next().ok_or_else(|| eyre!("--port requires a serial port"))?);
+                }
+                "--duration" => {
+                    let secs = args.next().ok_or_else(|| eyre!("--duration requires seconds"))?;
+                    duration_secs = secs
+                        .parse()
+                        .map_err(|_| eyre!("invalid --duration value: {secs}"))?;
+                }
+                "--subcarrier" => {
+                    let sc = args.next().ok_or_else(|| eyre!("--subcarrier requires an index"))?;
+                    subcarrier = sc
+                        .parse()
+                        .map_err(|_| eyre!("invalid --subcarrier value: {sc}"))?;
+                }
+                "--tail" => tail = true,
+                "--replay" => {
+                    replay = Some(args.next().ok_or_else(|| eyre!("--replay requires a .raw.log file"))?);
+                }
+                "--png" => png = true,
+                "--calibration" => {
+                    let path = args.next().ok_or_else(|| eyre!("--calibration requires a file"))?;
+                    out.calibration = Some(
+                        Calibration::load(&path).map_err(|e| eyre!("failed to load calibration {path}: {e}"))?,
+                    );
+                }
+                "--estimate-calibration" => {
+                    estimate_calibration = Some(
+                        args.next()
+                            .ok_or_else(|| eyre!("--estimate-calibration requires a capture file"))?,
+                    );
+                }
+                "-o" | "--output" => {
+                    output = Some(args.next().ok_or_else(|| eyre!("{arg} requires a file argument"))?);
+                }
+                other => return Err(eyre!("unknown argument: {other}")),
+            }
+        }
+        if let Some(inputs) = merge_inputs {
+            if inputs.len() < 2 {
+                return Err(eyre!("--merge needs at least two input files"));
+            }
+            let output = output.ok_or_else(|| eyre!("--merge requires -o <output.csv>"))?;
+            out.command = Some(Command::Merge { inputs, output });
+        } else if let Some(name) = to_amplitude {
+            let name = name.strip_suffix(".csv").unwrap_or(&name).to_string();
+            out.command = Some(Command::ToAmplitude { name, with_phase });
+        } else if let Some(name) = to_mat {
+            let name = name.strip_suffix(".csv").unwrap_or(&name).to_string();
+            out.command = Some(Command::ToMat { name });
+        } else if let Some(name) = record {
+            let name = name.strip_suffix(".csv").unwrap_or(&name).to_string();
+            out.command = Some(Command::Record {
+                name,
+                port,
+                duration_secs,
+                subcarrier,
+                tail,
+            });
+        } else if let Some(input) = replay {
+            let output = output.unwrap_or_else(|| {
+                let base = input.strip_suffix(".raw.log").unwrap_or(&input);
+                format!("{}.replay.csv", base)
+            });
+            out.command = Some(Command::Replay { input, output, png });
+        } else if let Some(input) = estimate_calibration {
+            let output = output.unwrap_or_else(|| {
+                let base = input.strip_suffix(".gz").unwrap_or(&input);
+                let base = base.strip_suffix(".csv").or_else(|| base.strip_suffix(".parquet")).unwrap_or(base);
+                format!("{}.cal.csv", base)
+            });
+            out.command = Some(Command::EstimateCalibration { input, output });
+        } else if tail {
+            return Err(eyre!("--tail only applies to --record"));
+        } else if png {
+            return Err(eyre!("--png only applies to --replay"));
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// How [`copy`] got the text to the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Copied {
+    /// A clipboard tool of the desktop session took it.
+    Tool(&'static str),
+    /// Sent to the terminal as an OSC 52 sequence. Whether the terminal
+    /// honours it (many do, also over SSH; some disable it) can't be known.
+    Terminal,
+}
+
+/// Clipboard tools to try, in order, with their arguments. Each only runs
+/// where its desktop session is present.
+fn tools() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if cfg!(target_os = "macos") {
+        tools.push(("pbcopy", &[]));
+    }
+    if cfg!(windows) {
+        tools.push(("clip", &[]));
+    }
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.push(("wl-copy", &[]));
+    }
+    if env::var_os("DISPLAY").is_some() {
+        tools.push(("xclip", &["-selection", "clipboard"]));
+        tools.push(("xsel", &["--clipboard", "--input"]));
+    }
+    tools
+}
+
+/// Pipe `text` into `program`; `Ok(false)` if it isn't installed or fails.
+fn copy_with(program: &str, args: &[&str], text: &str) -> io::Result<bool> {
+    let mut child = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child.wait()?.success())
+}
+
+/// Copy `text` to the system clipboard: through the desktop's clipboard
+/// tool when one is available, otherwise by asking the terminal (OSC 52),
+/// which also covers SSH sessions without a desktop.
+pub fn copy(text: &str) -> io::Result<Copied> {
+    for (program, args) in tools() {
+        match copy_with(program, args, text) {
+            Ok(true) => return Ok(Copied::Tool(program)),
+            Ok(false) => {}
+            Err(e) => tracing::debug!(program, "clipboard tool failed: {}", e),
+        }
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52(text).as_bytes())?;
+    stdout.flush()?;
+    Ok(Copied::Terminal)
+}
+
+/// OSC 52 sequence setting the clipboard to `text`.
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_carries_base64_text() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"saved_data/walk.csv"), "c2F2ZWRfZGF0YS93YWxrLmNzdg==");
+        assert_eq!(osc52("foo"), "\x1b]52;c;Zm9v\x07");
+    }
+}
@@ -1,19 +1,109 @@
 //use std::num::ParseIntError;
+use crate::calibration::Calibration;
+use crate::dsp;
+use std::collections::VecDeque;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CsiPacket {
     pub esp_timestamp: u64, //Timestampe from ESP (microseconds since boot)
     pub rssi: i32,  // RSSI value
     pub csi_values: Vec<i32>, // Raw CSI I/Q values
+    /// RX antenna index, if the firmware reports one.
+    pub antenna: Option<u8>,
+    /// Host wall-clock time (UNIX milliseconds) the packet was parsed at,
+    /// when the capture stamps it.
+    pub host_time_ms: Option<u64>,
 }
 
+/// Output format of the firmware on the other end of the serial port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Custom CLI build: `rssi:` / `timestamp:` / `csi raw data` / `[...]`,
+    /// or a `csi: <hex>` line in place of the marker and array.
+    Cli,
+    /// Stock esp-csi example firmware: one `CSI_DATA,...,"[...]"` line per frame.
+    CsiData,
+}
+
+/// How the integers of a decimal `[...]` CSI array are read. Hex frames
+/// are always signed bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueFormat {
+    /// Already-signed decimals, e.g. `-56`.
+    #[default]
+    Signed,
+    /// Raw bytes printed unsigned, reinterpreted as `i8` (`200` is `-56`).
+    UnsignedBytes,
+}
+
+impl ValueFormat {
+    pub const ALL: [ValueFormat; 2] = [ValueFormat::Signed, ValueFormat::UnsignedBytes];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ValueFormat::Signed => "signed",
+            ValueFormat::UnsignedBytes => "u8",
+        }
+    }
+
+    pub fn by_label(label: &str) -> Option<ValueFormat> {
+        ValueFormat::ALL.into_iter().find(|f| f.label() == label)
+    }
+}
+
+/// Field positions in a `CSI_DATA` line (esp-csi `csi_recv` layout:
+/// `CSI_DATA,id,mac,rssi,rate,...,local_timestamp,ant,sig_len,rx_state,len,first_word,"[...]"`).
+const CSI_DATA_SEQ: usize = 1;
+const CSI_DATA_RSSI: usize = 3;
+const CSI_DATA_TIMESTAMP: usize = 18;
+const CSI_DATA_ANTENNA: usize = 19;
+const CSI_DATA_LEN: usize = 22;
+
+/// Line-oriented parser for the ESP CLI output.
+///
+/// A packet is emitted for every 128-value `[...]` line that follows a
+/// `csi raw data` marker. The `rssi:`/`timestamp:` lines are optional: a
+/// frame whose RSSI is missing gets `rssi = 0` (never a real reading, so it
+/// marks the value as missing), and one whose timestamp is missing reuses
+/// the last seen timestamp (or 0 before any) so time stays monotonic. An
+/// optional `antenna:` line sets the RX antenna of the next frame.
+///
+/// Firmware variants that print the frame as a hex blob (`csi: 1a2bf0...`)
+/// are handled the same way: each byte is a signed 8-bit value, read as
+/// interleaved I/Q, and no `csi raw data` marker is needed.
+///
+/// Stock esp-csi `CSI_DATA,` lines are parsed too. The dialect is fixed by
+/// the first line that matches either format; lines of the other format
+/// are ignored after that.
+///
+/// Decimal arrays are read according to the parser's [`ValueFormat`]. With
+/// the default signed format, a frame whose values all fall in 0..=255 (and
+/// reach past 127) looks like unsigned bytes; that is warned about once.
+///
+/// Frame lines that can't be parsed are counted and described in warnings
+/// the caller collects with [`CsiCliParser::take_warnings`]. Only the last
+/// [`MAX_PENDING_WARNINGS`] are kept between calls.
 #[derive(Debug, Default)]
 pub struct CsiCliParser {
+    value_format: ValueFormat,
+    unsigned_warned: bool,
     current_timestamp: Option<u64>,
     current_rssi: Option<i32>,
+    current_antenna: Option<u8>,
+    last_timestamp: Option<u64>,
     waiting_for_csi_line: bool,
+    dialect: Option<Dialect>,
+    malformed_lines: u64,
+    warnings: VecDeque<String>,
+    /// Warnings dropped since the last take to stay within the cap.
+    dropped_warnings: u64,
 }
 
+/// Warnings [`CsiCliParser`] holds until they are taken; older ones are
+/// dropped and only counted, so a caller that never takes them doesn't
+/// grow the parser without bound.
+pub const MAX_PENDING_WARNINGS: usize = 32;
+
 impl CsiPacket {
     pub fn get_iq_pairs(&self) -> Vec<(i32, i32)> {
         self.csi_values
@@ -30,6 +120,23 @@ impl CsiPacket {
         .collect()
     }
 
+    /// Per-subcarrier SNR in dB over `noise_power` (in `I² + Q²` units),
+    /// or over [`dsp::noise_floor_estimate`] of this frame when the noise
+    /// floor isn't known. `None` if neither is available.
+    pub fn snr_db(&self, noise_power: Option<f64>) -> Option<Vec<f64>> {
+        let powers: Vec<f32> = self
+            .get_iq_pairs()
+            .iter()
+            .map(|&(i, q)| (i as f32).powi(2) + (q as f32).powi(2))
+            .collect();
+        let noise = noise_power.or_else(|| dsp::noise_floor_estimate(&powers))?;
+        Some(powers.iter().map(|&p| dsp::snr_db(p as f64, noise)).collect())
+    }
+
+    pub fn get_calibrated_amplitudes(&self, calibration: &Calibration) -> Vec<f32> {
+        calibration.apply(&self.get_amplitudes())
+    }
+
     pub fn get_phases(&self) -> Vec<f32> {
         self.get_iq_pairs()
             .iter()
@@ -43,24 +150,88 @@ impl CsiCliParser {
         Self::default()
     }
 
+    pub fn with_value_format(value_format: ValueFormat) -> Self {
+        Self {
+            value_format,
+            ..Self::default()
+        }
+    }
+
+    /// The detected dialect, once a frame line has been seen.
+    pub fn dialect(&self) -> Option<Dialect> {
+        self.dialect
+    }
+
+    /// Frame lines dropped so far because they couldn't be parsed.
+    pub fn malformed_lines(&self) -> u64 {
+        self.malformed_lines
+    }
+
+    /// Warnings about malformed lines since the last call, oldest first.
+    /// If some were dropped to stay within [`MAX_PENDING_WARNINGS`], the
+    /// first entry says how many.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        let mut warnings = Vec::with_capacity(self.warnings.len() + 1);
+        if self.dropped_warnings > 0 {
+            warnings.push(format!("{} earlier parser warnings not shown", self.dropped_warnings));
+            self.dropped_warnings = 0;
+        }
+        warnings.extend(self.warnings.drain(..));
+        warnings
+    }
+
+    fn warn(&mut self, msg: String) {
+        self.malformed_lines += 1;
+        tracing::debug!("{}", msg);
+        self.push_warning(msg);
+    }
+
+    fn push_warning(&mut self, msg: String) {
+        if self.warnings.len() == MAX_PENDING_WARNINGS {
+            self.warnings.pop_front();
+            self.dropped_warnings += 1;
+        }
+        self.warnings.push_back(msg);
+    }
+
     pub fn feed_line(&mut self, line: &str) -> Option<CsiPacket> {
         let line = line.trim();
         if line.is_empty() || line.starts_with('>') {
             return None;
         }
+        if line.starts_with("CSI_DATA,") {
+            if *self.dialect.get_or_insert(Dialect::CsiData) != Dialect::CsiData {
+                return None;
+            }
+            return self.parse_csi_data_line(line);
+        }
+        if self.dialect == Some(Dialect::CsiData) {
+            return None;
+        }
         if let Some(rest) = line.strip_prefix("rssi:") {
             if let Ok(rssi) = rest.trim().parse::<i32>() {
                 self.current_rssi = Some(rssi);
             }
             return None;
         }
+        if let Some(rest) = line.strip_prefix("antenna:") {
+            if let Ok(antenna) = rest.trim().parse::<u8>() {
+                self.current_antenna = Some(antenna);
+            }
+            return None;
+        }
         if let Some(rest) = line.strip_prefix("timestamp:") {
             if let Ok(ts) = rest.trim().parse::<u64>() {
                 self.current_timestamp = Some(ts);
             }
             return None;
         }
+        if let Some(hex) = line.strip_prefix("csi:") {
+            self.dialect = Some(Dialect::Cli);
+            return self.parse_hex_line(hex.trim());
+        }
         if line.starts_with("csi raw data") {
+            self.dialect = Some(Dialect::Cli);
             self.waiting_for_csi_line = true;
             return None;
         }
@@ -69,6 +240,7 @@ impl CsiCliParser {
 
             let inner = line.trim_matches(|c| c == '[' || c == ']');
             let mut vals: Vec<i32> = Vec::new();
+            let mut bad_values = 0;
             for tok in inner.split(',') {
                 let tok = tok.trim();
                 if tok.is_empty() {
@@ -76,25 +248,378 @@ impl CsiCliParser {
                 }
                 match tok.parse::<i32>() {
                     Ok(v) => vals.push(v),
-                    Err(e) => {
-                    }
+                    Err(_) => bad_values += 1,
                 }
             }
+            // Consume this frame's metadata even if the frame is dropped, so
+            // it can't leak into the next one.
+            let ts = self.current_timestamp.take();
+            let rssi = self.current_rssi.take().unwrap_or(0);
+            let antenna = self.current_antenna.take();
             if vals.len() != 128 {
+                self.warn(format!(
+                    "dropped CSI frame: {} values (expected 128), {} unparsable",
+                    vals.len(),
+                    bad_values
+                ));
                 return None;
             }
-            if let (Some(ts), Some(rssi)) = (self.current_timestamp, self.current_rssi) {
-                self.current_timestamp = None;
-                self.current_rssi = None;
-                return Some(CsiPacket {
-                    esp_timestamp: ts,
-                    rssi,
-                    csi_values: vals,
-                });
-            } else {
+            let vals = self.apply_value_format(vals)?;
+            let ts = ts.or(self.last_timestamp).unwrap_or(0);
+            self.last_timestamp = Some(ts);
+            return Some(CsiPacket {
+                esp_timestamp: ts,
+                rssi,
+                csi_values: vals,
+                antenna,
+                host_time_ms: None,
+            });
+        }
+        None
+    }
+}
+
+impl CsiCliParser {
+    /// Read a decimal frame's values as `self.value_format`. Unsigned frames
+    /// with a value outside 0..=255 are dropped.
+    fn apply_value_format(&mut self, vals: Vec<i32>) -> Option<Vec<i32>> {
+        let all_bytes = vals.iter().all(|v| (0..=255).contains(v));
+        match self.value_format {
+            ValueFormat::Signed => {
+                if all_bytes && !self.unsigned_warned && vals.iter().any(|&v| v > 127) {
+                    self.unsigned_warned = true;
+                    self.push_warning(
+                        "CSI values are all 0..255; if the firmware prints unsigned bytes, \
+                         record with --csi-values u8 or amplitudes and phases will be wrong"
+                            .to_string(),
+                    );
+                }
+                Some(vals)
+            }
+            ValueFormat::UnsignedBytes if all_bytes => {
+                Some(vals.into_iter().map(|v| v as u8 as i8 as i32).collect())
+            }
+            ValueFormat::UnsignedBytes => {
+                let bad = vals.iter().find(|v| !(0..=255).contains(*v)).copied().unwrap_or_default();
+                self.warn(format!("dropped CSI frame: value {} is not an unsigned byte", bad));
+                None
+            }
+        }
+    }
+
+    /// Parse a `csi: <hex>` frame using the pending `rssi:`/`timestamp:`/
+    /// `antenna:` metadata, like a `[...]` array line.
+    fn parse_hex_line(&mut self, hex: &str) -> Option<CsiPacket> {
+        self.waiting_for_csi_line = false;
+        let ts = self.current_timestamp.take();
+        let rssi = self.current_rssi.take().unwrap_or(0);
+        let antenna = self.current_antenna.take();
+        let vals = match decode_hex_iq(hex) {
+            Ok(vals) => vals,
+            Err(e) => {
+                self.warn(format!("dropped hex CSI frame: {}", e));
                 return None;
             }
+        };
+        let ts = ts.or(self.last_timestamp).unwrap_or(0);
+        self.last_timestamp = Some(ts);
+        Some(CsiPacket {
+            esp_timestamp: ts,
+            rssi,
+            csi_values: vals,
+            antenna,
+            host_time_ms: None,
+        })
+    }
+
+    /// Parse one esp-csi `CSI_DATA` record. The timestamp is the
+    /// `local_timestamp` field, or the sequence number if that is missing.
+    fn parse_csi_data_line(&mut self, line: &str) -> Option<CsiPacket> {
+        let (Some(open), Some(close)) = (line.find('['), line.rfind(']')) else {
+            self.warn("dropped CSI_DATA line: no [...] array".to_string());
+            return None;
+        };
+        let fields: Vec<&str> = line[..open]
+            .trim_end_matches('"')
+            .split(',')
+            .map(|f| f.trim())
+            .collect();
+        let vals: Vec<i32> = line[open + 1..close]
+            .split(',')
+            .filter_map(|tok| tok.trim().parse().ok())
+            .collect();
+        if vals.is_empty() || !vals.len().is_multiple_of(2) {
+            self.warn(format!("dropped CSI_DATA line: {} values, need an even count", vals.len()));
+            return None;
         }
-        None
+        let declared_len = fields.get(CSI_DATA_LEN).and_then(|f| f.parse::<usize>().ok());
+        if let Some(len) = declared_len.filter(|&len| len != vals.len()) {
+            self.warn(format!("dropped CSI_DATA line: {} values, header says {}", vals.len(), len));
+            return None;
+        }
+        let vals = self.apply_value_format(vals)?;
+        let rssi = fields
+            .get(CSI_DATA_RSSI)
+            .and_then(|f| f.parse().ok())
+            .unwrap_or(0);
+        let ts = fields
+            .get(CSI_DATA_TIMESTAMP)
+            .and_then(|f| f.parse().ok())
+            .or_else(|| fields.get(CSI_DATA_SEQ).and_then(|f| f.parse().ok()))
+            .or(self.last_timestamp)
+            .unwrap_or(0);
+        self.last_timestamp = Some(ts);
+        let antenna = fields.get(CSI_DATA_ANTENNA).and_then(|f| f.parse().ok());
+        Some(CsiPacket {
+            esp_timestamp: ts,
+            rssi,
+            csi_values: vals,
+            antenna,
+            host_time_ms: None,
+        })
+    }
+}
+
+/// Decode a hex blob into signed bytes, as interleaved I/Q values.
+fn decode_hex_iq(hex: &str) -> Result<Vec<i32>, String> {
+    if hex.is_empty() {
+        return Err("empty hex string".to_string());
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("{} hex digits, need whole bytes", hex.len()));
+    }
+    let vals = hex
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .map(|byte| byte as i8 as i32)
+                .ok_or_else(|| format!("invalid hex byte {:?}", String::from_utf8_lossy(pair)))
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+    if !vals.len().is_multiple_of(2) {
+        return Err(format!("{} bytes, need an even count for I/Q pairs", vals.len()));
+    }
+    Ok(vals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csi_line(len: usize) -> String {
+        let vals: Vec<String> = (0..len).map(|v| (v as i32 % 20 - 10).to_string()).collect();
+        format!("[{}]", vals.join(","))
+    }
+
+    fn feed_all(parser: &mut CsiCliParser, lines: &[&str]) -> Vec<CsiPacket> {
+        lines.iter().filter_map(|l| parser.feed_line(l)).collect()
+    }
+
+    #[test]
+    fn full_metadata() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        let packets = feed_all(&mut parser, &["rssi: -42", "timestamp: 1000", "csi raw data", &csi]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].rssi, -42);
+        assert_eq!(packets[0].esp_timestamp, 1000);
+        assert_eq!(packets[0].csi_values.len(), 128);
+        assert_eq!(packets[0].antenna, None);
+    }
+
+    #[test]
+    fn antenna_line_applies_to_next_frame() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        let packets = feed_all(
+            &mut parser,
+            &["antenna: 1", "timestamp: 1", "csi raw data", &csi, "timestamp: 2", "csi raw data", &csi],
+        );
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].antenna, Some(1));
+        assert_eq!(packets[1].antenna, None);
+    }
+
+    #[test]
+    fn missing_rssi_defaults_to_zero() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        let packets = feed_all(&mut parser, &["timestamp: 1000", "csi raw data", &csi]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].rssi, 0);
+        assert_eq!(packets[0].esp_timestamp, 1000);
+    }
+
+    #[test]
+    fn missing_timestamp_reuses_last() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        let packets = feed_all(
+            &mut parser,
+            &[
+                "rssi: -40",
+                "csi raw data",
+                &csi,
+                "rssi: -41",
+                "timestamp: 2000",
+                "csi raw data",
+                &csi,
+                "rssi: -42",
+                "csi raw data",
+                &csi,
+            ],
+        );
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].esp_timestamp, 0);
+        assert_eq!(packets[1].esp_timestamp, 2000);
+        assert_eq!(packets[2].esp_timestamp, 2000);
+        assert_eq!(packets[2].rssi, -42);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn wrong_length_is_dropped() {
+        let mut parser = CsiCliParser::new();
+        let short = csi_line(64);
+        let long = csi_line(130);
+        let packets = feed_all(
+            &mut parser,
+            &["rssi: -42", "timestamp: 1", "csi raw data", &short, "csi raw data", &long],
+        );
+        assert!(packets.is_empty());
+        assert_eq!(parser.malformed_lines(), 2);
+        let warnings = parser.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("64 values"));
+        assert!(parser.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn pending_warnings_are_capped() {
+        let mut parser = CsiCliParser::new();
+        let short = csi_line(64);
+        for _ in 0..100 {
+            feed_all(&mut parser, &["rssi: -42", "timestamp: 1", "csi raw data", &short]);
+        }
+        assert_eq!(parser.malformed_lines(), 100);
+        let warnings = parser.take_warnings();
+        assert_eq!(warnings.len(), MAX_PENDING_WARNINGS + 1);
+        assert_eq!(warnings[0], "68 earlier parser warnings not shown");
+        assert!(warnings[1].contains("64 values"));
+        assert!(parser.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn array_without_marker_is_ignored() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        assert!(feed_all(&mut parser, &["rssi: -42", "timestamp: 1", &csi]).is_empty());
+    }
+
+    fn csi_data_line(seq: u32, rssi: i32, ts: u64, len: usize) -> String {
+        let vals: Vec<String> = (0..len).map(|v| (v as i32 % 20 - 10).to_string()).collect();
+        format!(
+            "CSI_DATA,{seq},aa:bb:cc:dd:ee:ff,{rssi},11,1,7,1,1,1,1,0,0,0,-96,0,6,0,{ts},0,108,0,{len},0,\"[{}]\"",
+            vals.join(",")
+        )
+    }
+
+    #[test]
+    fn csi_data_line_is_parsed() {
+        let mut parser = CsiCliParser::new();
+        let line = csi_data_line(7, -55, 123456, 128);
+        let packets = feed_all(&mut parser, &["I (100) wifi: boot", &line]);
+        assert_eq!(parser.dialect(), Some(Dialect::CsiData));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].rssi, -55);
+        assert_eq!(packets[0].esp_timestamp, 123456);
+        assert_eq!(packets[0].csi_values.len(), 128);
+        assert_eq!(packets[0].csi_values[0], -10);
+        assert_eq!(packets[0].antenna, Some(0));
+    }
+
+    #[test]
+    fn csi_data_length_mismatch_is_dropped() {
+        let mut parser = CsiCliParser::new();
+        let line = csi_data_line(1, -55, 1, 128).replace(",128,0,", ",384,0,");
+        assert!(parser.feed_line(&line).is_none());
+    }
+
+    #[test]
+    fn dialect_is_locked_by_first_frame() {
+        let mut parser = CsiCliParser::new();
+        let csi = csi_line(128);
+        let data = csi_data_line(1, -55, 1, 128);
+        let packets = feed_all(&mut parser, &["rssi: -42", "timestamp: 1", "csi raw data", &csi, &data]);
+        assert_eq!(parser.dialect(), Some(Dialect::Cli));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].rssi, -42);
+    }
+
+    #[test]
+    fn hex_line_decodes_signed_iq() {
+        let mut parser = CsiCliParser::new();
+        let packets = feed_all(&mut parser, &["rssi: -50", "timestamp: 42", "csi: 0aF67f80"]);
+        assert_eq!(parser.dialect(), Some(Dialect::Cli));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].csi_values, vec![10, -10, 127, -128]);
+        assert_eq!(packets[0].get_iq_pairs(), vec![(10, -10), (127, -128)]);
+        assert_eq!(packets[0].rssi, -50);
+        assert_eq!(packets[0].esp_timestamp, 42);
+
+        // Odd digit count, odd byte count and bad digits are dropped with a warning.
+        assert!(feed_all(&mut parser, &["csi: 0a0", "csi: 0a0b0c", "csi: zz00"]).is_empty());
+        assert_eq!(parser.malformed_lines(), 3);
+        let warnings = parser.take_warnings();
+        assert!(warnings[0].contains("3 hex digits"));
+        assert!(warnings[1].contains("3 bytes"));
+        assert!(warnings[2].contains("invalid hex byte"));
+    }
+
+    fn byte_line(vals: &[i32]) -> String {
+        let mut all: Vec<String> = vals.iter().map(|v| v.to_string()).collect();
+        all.resize(128, "0".to_string());
+        format!("[{}]", all.join(","))
+    }
+
+    #[test]
+    fn signed_values_are_kept_and_unsigned_looking_frames_warn_once() {
+        let mut parser = CsiCliParser::new();
+        let signed = byte_line(&[-56, 12]);
+        let unsigned = byte_line(&[200, 12]);
+        let packets = feed_all(
+            &mut parser,
+            &["csi raw data", &signed, "csi raw data", &unsigned, "csi raw data", &unsigned],
+        );
+        assert_eq!(packets.len(), 3);
+        assert_eq!(&packets[0].csi_values[..2], &[-56, 12]);
+        assert_eq!(&packets[1].csi_values[..2], &[200, 12]);
+        assert_eq!(parser.malformed_lines(), 0);
+        let warnings = parser.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("--csi-values u8"));
+    }
+
+    #[test]
+    fn unsigned_bytes_are_reinterpreted_as_i8() {
+        let mut parser = CsiCliParser::with_value_format(ValueFormat::UnsignedBytes);
+        let cli = byte_line(&[200, 12, 255, 128]);
+        let header = csi_data_line(1, -55, 1, 128);
+        let data = format!("{}{}\"", &header[..header.find('[').unwrap()], byte_line(&[246, 3]));
+        let packets = feed_all(&mut parser, &["csi raw data", &cli]);
+        assert_eq!(&packets[0].csi_values[..4], &[-56, 12, -1, -128]);
+        assert_eq!(packets[0].get_amplitudes()[1], (1.0f32 + 128.0 * 128.0).sqrt());
+        assert!(parser.take_warnings().is_empty());
+
+        // Negative values can't be unsigned bytes.
+        assert!(feed_all(&mut parser, &["csi raw data", &byte_line(&[-3])]).is_empty());
+        assert_eq!(parser.malformed_lines(), 1);
+
+        let mut parser = CsiCliParser::with_value_format(ValueFormat::UnsignedBytes);
+        let packets = feed_all(&mut parser, &[&data]);
+        assert_eq!(&packets[0].csi_values[..2], &[-10, 3]);
+        assert_eq!(ValueFormat::by_label("u8"), Some(ValueFormat::UnsignedBytes));
+    }
+}
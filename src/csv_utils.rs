@@ -1,12 +1,44 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
+    path::Path,
 };
 
 use crate::csi_packet;
+use crate::read_data;
 
-pub fn generate_csv_header(num_csi_values: usize) -> String {
-    let mut header = String::from("esp_timestamp_us,rssi");
+/// Name of the optional column between `rssi` and the I/Q values, present
+/// when the firmware reports the RX antenna.
+pub const ANTENNA_COLUMN: &str = "antenna";
+
+/// Name of the optional leading column holding the host wall-clock time
+/// (UNIX milliseconds) each packet was parsed at.
+pub const HOST_TIME_COLUMN: &str = "host_time_ms";
+
+/// Create `dir` if needed and check a file can be written in it, so an
+/// unwritable data directory is reported before a capture starts rather
+/// than when its first file is created.
+pub fn ensure_writable_dir(dir: &Path) -> Result<(), String> {
+    let check = || -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let probe = dir.join(".write_test");
+        File::create(&probe)?;
+        fs::remove_file(&probe)
+    };
+    check().map_err(|e| format!("Cannot write to {}: {}", dir.display(), e.kind()))
+}
+
+pub fn generate_csv_header(num_csi_values: usize, with_antenna: bool, with_host_time: bool) -> String {
+    let mut header = String::new();
+    if with_host_time {
+        header.push_str(HOST_TIME_COLUMN);
+        header.push(',');
+    }
+    header.push_str("esp_timestamp_us,rssi");
+    if with_antenna {
+        header.push(',');
+        header.push_str(ANTENNA_COLUMN);
+    }
 
     let num_subcarriers = num_csi_values / 2;
     for i in 0..num_subcarriers {
@@ -15,12 +47,296 @@ pub fn generate_csv_header(num_csi_values: usize) -> String {
     header
 }
 
-pub fn write_csv_line(file: &mut File, packet: &csi_packet::CsiPacket) -> io::Result<()>
+pub fn write_csv_line<W: Write>(file: &mut W, packet: &csi_packet::CsiPacket) -> io::Result<()>
 {
-    let mut line = format!("{},{}", packet.esp_timestamp, packet.rssi);
+    let mut line = String::new();
+    if let Some(host_time) = packet.host_time_ms {
+        line.push_str(&format!("{},", host_time));
+    }
+    line.push_str(&format!("{},{}", packet.esp_timestamp, packet.rssi));
+    if let Some(antenna) = packet.antenna {
+        line.push_str(&format!(",{}", antenna));
+    }
 
     for val in &packet.csi_values {
         line.push_str(&format!(",{}", val));
     }
     writeln!(file, "{}", line)
-}
\ No newline at end of file
+}
+
+/// Concatenate captures into `output`, shifting each file's timestamps so
+/// they continue after the previous file (one typical inter-packet interval
+/// later). Host wall-clock times are kept as recorded. All inputs must have
+/// identical headers, and `output` must not be one of them. Returns the
+/// rows written.
+pub fn merge_csv_files(inputs: &[String], output: &str) -> io::Result<u64> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    if inputs.is_empty() {
+        return Err(invalid("no input files to merge".into()));
+    }
+    // Creating the output truncates it, which would lose that input's rows.
+    if let Ok(out_path) = fs::canonicalize(output)
+        && inputs.iter().any(|path| fs::canonicalize(path).is_ok_and(|p| p == out_path))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("output {} is also an input", output),
+        ));
+    }
+
+    let mut header: Option<String> = None;
+    let mut ts_col = 0;
+    let mut out = File::create(output)?;
+    let mut rows_written: u64 = 0;
+    // Timestamp the next file's first row should land on.
+    let mut next_start: Option<u64> = None;
+
+    for path in inputs {
+        let content = fs::read_to_string(path)?;
+        let mut lines = content.lines().map(|l| l.trim_end_matches('\r'));
+        let file_header = lines
+            .next()
+            .ok_or_else(|| invalid(format!("{} is empty", path)))?;
+        match &header {
+            None => {
+                writeln!(out, "{}", file_header)?;
+                header = Some(file_header.to_string());
+                ts_col = read_data::CsvLayout::from_header(file_header.split(',')).timestamp;
+            }
+            Some(h) if h != file_header => {
+                return Err(invalid(format!(
+                    "header of {} differs from {}; files must have the same subcarrier count",
+                    path, inputs[0]
+                )));
+            }
+            Some(_) => {}
+        }
+
+        let mut first_ts: Option<u64> = None;
+        let mut last_ts: Option<u64> = None;
+        let mut offset: i128 = 0;
+        let mut rows: u64 = 0;
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields: Vec<&str> = line.split(',').collect();
+            let Some(Ok(ts)) = fields.get(ts_col).map(|f| f.trim().parse::<u64>()) else {
+                continue;
+            };
+            if first_ts.is_none() {
+                first_ts = Some(ts);
+                offset = next_start.map_or(0, |start| start as i128 - ts as i128);
+            }
+            let shifted = (ts as i128 + offset).max(0) as u64;
+            let shifted_str = shifted.to_string();
+            fields[ts_col] = &shifted_str;
+            writeln!(out, "{}", fields.join(","))?;
+            last_ts = Some(shifted);
+            rows += 1;
+        }
+
+        if let (Some(first), Some(last)) = (first_ts, last_ts) {
+            let first_shifted = (first as i128 + offset).max(0) as u64;
+            let interval = if rows > 1 {
+                ((last - first_shifted) / (rows - 1)).max(1)
+            } else {
+                1
+            };
+            next_start = Some(last + interval);
+        }
+        rows_written += rows;
+    }
+    out.flush()?;
+    Ok(rows_written)
+}
+
+/// Write the rows of `input` from `start_s` to `end_s` (inclusive, seconds
+/// after its first row, as on the plot) to `output` as a capture CSV.
+/// Timestamps are kept as recorded. Returns the rows written.
+pub fn slice_capture_by_time(
+    input: &str,
+    output: &str,
+    start_s: f64,
+    end_s: f64,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let packets = read_data::load_csv_packets(input).map_err(|e| e.to_string())?;
+    let Some(ts0) = packets.first().map(|p| p.esp_timestamp) else {
+        return Err(format!("{} has no rows", input).into());
+    };
+    let selected: Vec<_> = packets
+        .iter()
+        .filter(|p| {
+            let t = p.esp_timestamp.saturating_sub(ts0) as f64 / 1e6;
+            t >= start_s && t <= end_s
+        })
+        .collect();
+    let Some(first) = selected.first() else {
+        return Err(format!("no rows of {} between {:.3}s and {:.3}s", input, start_s, end_s).into());
+    };
+    let mut out = io::BufWriter::new(File::create(output)?);
+    let header = generate_csv_header(
+        first.csi_values.len(),
+        first.antenna.is_some(),
+        first.host_time_ms.is_some(),
+    );
+    writeln!(out, "{}", header)?;
+    for packet in &selected {
+        write_csv_line(&mut out, packet)?;
+    }
+    out.flush()?;
+    Ok(selected.len() as u64)
+}
+
+/// Convert a raw I/Q capture into `esp_timestamp_us,rssi,amp0,...` (plus
+/// `phase0,...` when `with_phase`, and a leading `host_time_ms` column if
+/// the capture has one). Returns the rows written.
+pub fn convert_to_amplitude_csv(
+    input: &str,
+    output: &str,
+    with_phase: bool,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let packets = read_data::load_csv_packets(input).map_err(|e| e.to_string())?;
+    let num_subcarriers = packets.iter().map(|p| p.csi_values.len() / 2).max().unwrap_or(0);
+
+    let with_antenna = packets.iter().any(|p| p.antenna.is_some());
+    let with_host_time = packets.iter().any(|p| p.host_time_ms.is_some());
+
+    let mut out = File::create(output)?;
+    let mut header = String::new();
+    if with_host_time {
+        header.push_str(HOST_TIME_COLUMN);
+        header.push(',');
+    }
+    header.push_str("esp_timestamp_us,rssi");
+    if with_antenna {
+        header.push(',');
+        header.push_str(ANTENNA_COLUMN);
+    }
+    for k in 0..num_subcarriers {
+        header.push_str(&format!(",amp{}", k));
+    }
+    if with_phase {
+        for k in 0..num_subcarriers {
+            header.push_str(&format!(",phase{}", k));
+        }
+    }
+    writeln!(out, "{}", header)?;
+
+    for packet in &packets {
+        let mut line = String::new();
+        if with_host_time {
+            line.push_str(&format!("{},", packet.host_time_ms.unwrap_or(0)));
+        }
+        line.push_str(&format!("{},{}", packet.esp_timestamp, packet.rssi));
+        if with_antenna {
+            line.push_str(&format!(",{}", packet.antenna.unwrap_or(0)));
+        }
+        for amp in packet.get_amplitudes() {
+            line.push_str(&format!(",{}", amp));
+        }
+        if with_phase {
+            for phase in packet.get_phases() {
+                line.push_str(&format!(",{}", phase));
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    Ok(packets.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_shifts_timestamps_and_refuses_to_overwrite_an_input() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-merge-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("a.csv");
+        let second = dir.join("b.csv");
+        fs::write(&first, "esp_timestamp_us,rssi,i0,q0\n1000000,-40,1,0\n1010000,-41,2,0\n").unwrap();
+        fs::write(&second, "esp_timestamp_us,rssi,i0,q0\n5000,-42,3,0\n15000,-43,4,0\n").unwrap();
+        let inputs = [first.to_str().unwrap().to_string(), second.to_str().unwrap().to_string()];
+        let output = dir.join("merged.csv");
+        let output = output.to_str().unwrap();
+
+        assert_eq!(merge_csv_files(&inputs, output).unwrap(), 4);
+        let merged = read_data::load_csv_packets(output).unwrap();
+        // The second file continues one interval (10 ms) after the first.
+        assert_eq!(
+            merged.iter().map(|p| p.esp_timestamp).collect::<Vec<_>>(),
+            vec![1000000, 1010000, 1020000, 1030000]
+        );
+
+        // Also via a different spelling of the same path.
+        let same_as_second = dir.join(".").join("b.csv");
+        let err = merge_csv_files(&inputs, same_as_second.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(read_data::load_csv_packets(&inputs[1]).unwrap().len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn amplitude_export_writes_magnitude_and_phase_per_subcarrier() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-amp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("in.csv");
+        fs::write(&input, "esp_timestamp_us,rssi,i0,q0,i1,q1\n1000,-40,3,4,0,2\n2000,-41,-1,0,0,0\n").unwrap();
+        let (input, output) = (input.to_str().unwrap(), dir.join("in.amp.csv"));
+        let output = output.to_str().unwrap();
+
+        assert_eq!(convert_to_amplitude_csv(input, output, true).unwrap(), 2);
+        let text = fs::read_to_string(output).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("esp_timestamp_us,rssi,amp0,amp1,phase0,phase1"));
+        let rows: Vec<Vec<f32>> = lines
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        let expected = [
+            [1000.0, -40.0, 5.0, 2.0, (4.0f32).atan2(3.0), std::f32::consts::FRAC_PI_2],
+            [2000.0, -41.0, 1.0, 0.0, std::f32::consts::PI, 0.0],
+        ];
+        for (row, expected) in rows.iter().zip(expected) {
+            assert!(row.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6), "{row:?}");
+        }
+
+        convert_to_amplitude_csv(input, output, false).unwrap();
+        let header = fs::read_to_string(output).unwrap().lines().next().unwrap().to_string();
+        assert_eq!(header, "esp_timestamp_us,rssi,amp0,amp1");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn time_slice_keeps_both_bounds() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-slice-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("in.csv");
+        fs::write(
+            &input,
+            "esp_timestamp_us,rssi,i0,q0\n\
+             1000000,-40,1,0\n\
+             1010000,-41,2,0\n\
+             1020000,-42,3,0\n\
+             1030000,-43,4,0\n",
+        )
+        .unwrap();
+        let (input, output) = (input.to_str().unwrap(), dir.join("out.csv"));
+        let output = output.to_str().unwrap();
+
+        // Rows at exactly 0.01 s and 0.02 s are both kept.
+        assert_eq!(slice_capture_by_time(input, output, 0.01, 0.02).unwrap(), 2);
+        let sliced = read_data::load_csv_packets(output).unwrap();
+        assert_eq!(sliced.iter().map(|p| p.esp_timestamp).collect::<Vec<_>>(), vec![1010000, 1020000]);
+        assert_eq!(sliced[1].csi_values, vec![3, 0]);
+
+        fs::remove_file(output).unwrap();
+        assert!(slice_capture_by_time(input, output, 0.5, 1.0).is_err());
+        assert!(!Path::new(output).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,4 +13,23 @@ pub fn amplitude_for_subcarrier(packet: &CsiPacket, k: usize) -> Option<f32> {
 
 pub fn time_in_seconds(first_ts: u64, packet: &CsiPacket) -> f64 {
     (packet.esp_timestamp - first_ts) as f64 / 1e6
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::{Pattern, SyntheticCsi};
+
+    #[test]
+    fn sine_pattern_reads_back_per_subcarrier() {
+        let packets: Vec<CsiPacket> = SyntheticCsi::new(4, 40.0, Pattern::Sine { period_frames: 4.0 })
+            .take(4)
+            .collect();
+        let amps: Vec<f32> = packets
+            .iter()
+            .map(|p| amplitude_for_subcarrier(p, 3).unwrap())
+            .collect();
+        assert_eq!(amps, vec![40.0, 60.0, 40.0, 20.0]);
+        assert_eq!(amplitude_for_subcarrier(&packets[0], 4), None);
+        assert_eq!(time_in_seconds(packets[0].esp_timestamp, &packets[3]), 0.03);
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+
+/// Mean and (population) standard deviation of `values`.
+pub fn mean_std(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count();
+    if n == 0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.clone().sum::<f64>() / n as f64;
+    let var = values.map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+    (mean, var.sqrt())
+}
+
+/// Replace each value of a `(t, value)` series with its z-score over the
+/// whole series. A zero-variance series becomes all zeros.
+pub fn zscore_series(points: &mut [(f64, f64)]) {
+    let (mean, std) = mean_std(points.iter().map(|(_, v)| *v));
+    for (_, v) in points.iter_mut() {
+        *v = if std > 0.0 { (*v - mean) / std } else { 0.0 };
+    }
+}
+
+/// Z-score each column (subcarrier) of a row-major `time × subcarrier`
+/// matrix independently. Zero-variance columns become all zeros.
+pub fn zscore_columns(rows: &mut [Vec<f32>]) {
+    let cols = rows.iter().map(|r| r.len()).min().unwrap_or(0);
+    for c in 0..cols {
+        let (mean, std) = mean_std(rows.iter().map(|r| r[c] as f64));
+        for row in rows.iter_mut() {
+            row[c] = if std > 0.0 {
+                ((row[c] as f64 - mean) / std) as f32
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+/// Mean of each column (subcarrier) of a row-major `time × subcarrier`
+/// matrix: the capture's average channel response.
+pub fn column_means(rows: &[Vec<f32>]) -> Vec<f32> {
+    let cols = rows.iter().map(|r| r.len()).min().unwrap_or(0);
+    (0..cols)
+        .map(|c| mean_std(rows.iter().map(|r| r[c] as f64)).0 as f32)
+        .collect()
+}
+
+/// Subtract each column's (subcarrier's) mean from a row-major
+/// `time × subcarrier` matrix, removing the static channel response
+/// ("background subtraction") so only changes over time remain.
+pub fn subtract_column_means(rows: &mut [Vec<f32>]) {
+    let means = column_means(rows);
+    for row in rows.iter_mut() {
+        for (v, mean) in row.iter_mut().zip(&means) {
+            *v -= mean;
+        }
+    }
+}
+
+/// Fraction of the non-zero subcarriers averaged for the noise estimate.
+const NOISE_QUANTILE: f64 = 0.25;
+/// Lowest SNR reported, so silent subcarriers don't read as minus infinity.
+pub const SNR_FLOOR_DB: f64 = -20.0;
+
+/// Noise power estimated from the quietest subcarriers: the mean of the
+/// lowest quarter of `powers` (`I² + Q²`). Exact zeros are ignored, as null
+/// and guard subcarriers carry nothing at all. `None` if every power is 0.
+pub fn noise_floor_estimate(powers: &[f32]) -> Option<f64> {
+    let mut nonzero: Vec<f64> = powers.iter().filter(|p| **p > 0.0).map(|&p| p as f64).collect();
+    if nonzero.is_empty() {
+        return None;
+    }
+    nonzero.sort_by(f64::total_cmp);
+    let quiet = ((nonzero.len() as f64 * NOISE_QUANTILE).ceil() as usize).max(1);
+    Some(nonzero[..quiet].iter().sum::<f64>() / quiet as f64)
+}
+
+/// `10·log10(power / noise)`, floored at [`SNR_FLOOR_DB`].
+pub fn snr_db(power: f64, noise: f64) -> f64 {
+    (10.0 * (power / noise).log10()).max(SNR_FLOOR_DB)
+}
+
+/// Index of the series (one per subcarrier) with the highest variance,
+/// i.e. the subcarrier that moves the most. Ties go to the lowest index;
+/// `None` when there are no series.
+pub fn most_variable_series(series: &[Vec<f64>]) -> Option<usize> {
+    series
+        .iter()
+        .enumerate()
+        .map(|(c, values)| (c, mean_std(values.iter().copied()).1))
+        .fold(None, |best: Option<(usize, f64)>, (c, std)| match best {
+            Some((_, best_std)) if best_std >= std => best,
+            _ => Some((c, std)),
+        })
+        .map(|(c, _)| c)
+}
+
+/// Magnitude spectrum (bins `0..=n/2`) of a real series, after removing its
+/// mean and applying a Hann window. A direct DFT: fine for the short windows
+/// used by the live spectrogram.
+pub fn magnitude_spectrum(samples: &[f64]) -> Vec<f32> {
+    let n = samples.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let (mean, _) = mean_std(samples.iter().copied());
+    let windowed: Vec<f64> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let hann = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos();
+            (v - mean) * hann
+        })
+        .collect();
+    (0..=n / 2)
+        .map(|k| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (i, &v) in windowed.iter().enumerate() {
+                let angle = -2.0 * std::f64::consts::PI * (k * i) as f64 / n as f64;
+                re += v * angle.cos();
+                im += v * angle.sin();
+            }
+            (re * re + im * im).sqrt() as f32
+        })
+        .collect()
+}
+
+/// Replace each value of a `(t, value)` series with its absolute change
+/// from the previous sample. The first sample becomes 0.
+pub fn abs_delta_series(points: &mut [(f64, f64)]) {
+    let mut prev: Option<f64> = None;
+    for (_, v) in points.iter_mut() {
+        let current = *v;
+        *v = prev.map_or(0.0, |p| (current - p).abs());
+        prev = Some(current);
+    }
+}
+
+/// Fixed-width bins spanning `[min, max]` of a set of values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// Width of one bin.
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len().max(1) as f64
+    }
+}
+
+/// Count `values` into `bins` equal-width bins between their min and max;
+/// the max lands in the last bin. Non-finite values are ignored. A
+/// constant input puts everything in the first bin.
+pub fn histogram(values: impl Iterator<Item = f64> + Clone, bins: usize) -> Histogram {
+    let bins = bins.max(1);
+    let finite = values.filter(|v| v.is_finite());
+    let (min, max) = finite
+        .clone()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(mn, mx), v| (mn.min(v), mx.max(v)));
+    let mut counts = vec![0u64; bins];
+    if min > max {
+        return Histogram { min: 0.0, max: 0.0, counts };
+    }
+    let width = (max - min) / bins as f64;
+    for v in finite {
+        let idx = if width > 0.0 { ((v - min) / width) as usize } else { 0 };
+        counts[idx.min(bins - 1)] += 1;
+    }
+    Histogram { min, max, counts }
+}
+
+/// Population variance over the last `window` samples of a stream.
+#[derive(Debug, Clone)]
+pub struct RollingVariance {
+    window: usize,
+    samples: VecDeque<f64>,
+}
+
+impl RollingVariance {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Add a sample and return the variance of the current window.
+    pub fn push(&mut self, value: f64) -> f64 {
+        self.samples.push_back(value);
+        if self.samples.len() > self.window {
+            self.samples.pop_front();
+        }
+        let (_, std) = mean_std(self.samples.iter().copied());
+        std * std
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_and_population_std() {
+        let (mean, std) = mean_std([2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].into_iter());
+        assert_eq!((mean, std), (5.0, 2.0));
+        assert_eq!(mean_std(std::iter::empty()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn zscore_of_series_and_columns() {
+        let mut points = vec![(0.0, 1.0), (0.1, 3.0), (0.2, 5.0), (0.3, 7.0)];
+        zscore_series(&mut points);
+        let z: Vec<f64> = points.iter().map(|(_, v)| *v).collect();
+        let expected = [-3.0, -1.0, 1.0, 3.0].map(|v| v / 5f64.sqrt());
+        assert!(z.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-12));
+        assert_eq!(points[3].0, 0.3);
+
+        let mut flat = vec![(0.0, 4.0), (0.1, 4.0)];
+        zscore_series(&mut flat);
+        assert_eq!(flat, vec![(0.0, 0.0), (0.1, 0.0)]);
+
+        // Each column is scaled on its own; the constant one becomes zeros.
+        let mut rows = vec![vec![10.0, 2.0, 1.0], vec![20.0, 2.0, 3.0]];
+        zscore_columns(&mut rows);
+        assert_eq!(rows, vec![vec![-1.0, 0.0, -1.0], vec![1.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn abs_delta_of_series() {
+        let mut points = vec![(0.0, 5.0), (0.1, 7.0), (0.2, 4.0), (0.3, 4.0)];
+        abs_delta_series(&mut points);
+        let deltas: Vec<f64> = points.iter().map(|(_, v)| *v).collect();
+        assert_eq!(deltas, vec![0.0, 2.0, 3.0, 0.0]);
+        assert_eq!(points[2].0, 0.2);
+    }
+
+    #[test]
+    fn most_variable_series_is_picked() {
+        let series = vec![vec![5.0, 5.0, 5.0], vec![1.0, 9.0, 2.0], vec![3.0, 4.0, 3.0]];
+        assert_eq!(most_variable_series(&series), Some(1));
+        assert_eq!(most_variable_series(&[vec![2.0], vec![2.0]]), Some(0));
+        assert_eq!(most_variable_series(&[]), None);
+    }
+
+    #[test]
+    fn column_means_are_subtracted() {
+        let mut rows = vec![vec![10.0, 1.0], vec![12.0, 1.0], vec![14.0, 4.0]];
+        subtract_column_means(&mut rows);
+        assert_eq!(rows, vec![vec![-2.0, -1.0], vec![0.0, -1.0], vec![2.0, 2.0]]);
+        assert!(column_means(&rows).iter().all(|m| m.abs() < 1e-6));
+    }
+
+    #[test]
+    fn snr_uses_the_quietest_subcarriers_as_noise() {
+        // Zeros (guard bands) are skipped; the lowest quarter of 8 is 2 values.
+        let powers = [0.0, 0.0, 1.0, 3.0, 100.0, 100.0, 1000.0, 1000.0, 1000.0, 1000.0];
+        assert_eq!(noise_floor_estimate(&powers), Some(2.0));
+        assert_eq!(noise_floor_estimate(&[0.0, 0.0]), None);
+        assert!((snr_db(200.0, 2.0) - 20.0).abs() < 1e-9);
+        assert_eq!(snr_db(0.0, 2.0), SNR_FLOOR_DB);
+    }
+
+    #[test]
+    fn spectrum_peaks_at_the_tone_frequency() {
+        // 4 cycles over 32 samples, offset by a mean that must be removed.
+        let n = 32;
+        let samples: Vec<f64> = (0..n)
+            .map(|i| 10.0 + (2.0 * std::f64::consts::PI * 4.0 * i as f64 / n as f64).cos())
+            .collect();
+        let spectrum = magnitude_spectrum(&samples);
+        assert_eq!(spectrum.len(), n / 2 + 1);
+        // The Hann window gives N/4 at the tone and half that either side.
+        for (k, &mag) in spectrum.iter().enumerate() {
+            let expected = match k {
+                4 => 8.0,
+                3 | 5 => 4.0,
+                _ => 0.0,
+            };
+            assert!((mag - expected).abs() < 1e-4, "bin {k}: {mag}");
+        }
+        assert!(magnitude_spectrum(&[3.0; 8]).iter().all(|m| m.abs() < 1e-6));
+        assert!(magnitude_spectrum(&[]).is_empty());
+    }
+
+    #[test]
+    fn rolling_variance_forgets_old_samples() {
+        let mut variance = RollingVariance::new(3);
+        assert_eq!(variance.push(1.0), 0.0);
+        assert_eq!(variance.push(3.0), 1.0);
+        assert!((variance.push(5.0) - 8.0 / 3.0).abs() < 1e-12);
+        // The 1.0 drops out of the window.
+        assert!((variance.push(3.0) - 8.0 / 9.0).abs() < 1e-12);
+        assert_eq!([4.0; 3].map(|v| variance.push(v)).last(), Some(&0.0));
+    }
+
+    #[test]
+    fn histogram_bins_values() {
+        let values = [0.0, 1.0, 2.5, 4.9, 5.0, 10.0, f64::NAN];
+        let hist = histogram(values.iter().copied(), 4);
+        assert_eq!((hist.min, hist.max), (0.0, 10.0));
+        assert_eq!(hist.bin_width(), 2.5);
+        // [0, 2.5) [2.5, 5) [5, 7.5) [7.5, 10]
+        assert_eq!(hist.counts, vec![2, 2, 1, 1]);
+
+        let flat = histogram([3.0; 5].iter().copied(), 8);
+        assert_eq!(flat.counts[0], 5);
+        assert_eq!(flat.counts.iter().sum::<u64>(), 5);
+
+        let empty = histogram(std::iter::empty(), 3);
+        assert_eq!(empty.counts, vec![0, 0, 0]);
+    }
+}
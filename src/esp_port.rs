@@ -1,50 +1,113 @@
 use serialport::{available_ports, SerialPortType, UsbPortInfo, SerialPort};
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn find_esp_port() -> Option<String> {
-    let ports = available_ports().ok()?;
-
-    #[cfg(target_os = "linux")]
-    {
-        for p in &ports {
-            if let  SerialPortType::UsbPort(usb) = &p.port_type {
-                let product = usb.product.as_deref().unwrap_or("").to_lowercase();
-                let manufacturer = usb.manufacturer.as_deref().unwrap_or("").to_lowercase();
-                if product.contains("esp") || manufacturer.contains("espressif") {
-                    return Some(p.port_name.clone());
-                }
-            }
-        }
+/// How long to wait for the ESP console to come up after a DTR reset.
+pub const PROMPT_TIMEOUT: Duration = Duration::from_secs(2);
 
-        let found =  ports
-            .into_iter()
-            .map(|p| p.port_name)
-            .find(|name| name.contains("ttyUSB") || name.contains("ttyACM"));
+/// Lines printed by ESP-IDF style consoles once they accept commands.
+const READY_BANNERS: &[&str] = &["Type 'help' to get the list of commands"];
 
-        return found
-    }
+/// USB bridges commonly found on ESP dev boards, as `(vid, pid)`.
+/// A `None` PID matches any product from that vendor.
+const KNOWN_USB_IDS: &[(u16, Option<u16>)] = &[
+    (0x1A86, Some(0x7523)), // QinHeng CH340
+    (0x10C4, Some(0xEA60)), // Silicon Labs CP210x
+    (0x303A, None),         // Espressif native USB (ESP32-S2/S3/C3/C6)
+];
 
-    #[cfg(target_os = "windows")]
-    {
-        for p in &ports {
-            if let SerialPortType::UsbPort(usb) = &p.port_type {
-                let product = usb.product.as_deref().unwrap_or("").to_lowercase();
-                let manufacturer = usb.manufacturer.as_deref().unwrap_or("").to_lowercase();
-                if product.contains("esp") || manufacturer.contains("espressif") {
-                    return Some(p.port_name.clone());
-                }
+fn is_known_esp_usb(usb: &UsbPortInfo) -> bool {
+    KNOWN_USB_IDS
+        .iter()
+        .any(|&(vid, pid)| usb.vid == vid && pid.is_none_or(|pid| usb.pid == pid))
+}
+
+/// The best-matching ESP port; see [`find_esp_ports`].
+pub fn find_esp_port() -> Option<String> {
+    find_esp_ports().into_iter().next()
+}
+
+/// All ports that look like ESP boards, best matches first: USB descriptor
+/// strings, then known VID/PID, then the platform's name-based fallback.
+pub fn find_esp_ports() -> Vec<String> {
+    let Ok(ports) = available_ports() else {
+        return Vec::new();
+    };
+    let mut found: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        if !found.iter().any(|f| f == name) {
+            found.push(name.to_string());
+        }
+    };
+
+    for p in &ports {
+        if let SerialPortType::UsbPort(usb) = &p.port_type {
+            let product = usb.product.as_deref().unwrap_or("").to_lowercase();
+            let manufacturer = usb.manufacturer.as_deref().unwrap_or("").to_lowercase();
+            if product.contains("esp") || manufacturer.contains("espressif") {
+                push(&p.port_name);
             }
         }
-        
-        let found = ports
-            .into_iter()
-            .find(|port| port.port_name.eq_ignore_ascii_case("COM4"))
-            .map(|port| port.port_name);
-        return found
     }
+    for p in &ports {
+        if let SerialPortType::UsbPort(usb) = &p.port_type
+            && is_known_esp_usb(usb)
+        {
+            push(&p.port_name);
+        }
+    }
+    for p in &ports {
+        #[cfg(target_os = "linux")]
+        if p.port_name.contains("ttyUSB") || p.port_name.contains("ttyACM") {
+            push(&p.port_name);
+        }
+        #[cfg(target_os = "windows")]
+        if p.port_name.eq_ignore_ascii_case("COM4") {
+            push(&p.port_name);
+        }
+    }
+    found
+}
 
-    #[allow(unreachable_code)]
-    None
+/// A serial port with a one-line description for the port picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortInfo {
+    pub name: String,
+    pub description: String,
+}
+
+/// Every serial port the OS reports, not just ESP-looking ones, with the
+/// USB manufacturer/product strings and VID:PID where there are any.
+pub fn list_ports() -> Vec<PortInfo> {
+    let Ok(ports) = available_ports() else {
+        return Vec::new();
+    };
+    ports
+        .into_iter()
+        .map(|p| {
+            let description = match &p.port_type {
+                SerialPortType::UsbPort(usb) => {
+                    let names: Vec<&str> = [usb.manufacturer.as_deref(), usb.product.as_deref()]
+                        .into_iter()
+                        .flatten()
+                        .filter(|s| !s.is_empty())
+                        .collect();
+                    let known = if is_known_esp_usb(usb) { ", ESP bridge" } else { "" };
+                    format!("{} ({:04x}:{:04x}{})", names.join(" "), usb.vid, usb.pid, known)
+                        .trim_start()
+                        .to_string()
+                }
+                SerialPortType::PciPort => "PCI".to_string(),
+                SerialPortType::BluetoothPort => "Bluetooth".to_string(),
+                SerialPortType::Unknown => String::new(),
+            };
+            PortInfo {
+                name: p.port_name,
+                description,
+            }
+        })
+        .collect()
 }
 
 pub fn send_cli_command(
@@ -56,3 +119,82 @@ pub fn send_cli_command(
     port.flush()?;
     Ok(())
 }
+
+/// Whether `output` ends at a CLI prompt (a last line ending in `>`, such as
+/// `> ` or `csi> `) or contains a known ready banner.
+fn console_ready(output: &str) -> bool {
+    let last_line = output.rsplit(['\n', '\r']).next().unwrap_or("");
+    last_line.trim_end().ends_with('>') || READY_BANNERS.iter().any(|b| output.contains(b))
+}
+
+/// Nudge the console with an empty line and read until it shows a prompt
+/// or ready banner, or `timeout` passes. Returns whether it looked ready;
+/// callers fall back to fixed delays when it did not.
+pub fn wait_for_prompt<P: Read + Write + ?Sized>(port: &mut P, timeout: Duration) -> bool {
+    // A board that did not reset on DTR is already idle; Enter reprints the prompt.
+    if port.write_all(b"\r\n").and_then(|_| port.flush()).is_err() {
+        return false;
+    }
+    let deadline = Instant::now() + timeout;
+    let mut output = String::new();
+    let mut buf = [0u8; 256];
+    while Instant::now() < deadline {
+        match port.read(&mut buf) {
+            Ok(0) => return false,
+            Ok(n) => {
+                output.push_str(&String::from_utf8_lossy(&buf[..n]));
+                if console_ready(&output) {
+                    return true;
+                }
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock) => {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scripted console: reads come from `output`, writes are discarded.
+    struct FakeConsole {
+        output: io::Cursor<Vec<u8>>,
+    }
+
+    impl Read for FakeConsole {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.output.read(buf)
+        }
+    }
+
+    impl Write for FakeConsole {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn console(output: &str) -> FakeConsole {
+        FakeConsole { output: io::Cursor::new(output.as_bytes().to_vec()) }
+    }
+
+    #[test]
+    fn detects_prompt_and_banner() {
+        let timeout = Duration::from_millis(50);
+        assert!(wait_for_prompt(&mut console("ets Jun  8 2016 00:22:57\r\nboot: ok\r\n> "), timeout));
+        assert!(wait_for_prompt(&mut console("\r\ncsi>"), timeout));
+        assert!(wait_for_prompt(
+            &mut console("Type 'help' to get the list of commands.\r\n"),
+            timeout
+        ));
+        // Boot log only: no prompt yet, so the caller falls back to delays.
+        assert!(!wait_for_prompt(&mut console("rst:0x1 (POWERON_RESET)\r\nload:0x3fff0030\r\n"), timeout));
+    }
+}
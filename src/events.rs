@@ -0,0 +1,59 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+/// Labels cycled through when annotating during a recording.
+pub const EVENT_LABELS: &[&str] = &["event", "gesture", "walk", "sit", "stand", "still"];
+
+/// A labelled moment in a capture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Seconds since the start of the capture (same basis as the plotted series).
+    pub t: f64,
+    /// Host wall-clock time when the event was marked.
+    pub unix_ms: u128,
+    pub label: String,
+}
+
+/// Suffix of the annotation sidecar next to a capture.
+pub const EVENTS_SUFFIX: &str = ".events.csv";
+
+/// `saved_data/foo.csv` -> `saved_data/foo.events.csv`.
+pub fn events_path_for(csv_filename: &str) -> String {
+    let base = csv_filename.strip_suffix(".csv").unwrap_or(csv_filename);
+    format!("{}{}", base, EVENTS_SUFFIX)
+}
+
+/// Append one event to the sidecar, writing the header on first use.
+pub fn append_event(path: &str, event: &Event) -> io::Result<()> {
+    let new_file = !Path::new(path).exists();
+    let mut out = OpenOptions::new().create(true).append(true).open(path)?;
+    if new_file {
+        writeln!(out, "t_s,unix_ms,label")?;
+    }
+    // Labels are free text; keep the file a simple 3-column CSV.
+    writeln!(out, "{:.6},{},{}", event.t, event.unix_ms, event.label.replace(',', " "))
+}
+
+/// Read all events from a sidecar, skipping malformed lines.
+pub fn load_events(path: &str) -> io::Result<Vec<Event>> {
+    let content = fs::read_to_string(path)?;
+    let mut events = Vec::new();
+    for line in content.lines().skip(1) {
+        let mut parts = line.trim().splitn(3, ',');
+        let (Some(t), Some(unix_ms), Some(label)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        let (Ok(t), Ok(unix_ms)) = (t.trim().parse::<f64>(), unix_ms.trim().parse::<u128>()) else {
+            continue;
+        };
+        events.push(Event {
+            t,
+            unix_ms,
+            label: label.trim().to_string(),
+        });
+    }
+    Ok(events)
+}
@@ -1,4 +1,5 @@
 use std::collections::btree_map::Values;
+use std::{fs::File, io::BufWriter};
 
 use ratatui::{
     prelude::Buffer,
@@ -7,9 +8,161 @@ use ratatui::{
     widgets::{Widget},
 };
 
+use crate::subcarrier_layout::SubcarrierKind;
+
+/// Brightness left on screen in pilot and null subcarrier columns.
+const PILOT_DIM: f32 = 0.6;
+const NULL_DIM: f32 = 0.3;
+
+/// Pixels per heatmap cell side in exported PNGs.
+pub const PNG_CELL_PX: u32 = 4;
+
+/// Mapping from 0–100 heatmap values to colours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Blue (low) to orange (high).
+    #[default]
+    BlueOrange,
+    /// Cividis-like dark blue to yellow: lightness rises steadily and the
+    /// hues stay distinguishable with red-green colour blindness.
+    Cividis,
+    /// Black to white, for monochrome or high-contrast terminals.
+    Grayscale,
+    /// Blue (below) through white (50) to red (above), for values centred
+    /// on zero such as the background-subtracted heatmap.
+    Diverging,
+}
+
+/// Cividis sampled at 0, 25, 50, 75 and 100%.
+const CIVIDIS_STOPS: [(u8, u8, u8); 5] = [
+    (0, 34, 78),
+    (65, 77, 108),
+    (124, 123, 120),
+    (189, 175, 111),
+    (255, 234, 70),
+];
+
+/// Diverging endpoints and centre (ColorBrewer RdBu).
+const DIVERGING_STOPS: [(u8, u8, u8); 3] = [(33, 102, 172), (247, 247, 247), (178, 24, 43)];
+
+impl Colormap {
+    /// RGB for `value` (clamped to 0–100), in steps of 2 so neighbouring
+    /// values share a colour.
+    pub fn rgb(self, value: u8) -> (u8, u8, u8) {
+        // Bucket into ranges of size 2: 0..=1, 2..=3, ..., 98..=100
+        let bucket = (value.min(100) / 2) * 2;
+        let t = bucket as f32 / 100.0; // 0.0 .. 1.0
+        match self {
+            Colormap::BlueOrange => {
+                let r = (255.0 * t) as u8; // grows from 0 → 255
+                let g = (200.0 * t) as u8; // grows from 0 → 200
+                let b = (255.0 * (1.0 - t)) as u8; // fades from 255 → 0
+                (r, g, b)
+            }
+            Colormap::Cividis => {
+                let pos = t * (CIVIDIS_STOPS.len() - 1) as f32;
+                let i = (pos as usize).min(CIVIDIS_STOPS.len() - 2);
+                let f = pos - i as f32;
+                let (a, b) = (CIVIDIS_STOPS[i], CIVIDIS_STOPS[i + 1]);
+                let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+                (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+            }
+            Colormap::Grayscale => {
+                let v = (255.0 * t) as u8;
+                (v, v, v)
+            }
+            Colormap::Diverging => {
+                let (a, b, f) = if t < 0.5 {
+                    (DIVERGING_STOPS[0], DIVERGING_STOPS[1], t * 2.0)
+                } else {
+                    (DIVERGING_STOPS[1], DIVERGING_STOPS[2], t * 2.0 - 1.0)
+                };
+                let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+                (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Heatmap {
     pub values: Vec<Vec<u8>>, // 0–100 values
+    /// Terminal columns per value: 2 for square-ish cells, 1 to fit twice
+    /// as many subcarriers.
+    pub cell_width: u16,
+    /// Colours used on screen and in exported PNGs.
+    pub colormap: Colormap,
+    /// Kind of the subcarrier in each column; pilot and null columns are
+    /// dimmed on screen (not in PNGs). Empty leaves every column as is.
+    pub column_kinds: Vec<SubcarrierKind>,
+}
+
+impl Heatmap {
+    pub fn new(values: Vec<Vec<u8>>) -> Self {
+        Self {
+            values,
+            cell_width: 2,
+            colormap: Colormap::default(),
+            column_kinds: Vec::new(),
+        }
+    }
+
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    pub fn with_cell_width(mut self, cell_width: u16) -> Self {
+        self.cell_width = cell_width.max(1);
+        self
+    }
+
+    pub fn with_column_kinds(mut self, column_kinds: Vec<SubcarrierKind>) -> Self {
+        self.column_kinds = column_kinds;
+        self
+    }
+
+    /// On-screen colour of `value` in column `x`.
+    fn cell_rgb(&self, value: u8, x: usize) -> (u8, u8, u8) {
+        let (r, g, b) = self.colormap.rgb(value);
+        let dim = match self.column_kinds.get(x) {
+            Some(SubcarrierKind::Pilot) => PILOT_DIM,
+            Some(SubcarrierKind::Null) => NULL_DIM,
+            _ => return (r, g, b),
+        };
+        let scale = |c: u8| (c as f32 * dim).round() as u8;
+        (scale(r), scale(g), scale(b))
+    }
+
+    /// Rasterize the grid as RGB8 pixels, `cell_px` × `cell_px` per value,
+    /// using the on-screen colormap. Returns `(width, height, pixels)`.
+    pub fn to_rgb(&self, cell_px: u32) -> (u32, u32, Vec<u8>) {
+        let rows = self.values.len() as u32;
+        let cols = self.values.iter().map(|r| r.len()).min().unwrap_or(0) as u32;
+        let (width, height) = (cols * cell_px, rows * cell_px);
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            let row = &self.values[(y / cell_px) as usize];
+            for x in 0..width {
+                let (r, g, b) = self.colormap.rgb(row[(x / cell_px) as usize]);
+                pixels.extend_from_slice(&[r, g, b]);
+            }
+        }
+        (width, height, pixels)
+    }
+
+    /// Write the grid to `path` as a PNG (see [`Heatmap::to_rgb`]).
+    pub fn write_png(&self, path: &str, cell_px: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let (width, height, pixels) = self.to_rgb(cell_px);
+        if width == 0 || height == 0 {
+            return Err("heatmap is empty".into());
+        }
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&pixels)?;
+        Ok(())
+    }
 }
 
 impl Widget for &Heatmap {
@@ -19,44 +172,72 @@ impl Widget for &Heatmap {
             return;
         }
         let cols = self.values[0].len();
+        let cell_width = self.cell_width.max(1);
 
         // Keep within terminal bounds
         let height = rows.min(area.height as usize);
-        let width = cols.min(area.width as usize);
+        let width = cols.min((area.width / cell_width) as usize);
+        let symbol = " ".repeat(cell_width as usize);
 
         for y in 0..height {
             for x in 0..width {
-                let value = self.values[y][x];
-
-
-                let color = heatmap_color(value);
-                // Draw a block (two spaces to make it square-ish)
-                let symbol = "  ";
+                let (r, g, b) = self.cell_rgb(self.values[y][x], x);
 
                 buf.set_string(
-                    area.x + x as u16,
+                    area.x + x as u16 * cell_width,
                     area.y + y as u16,
-                    symbol,
-                    Style::default().bg(color),
+                    &symbol,
+                    Style::default().bg(Color::Rgb(r, g, b)),
                 );
             }
         }
     }
 }
 
-fn heatmap_color(value: u8) -> Color {
-    // Clamp to 0–100
-    let v = value.min(100);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Bucket into ranges of size 2: 0..=1, 2..=3, ..., 98..=100
-    let bucket = (v / 2) * 2;          // 0, 2, 4, ..., 100
-    let t = bucket as f32 / 100.0;     // 0.0 .. 1.0
+    #[test]
+    fn rgb_matches_colormap_and_scale() {
+        let heatmap = Heatmap::new(vec![vec![0, 100], vec![50, 51]]);
+        let (width, height, pixels) = heatmap.to_rgb(2);
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(pixels.len(), 4 * 4 * 3);
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 4 + x) * 3;
+            (pixels[i], pixels[i + 1], pixels[i + 2])
+        };
+        assert_eq!(pixel(0, 0), (0, 0, 255));
+        assert_eq!(pixel(1, 1), (0, 0, 255));
+        assert_eq!(pixel(2, 0), (255, 200, 0));
+        // 50 and 51 share a colour bucket.
+        assert_eq!(pixel(0, 2), pixel(3, 3));
+        assert_eq!(pixel(0, 2), Colormap::BlueOrange.rgb(50));
+    }
 
-    // t = 0.0  -> warm (orange/yellow)
-    // t = 1.0  -> cold (blue)
-    let r = (255.0 * t) as u8;   // fades from 255 → 0
-    let g = (200.0 * t) as u8;   // fades from 200 → 0
-    let b = (255.0 * (1.0 - t)) as u8;           // grows from 0   → 255
+    #[test]
+    fn pilot_and_null_columns_are_dimmed_on_screen_only() {
+        let heatmap = Heatmap::new(vec![vec![100, 100, 100]])
+            .with_colormap(Colormap::Grayscale)
+            .with_column_kinds(vec![SubcarrierKind::Null, SubcarrierKind::Data, SubcarrierKind::Pilot]);
+        assert_eq!(heatmap.cell_rgb(100, 0), (77, 77, 77));
+        assert_eq!(heatmap.cell_rgb(100, 1), (255, 255, 255));
+        assert_eq!(heatmap.cell_rgb(100, 2), (153, 153, 153));
+        assert_eq!(heatmap.to_rgb(1).2, vec![255; 9]);
+    }
 
-    Color::Rgb(r, g, b)
+    #[test]
+    fn colormaps_span_their_endpoints() {
+        assert_eq!(Colormap::Cividis.rgb(0), CIVIDIS_STOPS[0]);
+        assert_eq!(Colormap::Cividis.rgb(50), CIVIDIS_STOPS[2]);
+        assert_eq!(Colormap::Cividis.rgb(100), CIVIDIS_STOPS[4]);
+        assert_eq!(Colormap::Grayscale.rgb(0), (0, 0, 0));
+        assert_eq!(Colormap::Grayscale.rgb(255), (255, 255, 255));
+        assert_eq!(Colormap::Diverging.rgb(0), DIVERGING_STOPS[0]);
+        assert_eq!(Colormap::Diverging.rgb(50), DIVERGING_STOPS[1]);
+        assert_eq!(Colormap::Diverging.rgb(100), DIVERGING_STOPS[2]);
+        let png = Heatmap::new(vec![vec![100]]).with_colormap(Colormap::Grayscale).to_rgb(1);
+        assert_eq!(png.2, vec![255, 255, 255]);
+    }
 }
\ No newline at end of file
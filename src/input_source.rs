@@ -0,0 +1,132 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+/// Largest UDP payload.
+const MAX_DATAGRAM: usize = 65_507;
+
+/// Where the recorder reads CSI lines from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputSource {
+    /// The ESP's USB serial console, configured and started by the recorder
+    /// (default).
+    #[default]
+    Serial,
+    /// Datagrams sent to this local address by firmware that forwards its
+    /// CLI output over WiFi. The board is configured and started on its own.
+    Udp(SocketAddr),
+}
+
+impl InputSource {
+    /// `--udp` argument: a port (bound on all interfaces) or `host:port`.
+    pub fn parse_udp(s: &str) -> Option<Self> {
+        let addr = match s.parse::<u16>() {
+            Ok(port) => SocketAddr::from(([0, 0, 0, 0], port)),
+            Err(_) => s.parse().ok()?,
+        };
+        Some(InputSource::Udp(addr))
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Serial => write!(f, "serial"),
+            InputSource::Udp(addr) => write!(f, "udp://{}", addr),
+        }
+    }
+}
+
+/// Reads the payloads of datagrams arriving on a UDP socket as one byte
+/// stream, the way the serial port is read. Each datagram carries one or
+/// more complete CLI lines; a missing trailing newline is added so lines
+/// never run across datagrams.
+pub struct UdpReader {
+    socket: UdpSocket,
+    datagram: Vec<u8>,
+    /// Bytes of `datagram` already returned.
+    pos: usize,
+    /// Datagrams received so far.
+    pub datagrams: u64,
+}
+
+impl UdpReader {
+    /// Bind `addr`. Reads time out after `timeout` like the serial port's,
+    /// so the capture loop can check its duration.
+    pub fn bind(addr: SocketAddr, timeout: Duration) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(timeout))?;
+        Ok(Self {
+            socket,
+            datagram: Vec::new(),
+            pos: 0,
+            datagrams: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Read for UdpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.datagram.len() {
+            self.datagram.resize(MAX_DATAGRAM, 0);
+            let (n, from) = self.socket.recv_from(&mut self.datagram)?;
+            self.datagram.truncate(n);
+            if !self.datagram.ends_with(b"\n") {
+                self.datagram.push(b'\n');
+            }
+            self.pos = 0;
+            self.datagrams += 1;
+            tracing::trace!(bytes = n, %from, "udp datagram");
+        }
+        let n = buf.len().min(self.datagram.len() - self.pos);
+        buf[..n].copy_from_slice(&self.datagram[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn datagrams_read_as_lines() {
+        let mut reader = UdpReader::bind(SocketAddr::from(([127, 0, 0, 1], 0)), Duration::from_millis(500)).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let to = reader.local_addr().unwrap();
+        sender.send_to(b"rssi: -40\n", to).unwrap();
+        sender.send_to(b"csi raw data: [1, 2]", to).unwrap();
+
+        let mut text = String::new();
+        let mut buf = [0u8; 4];
+        while !text.ends_with("]\n") {
+            let n = reader.read(&mut buf).unwrap();
+            text.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+        }
+        assert_eq!(text, "rssi: -40\ncsi raw data: [1, 2]\n");
+        assert_eq!(reader.datagrams, 2);
+        // Nothing more arrives: the read times out instead of blocking.
+        let err = reader.read(&mut buf).unwrap_err();
+        assert!(matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut));
+    }
+
+    #[test]
+    fn parse_udp_accepts_port_or_address() {
+        assert_eq!(
+            InputSource::parse_udp("5000"),
+            Some(InputSource::Udp(SocketAddr::from(([0, 0, 0, 0], 5000))))
+        );
+        assert_eq!(
+            InputSource::parse_udp("192.168.4.2:3333"),
+            Some(InputSource::Udp(SocketAddr::from(([192, 168, 4, 2], 3333))))
+        );
+        assert_eq!(InputSource::parse_udp("nope"), None);
+    }
+}
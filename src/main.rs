@@ -1,5 +1,5 @@
-
-use color_eyre::Result;
+use color_eyre::{Result, eyre::eyre};
+use std::{fs, path::Path, sync::mpsc, thread, time::SystemTime};
 
 pub mod app;
 pub mod esp_port;
@@ -10,12 +10,252 @@ pub mod detect_motion;
 pub mod read_data;
 pub mod wifi_mode;
 pub mod heatmap;
+pub mod calibration;
+pub mod cli;
+pub mod packet_gaps;
+pub mod presets;
+pub mod quality;
+pub mod stuck_frames;
+pub mod dsp;
+pub mod manifest;
+pub mod trigger;
+pub mod events;
+pub mod spectrogram;
+pub mod theme;
+pub mod parquet_capture;
+pub mod bookmarks;
+pub mod input_source;
+pub mod number_format;
+pub mod schedule;
+pub mod mat_export;
+pub mod subcarrier_layout;
+pub mod clipboard;
+#[cfg(test)]
+pub mod synthetic;
+
+/// Debug log file written under the data directory when `RUST_LOG` is set.
+const TRACE_LOG_FILE: &str = "esp-csi-tui.log";
+
+/// Send `tracing` output to `<data_dir>/esp-csi-tui.log`, filtered by
+/// `RUST_LOG`. Without `RUST_LOG` nothing is installed and logging stays
+/// silent; stdout is never used since it belongs to the TUI.
+fn init_tracing(data_dir: &str) -> Result<()> {
+    let Ok(filter) = tracing_subscriber::EnvFilter::try_from_default_env() else {
+        return Ok(());
+    };
+    fs::create_dir_all(data_dir)?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(Path::new(data_dir).join(TRACE_LOG_FILE))?;
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        // Span close events carry the time spent in serial reads and parsing.
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .init();
+    Ok(())
+}
+
+/// Run a one-shot CLI command without starting the TUI.
+fn run_command(command: cli::Command, args: &cli::CliArgs) -> Result<()> {
+    let data_dir = &args.data_dir;
+    match command {
+        cli::Command::Merge { inputs, output } => {
+            let rows = csv_utils::merge_csv_files(&inputs, &output)?;
+            println!("Merged {} files ({} rows) into {}", inputs.len(), rows, output);
+        }
+        cli::Command::ToAmplitude { name, with_phase } => {
+            let input = format!("{}/{}.csv", data_dir, name);
+            let output = format!("{}/{}.amp.csv", data_dir, name);
+            let rows = csv_utils::convert_to_amplitude_csv(&input, &output, with_phase)
+                .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            println!("Wrote {} rows to {}", rows, output);
+        }
+        cli::Command::ToMat { name } => {
+            // Parquet and gzipped captures load like CSVs.
+            let csv = format!("{}/{}.csv", data_dir, name);
+            let input = [csv.clone(), format!("{}.gz", csv), parquet_capture::CaptureFormat::Parquet.path_for(&csv)]
+                .into_iter()
+                .find(|path| Path::new(path).exists())
+                .unwrap_or(csv);
+            let packets = read_data::load_csv_packets(&input).map_err(|e| eyre!("failed to read {input}: {e}"))?;
+            let output = format!("{}/{}.mat", data_dir, name);
+            mat_export::write_mat(&output, &packets).map_err(|e| eyre!("failed to write {output}: {e}"))?;
+            println!("Wrote {} packets from {} to {}", packets.len(), input, output);
+        }
+        cli::Command::Replay { input, output, png } => {
+            let config = parse_data::RecordingConfig::new("replay", &output)
+                .value_format(args.value_format)
+                .format(args.capture_format)
+                .warnings_to_stderr(true);
+            let written = config.output_filename();
+            let stats = parse_data::replay_raw_log(&input, config).map_err(|e| eyre!("replay failed: {e}"))?;
+            println!(
+                "Replayed {} into {}: {} packets, {} malformed lines, quality {}",
+                input,
+                written,
+                stats.lines_written,
+                stats.malformed_lines,
+                quality::QualityScore::from_stats(&stats)
+            );
+            if png {
+                let base = output.strip_suffix(".csv").unwrap_or(&output);
+                let path = format!("{}.heatmap.png", base);
+                let grid = read_data::load_csv_heatmap(&written, read_data::AmplitudeMetric::default(), None)
+                    .map_err(|e| eyre!("failed to read {written}: {e}"))?;
+                heatmap::Heatmap::new(grid)
+                    .write_png(&path, heatmap::PNG_CELL_PX)
+                    .map_err(|e| eyre!("{e}"))?;
+                println!("Wrote {}", path);
+            }
+        }
+        cli::Command::EstimateCalibration { input, output } => {
+            let packets = read_data::load_csv_packets(&input).map_err(|e| eyre!("failed to read {input}: {e}"))?;
+            let calibration = calibration::Calibration::estimate_from_packets(&packets)
+                .ok_or_else(|| eyre!("{input} has no CSI frames to estimate a calibration from"))?;
+            calibration
+                .save(&output)
+                .map_err(|e| eyre!("failed to write {output}: {e}"))?;
+            println!(
+                "Wrote a {}-subcarrier calibration from {} packets of {} to {}",
+                calibration.len(),
+                packets.len(),
+                input,
+                output
+            );
+        }
+        cli::Command::Record {
+            name,
+            port,
+            duration_secs,
+            subcarrier,
+            tail,
+        } => {
+            let port = match args.source {
+                // Only a label in the manifest and logs; no serial port is opened.
+                input_source::InputSource::Udp(_) => args.source.to_string(),
+                input_source::InputSource::Serial => port
+                    .or_else(esp_port::find_esp_port)
+                    .ok_or_else(|| eyre!("no ESP serial port found; pass --port <port>"))?,
+            };
+            match args.schedule {
+                None => record_once(args, &port, &name, duration_secs, subcarrier, tail)?,
+                Some(schedule) => {
+                    let mut progress = schedule::ScheduleProgress::resume_or_start(data_dir, &name, schedule);
+                    while !progress.is_done() {
+                        let wait = progress.wait();
+                        if !wait.is_zero() {
+                            eprintln!(
+                                "Recording {}/{} in {}s",
+                                progress.completed + 1,
+                                schedule.count,
+                                wait.as_secs()
+                            );
+                            thread::sleep(wait);
+                        }
+                        let started = SystemTime::now();
+                        let run = schedule::run_name(&name, started);
+                        record_once(args, &port, &run, schedule.on_secs, subcarrier, tail)?;
+                        progress.record_done(started);
+                        progress
+                            .save(data_dir)
+                            .map_err(|e| eyre!("failed to save schedule progress: {e}"))?;
+                    }
+                    eprintln!("Schedule finished: {} recordings", schedule.count);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One headless `--record` run into `<data_dir>/<name>.csv`.
+fn record_once(
+    args: &cli::CliArgs,
+    port: &str,
+    name: &str,
+    duration_secs: u64,
+    subcarrier: usize,
+    tail: bool,
+) -> Result<()> {
+    let data_dir = &args.data_dir;
+    let csv = format!("{}/{}.csv", data_dir, name);
+    if let Some(parent) = Path::new(&csv).parent() {
+        csv_utils::ensure_writable_dir(parent).map_err(|e| eyre!(e))?;
+    }
+    let mut config = parse_data::RecordingConfig::new(port, &csv)
+        .duration_secs(duration_secs)
+        .subcarrier(subcarrier)
+        .plot_interval(args.plot_interval)
+        .calibration(args.calibration.clone())
+        .rerun_sink(args.rerun_sink.clone())
+        .rerun_entities(args.rerun_entities)
+        .rerun_tensor_every(args.rerun_tensor_every)
+        .raw_log(args.raw_log)
+        .value_format(args.value_format)
+        .host_time(args.host_time)
+        .format(args.capture_format)
+        .idle_timeout(args.idle_timeout)
+        .warmup_packets(args.warmup_packets)
+        .assert_dtr(args.assert_dtr)
+        .release_rts(args.release_rts)
+        .source(args.source)
+        .restart_after(args.restart_after)
+        .warnings_to_stderr(true);
+    let written = config.output_filename();
+    // Same channel the TUI plots from; only created with --tail, since the
+    // loop below ends only once the worker drops the sender.
+    let mut plot_rx = None;
+    if tail {
+        let (plot_tx, rx) = mpsc::channel();
+        config = config.plot_tx(plot_tx);
+        plot_rx = Some(rx);
+    }
+    let worker = thread::spawn(move || parse_data::record_csi_to_file(config));
+    for (t, amp) in plot_rx.into_iter().flatten() {
+        println!("{:.6},{},{}", t, subcarrier, amp);
+    }
+    let stats = match worker.join().map_err(|_| eyre!("recording thread panicked"))? {
+        Ok(stats) => stats,
+        // Not fatal, so a --schedule carries on with its next recording.
+        Err(parse_data::RecordError::NoPacketsReceived) => {
+            eprintln!("No packets received into {}; check the board streams CSI.", written);
+            return Ok(());
+        }
+        Err(e) => return Err(eyre!("recording failed: {e}")),
+    };
+    // Stdout carries the tailed points; keep the summary off it.
+    eprintln!(
+        "Recorded {} packets to {} (~{} dropped, {} gaps), quality {}",
+        stats.frames_logged,
+        written,
+        stats.dropped_packets,
+        stats.gaps_detected,
+        quality::QualityScore::from_stats(&stats)
+    );
+    if stats.warmup_skipped > 0 {
+        eprintln!("Discarded the first {} packets as warm-up.", stats.warmup_skipped);
+    }
+    if stats.stopped_idle {
+        eprintln!("Stopped early: no packets within --idle-timeout.");
+    }
+    Ok(())
+}
 
 /// Entry point: initialize terminal + run app.
 fn main() -> Result<()> {
     color_eyre::install()?;
+    let args = cli::CliArgs::parse()?;
+    init_tracing(&args.data_dir)?;
+    if let Some(command) = args.command.clone() {
+        return run_command(command, &args);
+    }
+    // Also chains a panic hook in front of color_eyre's that restores the
+    // terminal, so a crash doesn't leave the shell in raw mode.
     let terminal = ratatui::init();
-    let result = app::App::new().run(terminal);
+    let result = app::App::from_args(args).run(terminal);
     ratatui::restore();
     result
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Write},
+};
+
+/// Sidecar metadata written next to each capture as `<name>.manifest`,
+/// one `key=value` pair per line.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    entries: Vec<(String, String)>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key`, replacing any earlier value.
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        let value = value.to_string().replace('\n', " ");
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut out = File::create(path)?;
+        for (k, v) in &self.entries {
+            writeln!(out, "{}={}", k, v)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut manifest = Self::new();
+        for line in content.lines() {
+            if let Some((k, v)) = line.split_once('=') {
+                manifest.set(k.trim(), v.trim());
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// `saved_data/foo.csv` -> `saved_data/foo.manifest`.
+pub fn manifest_path_for(csv_filename: &str) -> String {
+    let base = csv_filename.strip_suffix(".csv").unwrap_or(csv_filename);
+    format!("{}.manifest", base)
+}
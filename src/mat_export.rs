@@ -0,0 +1,176 @@
+use std::{fs, io};
+
+use crate::csi_packet::CsiPacket;
+
+// Data types and array class of the MAT-file v5 format.
+const MI_INT8: u32 = 1;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+const MX_DOUBLE_CLASS: u32 = 6;
+const COMPLEX_FLAG: u32 = 0x0800;
+
+/// Descriptive text at the start of the 128-byte header. Fixed, so the same
+/// capture always exports to the same bytes.
+const HEADER_TEXT: &str = "MATLAB 5.0 MAT-file, written by esp-csi-tui-rs";
+
+/// Append a data element: its tag, `data`, and padding to 8 bytes.
+fn push_element(out: &mut Vec<u8>, data_type: u32, data: &[u8]) -> io::Result<()> {
+    let size = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "capture too large for a v5 .mat file"))?;
+    out.extend_from_slice(&data_type.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(data);
+    out.resize(out.len().next_multiple_of(8), 0);
+    Ok(())
+}
+
+fn f64_bytes(values: &[f64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Append a `rows × cols` double matrix named `name`. Values are
+/// column-major, as MATLAB stores them.
+fn push_matrix(
+    out: &mut Vec<u8>,
+    name: &str,
+    rows: usize,
+    cols: usize,
+    real: &[f64],
+    imag: Option<&[f64]>,
+) -> io::Result<()> {
+    let mut body = Vec::new();
+    let flags = MX_DOUBLE_CLASS | if imag.is_some() { COMPLEX_FLAG } else { 0 };
+    push_element(&mut body, MI_UINT32, &[flags.to_le_bytes(), [0; 4]].concat())?;
+    let dims = [rows as i32, cols as i32];
+    push_element(&mut body, MI_INT32, &dims.iter().flat_map(|d| d.to_le_bytes()).collect::<Vec<u8>>())?;
+    push_element(&mut body, MI_INT8, name.as_bytes())?;
+    push_element(&mut body, MI_DOUBLE, &f64_bytes(real))?;
+    if let Some(imag) = imag {
+        push_element(&mut body, MI_DOUBLE, &f64_bytes(imag))?;
+    }
+    push_element(out, MI_MATRIX, &body)
+}
+
+/// A capture as a MATLAB v5 `.mat` file, with one row per packet:
+/// `timestamp_us` and `rssi` (N×1), `csi` (N×S complex, `I + jQ` per
+/// subcarrier), and `antenna` / `host_time_ms` (N×1, NaN where a packet has
+/// none) when the capture records them. All variables are doubles. The
+/// subcarrier count is the first packet's; other packets are cut or padded
+/// with zeros to match.
+pub fn mat_bytes(packets: &[CsiPacket]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut text = HEADER_TEXT.as_bytes().to_vec();
+    text.resize(116, b' ');
+    out.extend_from_slice(&text);
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&0x0100u16.to_le_bytes());
+    out.extend_from_slice(b"IM");
+
+    let n = packets.len();
+    let column = |value: &dyn Fn(&CsiPacket) -> f64| packets.iter().map(value).collect::<Vec<f64>>();
+    push_matrix(&mut out, "timestamp_us", n, 1, &column(&|p| p.esp_timestamp as f64), None)?;
+    push_matrix(&mut out, "rssi", n, 1, &column(&|p| p.rssi as f64), None)?;
+    let subcarriers = packets.first().map_or(0, |p| p.csi_values.len() / 2);
+    let (mut real, mut imag) = (Vec::with_capacity(n * subcarriers), Vec::with_capacity(n * subcarriers));
+    for sc in 0..subcarriers {
+        for p in packets {
+            real.push(p.csi_values.get(2 * sc).copied().unwrap_or(0) as f64);
+            imag.push(p.csi_values.get(2 * sc + 1).copied().unwrap_or(0) as f64);
+        }
+    }
+    push_matrix(&mut out, "csi", n, subcarriers, &real, Some(&imag))?;
+    if packets.iter().any(|p| p.antenna.is_some()) {
+        let antenna = column(&|p| p.antenna.map_or(f64::NAN, f64::from));
+        push_matrix(&mut out, "antenna", n, 1, &antenna, None)?;
+    }
+    if packets.iter().any(|p| p.host_time_ms.is_some()) {
+        let host_time = column(&|p| p.host_time_ms.map_or(f64::NAN, |ms| ms as f64));
+        push_matrix(&mut out, "host_time_ms", n, 1, &host_time, None)?;
+    }
+    Ok(out)
+}
+
+/// Write [`mat_bytes`] of `packets` to `path`.
+pub fn write_mat(path: &str, packets: &[CsiPacket]) -> io::Result<()> {
+    fs::write(path, mat_bytes(packets)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A matrix read back from a .mat file: name, dims, real and imaginary parts.
+    type Variable = (String, Vec<i32>, Vec<f64>, Option<Vec<f64>>);
+
+    fn read_element(bytes: &[u8], pos: &mut usize) -> (u32, Vec<u8>) {
+        let data_type = u32::from_le_bytes(bytes[*pos..*pos + 4].try_into().unwrap());
+        let size = u32::from_le_bytes(bytes[*pos + 4..*pos + 8].try_into().unwrap()) as usize;
+        let data = bytes[*pos + 8..*pos + 8 + size].to_vec();
+        *pos = (*pos + 8 + size).next_multiple_of(8);
+        (data_type, data)
+    }
+
+    fn read_mat(bytes: &[u8]) -> Vec<Variable> {
+        assert_eq!(&bytes[124..128], &[0x00, 0x01, b'I', b'M']);
+        let doubles = |data: Vec<u8>| -> Vec<f64> {
+            data.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap())).collect()
+        };
+        let mut variables = Vec::new();
+        let mut pos = 128;
+        while pos < bytes.len() {
+            let (data_type, body) = read_element(bytes, &mut pos);
+            assert_eq!(data_type, MI_MATRIX);
+            let mut at = 0;
+            let (_, flags) = read_element(&body, &mut at);
+            let flags = u32::from_le_bytes(flags[..4].try_into().unwrap());
+            assert_eq!(flags & 0xff, MX_DOUBLE_CLASS);
+            let (_, dims) = read_element(&body, &mut at);
+            let dims = dims.chunks_exact(4).map(|c| i32::from_le_bytes(c.try_into().unwrap())).collect();
+            let (_, name) = read_element(&body, &mut at);
+            let (_, real) = read_element(&body, &mut at);
+            let imag = (flags & COMPLEX_FLAG != 0).then(|| doubles(read_element(&body, &mut at).1));
+            assert_eq!(at, body.len());
+            variables.push((String::from_utf8(name).unwrap(), dims, doubles(real), imag));
+        }
+        variables
+    }
+
+    #[test]
+    fn capture_reads_back_from_mat() {
+        let packets = [
+            CsiPacket {
+                esp_timestamp: 1_000_000,
+                rssi: -40,
+                csi_values: vec![3, 4, -1, 2],
+                antenna: Some(1),
+                host_time_ms: None,
+            },
+            CsiPacket {
+                esp_timestamp: 1_010_000,
+                rssi: -41,
+                csi_values: vec![6, -8, 0, 5],
+                antenna: None,
+                host_time_ms: None,
+            },
+        ];
+        let bytes = mat_bytes(&packets).unwrap();
+        assert_eq!(bytes, mat_bytes(&packets).unwrap());
+        assert!(bytes.starts_with(b"MATLAB 5.0 MAT-file"));
+
+        let variables = read_mat(&bytes);
+        let names: Vec<&str> = variables.iter().map(|v| v.0.as_str()).collect();
+        assert_eq!(names, ["timestamp_us", "rssi", "csi", "antenna"]);
+        assert_eq!(variables[0].1, vec![2, 1]);
+        assert_eq!(variables[0].2, vec![1_000_000.0, 1_010_000.0]);
+        assert_eq!(variables[1].2, vec![-40.0, -41.0]);
+        let (_, dims, real, imag) = &variables[2];
+        assert_eq!(dims, &vec![2, 2]);
+        // Column-major: subcarrier 0 for both packets, then subcarrier 1.
+        assert_eq!(real, &vec![3.0, 6.0, -1.0, 0.0]);
+        assert_eq!(imag.as_ref().unwrap(), &vec![4.0, -8.0, 2.0, 5.0]);
+        assert_eq!(variables[3].2[0], 1.0);
+        assert!(variables[3].2[1].is_nan());
+    }
+}
@@ -0,0 +1,46 @@
+/// Significant digits shown for plotted values in labels and the status line.
+pub const DISPLAY_DIGITS: usize = 4;
+
+/// `value` rounded to `digits` significant digits: `0.001234`, `12.35`,
+/// `1235`. Values below 1e-3 or from 1e6 up switch to scientific notation
+/// (`1.234e-5`) so they neither read as zero nor grow wide.
+pub fn format_sig(value: f64, digits: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let digits = digits.max(1);
+    let magnitude = value.abs().log10().floor() as i32;
+    if !(-3..6).contains(&magnitude) {
+        return format!("{:.*e}", digits - 1, value);
+    }
+    let decimals = (digits as i32 - 1 - magnitude).max(0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+/// [`format_sig`] at [`DISPLAY_DIGITS`].
+pub fn display(value: f64) -> String {
+    format_sig(value, DISPLAY_DIGITS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_significant_digits_across_magnitudes() {
+        assert_eq!(display(0.0), "0");
+        assert_eq!(display(0.0012346), "0.001235");
+        assert_eq!(display(0.5), "0.5000");
+        assert_eq!(display(12.345), "12.35");
+        assert_eq!(display(-42.0), "-42.00");
+        assert_eq!(display(1234.5), "1234");
+        assert_eq!(display(98_765.4), "98765");
+        assert_eq!(display(0.0000123), "1.230e-5");
+        assert_eq!(display(2.5e7), "2.500e7");
+        assert_eq!(display(f64::NAN), "NaN");
+        assert_eq!(format_sig(1.2345, 2), "1.2");
+    }
+}
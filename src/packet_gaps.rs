@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+/// Number of recent inter-packet intervals used for the running median.
+const INTERVAL_WINDOW: usize = 64;
+/// Intervals needed before gaps are reported, so the median is meaningful.
+const MIN_INTERVALS: usize = 8;
+/// A delta larger than this multiple of the median interval counts as a gap.
+const GAP_FACTOR: f64 = 1.5;
+
+/// Estimates dropped packets from gaps between consecutive ESP timestamps.
+///
+/// Each delta is compared against the running median interval; a delta of
+/// `n` medians means roughly `n - 1` packets were lost in between.
+#[derive(Debug, Default)]
+pub struct GapDetector {
+    last_ts: Option<u64>,
+    intervals: VecDeque<u64>,
+    pub gaps: u64,
+    pub dropped: u64,
+}
+
+impl GapDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next packet timestamp (microseconds). Returns the estimated
+    /// number of packets dropped just before it, if a gap was detected.
+    pub fn observe(&mut self, ts: u64) -> Option<u64> {
+        let prev = self.last_ts.replace(ts)?;
+        // Timestamp went backwards (ESP reset / wrap): start over.
+        if ts <= prev {
+            self.intervals.clear();
+            return None;
+        }
+        let delta = ts - prev;
+
+        let mut dropped = None;
+        if let Some(median) = self.median_interval()
+            && median > 0
+            && delta as f64 > median as f64 * GAP_FACTOR
+        {
+            let missing = ((delta as f64 / median as f64).round() as u64).saturating_sub(1).max(1);
+            self.gaps += 1;
+            self.dropped += missing;
+            dropped = Some(missing);
+        }
+
+        // Gaps are not typical intervals; keep them out of the median.
+        if dropped.is_none() {
+            self.intervals.push_back(delta);
+            if self.intervals.len() > INTERVAL_WINDOW {
+                self.intervals.pop_front();
+            }
+        }
+        dropped
+    }
+
+    pub fn median_interval(&self) -> Option<u64> {
+        if self.intervals.len() < MIN_INTERVALS {
+            return None;
+        }
+        let mut sorted: Vec<u64> = self.intervals.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::{Pattern, SyntheticCsi};
+
+    #[test]
+    fn estimates_packets_dropped_in_a_gap() {
+        let mut gaps = GapDetector::new();
+        let packets = SyntheticCsi::new(8, 10.0, Pattern::Constant).interval_us(5_000);
+        // Frames 20..23 never arrive.
+        let reported: Vec<u64> = packets
+            .enumerate()
+            .take(40)
+            .filter(|(idx, _)| !(20..23).contains(idx))
+            .filter_map(|(_, p)| gaps.observe(p.esp_timestamp))
+            .collect();
+        assert_eq!(reported, vec![3]);
+        assert_eq!(gaps.median_interval(), Some(5_000));
+        assert_eq!((gaps.gaps, gaps.dropped), (1, 3));
+    }
+
+    #[test]
+    fn gaps_wait_for_enough_intervals_and_reset_on_a_backwards_timestamp() {
+        let mut gaps = GapDetector::new();
+        // A long delta before MIN_INTERVALS deltas are seen is not a gap.
+        assert_eq!(gaps.observe(0), None);
+        assert_eq!(gaps.observe(50_000), None);
+        for ts in (1..=MIN_INTERVALS as u64).map(|i| 50_000 + i * 1_000) {
+            assert_eq!(gaps.observe(ts), None);
+        }
+        // 8 short deltas and one long one; the median is the short interval.
+        assert_eq!(gaps.median_interval(), Some(1_000));
+        // 1.4 medians is jitter, 4 medians is a gap with 3 packets missing.
+        assert_eq!(gaps.observe(59_400), None);
+        assert_eq!(gaps.observe(63_400), Some(3));
+        assert_eq!(gaps.median_interval(), Some(1_000));
+        // An ESP reset starts the median over.
+        assert_eq!(gaps.observe(100), None);
+        assert_eq!(gaps.median_interval(), None);
+        assert_eq!((gaps.gaps, gaps.dropped), (1, 3));
+    }
+}
@@ -0,0 +1,240 @@
+use std::{fs::File, io, sync::Arc};
+
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, UInt8Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema, SchemaRef, UInt8Type, UInt64Type};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::csi_packet::CsiPacket;
+use crate::csv_utils::{ANTENNA_COLUMN, HOST_TIME_COLUMN};
+
+/// Rows buffered per Parquet row group.
+const ROW_GROUP_ROWS: usize = 4096;
+
+/// File format captures are recorded in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// One text row per packet, flushed as it goes (default).
+    #[default]
+    Csv,
+    /// Typed, compressed columns with the same names as the CSV header.
+    Parquet,
+}
+
+impl CaptureFormat {
+    pub const ALL: [CaptureFormat; 2] = [CaptureFormat::Csv, CaptureFormat::Parquet];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaptureFormat::Csv => "csv",
+            CaptureFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn by_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.label() == label)
+    }
+
+    /// `saved_data/foo.csv` -> `saved_data/foo.parquet` for Parquet.
+    pub fn path_for(self, csv_filename: &str) -> String {
+        match self {
+            CaptureFormat::Csv => csv_filename.to_string(),
+            CaptureFormat::Parquet => {
+                let base = csv_filename.strip_suffix(".csv").unwrap_or(csv_filename);
+                format!("{}.parquet", base)
+            }
+        }
+    }
+}
+
+/// Whether `path` names a Parquet capture.
+pub fn is_parquet(path: &str) -> bool {
+    path.ends_with(".parquet")
+}
+
+/// Writes packets as Parquet, laid out like the capture CSV:
+/// `[host_time_ms,]esp_timestamp_us,rssi[,antenna],i0,q0,...`. The columns
+/// are fixed by the first packet; later packets with a different I/Q count
+/// are dropped. Nothing is readable until [`ParquetPacketWriter::finish`]
+/// writes the footer.
+pub struct ParquetPacketWriter {
+    file: Option<File>,
+    writer: Option<ArrowWriter<File>>,
+    schema: Option<SchemaRef>,
+    /// I/Q values per row, set by the first packet.
+    iq_values: usize,
+    pending: Vec<CsiPacket>,
+    /// Packets dropped for not matching the first packet's I/Q count.
+    pub dropped: u64,
+}
+
+impl ParquetPacketWriter {
+    pub fn new(file: File) -> Self {
+        Self {
+            file: Some(file),
+            writer: None,
+            schema: None,
+            iq_values: 0,
+            pending: Vec::with_capacity(ROW_GROUP_ROWS),
+            dropped: 0,
+        }
+    }
+
+    pub fn write(&mut self, packet: &CsiPacket) -> io::Result<()> {
+        if self.schema.is_none() {
+            let schema = capture_schema(packet);
+            let props = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .set_max_row_group_size(ROW_GROUP_ROWS)
+                .build();
+            let file = self.file.take().ok_or_else(|| io::Error::other("writer already finished"))?;
+            self.writer = Some(ArrowWriter::try_new(file, schema.clone(), Some(props)).map_err(io::Error::other)?);
+            self.schema = Some(schema);
+            self.iq_values = packet.csi_values.len() / 2 * 2;
+        }
+        if packet.csi_values.len() / 2 * 2 != self.iq_values {
+            self.dropped += 1;
+            tracing::debug!(values = packet.csi_values.len(), "parquet: dropped frame with a different I/Q count");
+            return Ok(());
+        }
+        self.pending.push(packet.clone());
+        if self.pending.len() >= ROW_GROUP_ROWS {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered rows and the footer.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.write_pending()?;
+        if let Some(writer) = self.writer.take() {
+            writer.close().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let (Some(schema), Some(writer)) = (&self.schema, &mut self.writer) else {
+            return Ok(());
+        };
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = packets_to_batch(schema, &self.pending)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+fn capture_schema(packet: &CsiPacket) -> SchemaRef {
+    let mut fields = Vec::new();
+    if packet.host_time_ms.is_some() {
+        fields.push(Field::new(HOST_TIME_COLUMN, DataType::UInt64, false));
+    }
+    fields.push(Field::new("esp_timestamp_us", DataType::UInt64, false));
+    fields.push(Field::new("rssi", DataType::Int32, false));
+    if packet.antenna.is_some() {
+        fields.push(Field::new(ANTENNA_COLUMN, DataType::UInt8, false));
+    }
+    for sc in 0..packet.csi_values.len() / 2 {
+        fields.push(Field::new(format!("i{}", sc), DataType::Int32, false));
+        fields.push(Field::new(format!("q{}", sc), DataType::Int32, false));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+fn packets_to_batch(schema: &SchemaRef, packets: &[CsiPacket]) -> io::Result<RecordBatch> {
+    let first = &packets[0];
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+    if first.host_time_ms.is_some() {
+        let host_time: UInt64Array = packets.iter().map(|p| p.host_time_ms.unwrap_or(0)).collect();
+        columns.push(Arc::new(host_time));
+    }
+    columns.push(Arc::new(packets.iter().map(|p| p.esp_timestamp).collect::<UInt64Array>()));
+    columns.push(Arc::new(packets.iter().map(|p| p.rssi).collect::<Int32Array>()));
+    if first.antenna.is_some() {
+        let antenna: UInt8Array = packets.iter().map(|p| p.antenna.unwrap_or(0)).collect();
+        columns.push(Arc::new(antenna));
+    }
+    for col in 0..schema.fields().len() - columns.len() {
+        columns.push(Arc::new(packets.iter().map(|p| p.csi_values[col]).collect::<Int32Array>()));
+    }
+    RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)
+}
+
+/// Read a Parquet capture written by [`ParquetPacketWriter`] back into packets.
+pub fn load_packets(path: &str) -> io::Result<Vec<CsiPacket>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid(e.to_string()))?;
+    let mut packets = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| invalid(e.to_string()))?;
+        let column = |name: &str| batch.column_by_name(name);
+        let u64_column = |name: &str| column(name).and_then(|c| c.as_primitive_opt::<UInt64Type>());
+        let timestamps = u64_column("esp_timestamp_us")
+            .ok_or_else(|| invalid(format!("{} has no esp_timestamp_us column", path)))?;
+        let rssi = column("rssi")
+            .and_then(|c| c.as_primitive_opt::<Int32Type>())
+            .ok_or_else(|| invalid(format!("{} has no rssi column", path)))?;
+        let host_time = u64_column(HOST_TIME_COLUMN);
+        let antenna = column(ANTENNA_COLUMN).and_then(|c| c.as_primitive_opt::<UInt8Type>());
+        let mut iq = Vec::new();
+        for sc in 0.. {
+            let (Some(i), Some(q)) = (column(&format!("i{}", sc)), column(&format!("q{}", sc))) else {
+                break;
+            };
+            match (i.as_primitive_opt::<Int32Type>(), q.as_primitive_opt::<Int32Type>()) {
+                (Some(i), Some(q)) => iq.extend([i, q]),
+                _ => return Err(invalid(format!("{}: I/Q columns must be int32", path))),
+            }
+        }
+        for row in 0..batch.num_rows() {
+            packets.push(CsiPacket {
+                esp_timestamp: timestamps.value(row),
+                rssi: rssi.value(row),
+                csi_values: iq.iter().map(|c| if c.is_null(row) { 0 } else { c.value(row) }).collect(),
+                antenna: antenna.map(|a| a.value(row)),
+                host_time_ms: host_time.map(|h| h.value(row)),
+            });
+        }
+    }
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_round_trip_through_parquet() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-parquet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.parquet");
+        let packets: Vec<CsiPacket> = (0..5000u64)
+            .map(|n| CsiPacket {
+                esp_timestamp: 1_000_000 + n * 10_000,
+                rssi: -40 - (n % 7) as i32,
+                csi_values: vec![n as i32 % 50, -3, 4, -(n as i32 % 20)],
+                antenna: Some((n % 2) as u8),
+                host_time_ms: Some(1_700_000_000_000 + n * 10),
+            })
+            .collect();
+        let mut writer = ParquetPacketWriter::new(File::create(&path).unwrap());
+        for packet in &packets {
+            writer.write(packet).unwrap();
+        }
+        // A frame with a different subcarrier count doesn't fit the columns.
+        writer.write(&CsiPacket { csi_values: vec![1, 2], ..packets[0].clone() }).unwrap();
+        assert_eq!(writer.dropped, 1);
+        writer.finish().unwrap();
+
+        assert_eq!(load_packets(path.to_str().unwrap()).unwrap(), packets);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
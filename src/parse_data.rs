@@ -1,21 +1,307 @@
 use crate::csi_packet;
-use crate::csi_packet::CsiCliParser;
-use crate::wifi_mode::apply_wifi_config;
-use crate::{csv_utils, esp_port::send_cli_command, wifi_mode::WifiMode};
+use crate::csi_packet::{CsiCliParser, ValueFormat};
+use crate::dsp::RollingVariance;
+use crate::input_source::{InputSource, UdpReader};
+use crate::packet_gaps::GapDetector;
+use crate::stuck_frames::StuckFrameDetector;
+use crate::quality::{QualityScore, QualityTracker};
+use crate::read_data::{
+    AmplitudeMetric, Quantity, VARIANCE_WINDOW, clamp_subcarrier_range, packet_metric_values, packet_sample,
+};
+use crate::calibration::Calibration;
+use crate::trigger::{Trigger, TriggerMode};
+use crate::manifest::{Manifest, manifest_path_for};
+use crate::parquet_capture::{CaptureFormat, ParquetPacketWriter};
+use crate::wifi_mode::{CsiConfig, apply_wifi_config};
+use crate::{csv_utils, esp_port::{self, send_cli_command}, wifi_mode::WifiMode};
 use color_eyre::Result;
-use serialport::{DataBits, FlowControl, Parity, StopBits};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
 use std::{
+    error::Error,
+    fmt,
     fs::File,
-    io::{self, Read, Write},
-    sync::mpsc,
+    io::{self, BufReader, BufWriter, Read, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+/// Default minimum time between live plot points sent to the UI.
+pub const DEFAULT_PLOT_INTERVAL: Duration = Duration::from_millis(20);
+/// Default minimum time between live heatmap snapshots sent to the UI.
+pub const DEFAULT_HEATMAP_INTERVAL: Duration = Duration::from_millis(250);
+/// Packets kept in the scrolling live heatmap.
+pub const LIVE_HEATMAP_ROWS: usize = 50;
+/// Default number of CSV rows buffered between flushes to disk.
+pub const DEFAULT_FLUSH_EVERY: u64 = 100;
+/// Upper bound on opening a board and applying its settings: the console
+/// wait plus margin for the port open, reset and config commands. A shared
+/// `start_at` needs at least this much lead so every board makes it.
+pub const BOARD_SETUP_TIME: Duration = esp_port::PROMPT_TIMEOUT.saturating_add(Duration::from_secs(2));
+
+/// Packet rate assumed by [`estimate_output_bytes`] unless configured.
+pub const DEFAULT_EXPECTED_RATE_HZ: u32 = 100;
+/// CSV bytes per I/Q value (`-12,`) and per row for timestamp and RSSI.
+const CSV_BYTES_PER_VALUE: u64 = 4;
+const CSV_BYTES_PER_ROW: u64 = 20;
+/// Rerun bytes per frame: raw I/Q and amplitude tensors as f32, plus
+/// per-log overhead.
+const RRD_BYTES_PER_VALUE: u64 = 6;
+const RRD_BYTES_PER_FRAME: u64 = 256;
+/// I/Q values per frame assumed by the estimate (64 subcarriers).
+const ESTIMATE_IQ_VALUES: u64 = 128;
+
+/// Rough on-disk size of a capture of `duration_secs` at `rate_hz`
+/// packets per second: the CSV, plus the `.rrd` when saving to file.
+pub fn estimate_output_bytes(duration_secs: u64, rate_hz: u32, rerun_sink: &RerunSink) -> u64 {
+    let frames = duration_secs.saturating_mul(rate_hz as u64);
+    let mut per_frame = CSV_BYTES_PER_ROW + CSV_BYTES_PER_VALUE * ESTIMATE_IQ_VALUES;
+    if *rerun_sink == RerunSink::File {
+        // Amplitudes are half as many values as I/Q.
+        per_frame += RRD_BYTES_PER_FRAME + RRD_BYTES_PER_VALUE * ESTIMATE_IQ_VALUES * 3 / 2;
+    }
+    frames.saturating_mul(per_frame)
+}
+
+/// Progress / outcome of a capture, sent live and returned at the end.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordingStats {
+    pub lines_written: u64,
+    /// Frames written to the capture (and logged to Rerun): those left after
+    /// warm-up, the trigger and decimation.
+    pub frames_logged: u64,
+    /// Packets after warm-up and the trigger, including those decimation
+    /// skipped. Gaps and stuck frames are judged on all of them.
+    pub packets_received: u64,
+    /// Timestamp gaps larger than the typical inter-packet interval.
+    pub gaps_detected: u64,
+    /// Estimated number of packets lost in those gaps.
+    pub dropped_packets: u64,
+    /// Runs of identical CSI frames long enough to suggest a wedged link.
+    pub stuck_runs: u64,
+    /// Frame lines dropped because they couldn't be parsed.
+    pub malformed_lines: u64,
+    /// RSSI of the most recent packet.
+    pub last_rssi: Option<i32>,
+    /// Mean RSSI over all packets.
+    pub mean_rssi: Option<f64>,
+    /// Spread of inter-packet intervals (std / mean), gaps excluded.
+    pub interval_cv: Option<f64>,
+    /// Subcarrier the live plot follows, once the first frame has shown
+    /// how many there are (clamped if the requested one is out of range).
+    pub plot_subcarrier: Option<usize>,
+    /// The capture ended early because no packet arrived within the idle
+    /// timeout.
+    pub stopped_idle: bool,
+    /// The capture was stopped early through [`RecordingConfig::cancel`].
+    pub cancelled: bool,
+    /// An armed trigger gave up waiting; nothing was recorded.
+    pub trigger_timed_out: bool,
+    /// Packets parsed but discarded as warm-up at the start.
+    pub warmup_skipped: u64,
+}
+
+/// Why a recording failed, so callers can react per cause (rescan ports,
+/// warn about a silent board, ...).
+#[derive(Debug)]
+pub enum RecordError {
+    /// The serial port (or UDP socket) couldn't be opened or set up.
+    OpenPort { port: String, source: io::Error },
+    /// A command to the board couldn't be written.
+    ConfigWrite(io::Error),
+    /// The input couldn't be read, e.g. the raw log of a replay.
+    Read(io::Error),
+    /// The capture file, its raw log or its manifest couldn't be written.
+    CsvWrite(io::Error),
+    /// The Rerun stream couldn't be opened, or Rerun support isn't built in.
+    Rerun(String),
+    /// Stopped on request before the duration elapsed. The rows captured so
+    /// far and the manifest (with `cancelled=true`) are kept.
+    Cancelled,
+    /// The capture ran to its end without a single packet.
+    NoPacketsReceived,
+    /// The armed trigger didn't fire within its timeout, so nothing was
+    /// recorded.
+    TriggerTimedOut,
+    /// The recording thread died without reporting back.
+    WorkerLost,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::OpenPort { port, source } => write!(f, "cannot open {}: {}", port, source),
+            RecordError::ConfigWrite(e) => write!(f, "cannot send commands to the ESP: {}", e),
+            RecordError::Read(e) => write!(f, "cannot read input: {}", e),
+            RecordError::CsvWrite(e) => write!(f, "cannot write the capture: {}", e),
+            RecordError::Rerun(e) => write!(f, "Rerun: {}", e),
+            RecordError::Cancelled => write!(f, "recording cancelled"),
+            RecordError::NoPacketsReceived => write!(f, "no packets received"),
+            RecordError::TriggerTimedOut => write!(f, "the trigger never fired"),
+            RecordError::WorkerLost => write!(f, "recording thread disconnected unexpectedly"),
+        }
+    }
+}
+
+impl Error for RecordError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordError::OpenPort { source: e, .. }
+            | RecordError::ConfigWrite(e)
+            | RecordError::Read(e)
+            | RecordError::CsvWrite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Where the Rerun recording stream is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RerunSink {
+    /// Save to the `.rrd` file next to the CSV (default).
+    File,
+    /// Stream to a running Rerun viewer over gRPC. `None` uses Rerun's default address.
+    Connect(Option<String>),
+    /// Spawn a local Rerun viewer and stream to it.
+    Spawn,
+    /// Skip Rerun entirely; only the CSV is written.
+    Disabled,
+}
+
+impl Default for RerunSink {
+    fn default() -> Self {
+        if cfg!(feature = "rerun") {
+            RerunSink::File
+        } else {
+            RerunSink::Disabled
+        }
+    }
+}
+
+/// Which `csi/...` entities are logged to Rerun per frame. Every entity
+/// is a separate write, so trimming the set speeds up fast captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RerunEntities {
+    pub rssi: bool,
+    pub raw_iq: bool,
+    pub amplitude_tensor: bool,
+    pub amplitude_plot: bool,
+    /// `csi/subcarrier_<k>/amplitude` for every 8th subcarrier.
+    pub subcarrier_scalars: bool,
+    pub phase_tensor: bool,
+}
+
+impl Default for RerunEntities {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl RerunEntities {
+    pub const ALL: Self = Self {
+        rssi: true,
+        raw_iq: true,
+        amplitude_tensor: true,
+        amplitude_plot: true,
+        subcarrier_scalars: true,
+        phase_tensor: true,
+    };
+    pub const NAMES: [&'static str; 6] = [
+        "rssi",
+        "raw_iq",
+        "amplitude_tensor",
+        "amplitude_plot",
+        "subcarrier_scalars",
+        "phase_tensor",
+    ];
+
+    /// Parse a comma-separated list of entity names, e.g. `rssi,amplitude_tensor`.
+    pub fn parse(list: &str) -> std::result::Result<Self, String> {
+        let mut out = Self {
+            rssi: false,
+            raw_iq: false,
+            amplitude_tensor: false,
+            amplitude_plot: false,
+            subcarrier_scalars: false,
+            phase_tensor: false,
+        };
+        for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let flag = match name {
+                "rssi" => &mut out.rssi,
+                "raw_iq" => &mut out.raw_iq,
+                "amplitude_tensor" => &mut out.amplitude_tensor,
+                "amplitude_plot" => &mut out.amplitude_plot,
+                "subcarrier_scalars" => &mut out.subcarrier_scalars,
+                "phase_tensor" => &mut out.phase_tensor,
+                other => {
+                    return Err(format!(
+                        "unknown Rerun entity '{}' (expected {})",
+                        other,
+                        Self::NAMES.join(", ")
+                    ));
+                }
+            };
+            *flag = true;
+        }
+        Ok(out)
+    }
+
+    /// The enabled entity names, comma-separated.
+    pub fn names(&self) -> String {
+        let flags = [
+            self.rssi,
+            self.raw_iq,
+            self.amplitude_tensor,
+            self.amplitude_plot,
+            self.subcarrier_scalars,
+            self.phase_tensor,
+        ];
+        Self::NAMES
+            .iter()
+            .zip(flags)
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Rerun stream handed to the capture loop; uninhabited without the feature.
+#[cfg(feature = "rerun")]
+pub type RerunStream = rerun::RecordingStream;
+#[cfg(not(feature = "rerun"))]
+pub type RerunStream = std::convert::Infallible;
+
+#[cfg(feature = "rerun")]
+fn open_recording_stream(
+    sink: &RerunSink,
+    rrd_filename: &str,
+) -> Result<Option<rerun::RecordingStream>, RecordError> {
+    let builder = rerun::RecordingStreamBuilder::new("esp-csi-tui-rs");
+    let rec = match sink {
+        RerunSink::File => builder.save(rrd_filename).map_err(|e| e.to_string()),
+        RerunSink::Connect(Some(url)) => builder.connect_grpc_opts(url.clone()).map_err(|e| e.to_string()),
+        RerunSink::Connect(None) => builder.connect_grpc().map_err(|e| e.to_string()),
+        RerunSink::Spawn => builder.spawn().map_err(|e| e.to_string()),
+        RerunSink::Disabled => return Ok(None),
+    };
+    rec.map(Some).map_err(RecordError::Rerun)
+}
+
+/// Log one frame's `entities`. The raw I/Q, amplitude and phase tensors and
+/// the amplitude point cloud are only logged `with_tensors`; RSSI and the
+/// subcarrier scalars always are.
+#[cfg(feature = "rerun")]
 pub fn log_csi_frame(
     rec: &rerun::RecordingStream,
     frame_idx: u64,
     packet: &csi_packet::CsiPacket,
+    entities: &RerunEntities,
+    with_tensors: bool,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use rerun::external::ndarray;
     rec.set_time_sequence("frame", frame_idx as i64);
@@ -24,34 +310,44 @@ pub fn log_csi_frame(
         rerun::TimeCell::from_sequence(packet.esp_timestamp as i64),
     );
 
-    rec.log("csi/rssi", &rerun::Scalars::new([packet.rssi as f64]));
-    let raw_values: Vec<f32> = packet.csi_values.iter().map(|&v| v as f32).collect();
-    if !raw_values.is_empty() {
+    if entities.rssi {
+        rec.log("csi/rssi", &rerun::Scalars::new([packet.rssi as f64]))?;
+    }
+    if with_tensors && entities.raw_iq && !packet.csi_values.is_empty() {
+        let raw_values: Vec<f32> = packet.csi_values.iter().map(|&v| v as f32).collect();
         let num_values = raw_values.len();
         let array = ndarray::Array::from_vec(raw_values).into_shape_with_order((1, num_values))?;
         rec.log("csi/raw_iq", &rerun::Tensor::try_from(array)?)?;
     }
 
-    let amplitudes = packet.get_amplitudes();
+    let wants_amplitude =
+        (with_tensors && (entities.amplitude_tensor || entities.amplitude_plot)) || entities.subcarrier_scalars;
+    let amplitudes = if wants_amplitude { packet.get_amplitudes() } else { Vec::new() };
     if !amplitudes.is_empty() {
         let num_subcarriers = amplitudes.len();
-        let amp_array = ndarray::Array::from_vec(amplitudes.clone())
-            .into_shape_with_order((1, num_subcarriers))?;
-        rec.log("csi/amplitude_tensor", &rerun::Tensor::try_from(amp_array)?)?;
-        let points: Vec<rerun::Position2D> = amplitudes
-            .iter()
-            .enumerate()
-            .map(|(i, &amp)| rerun::Position2D::new(i as f32, amp))
-            .collect();
-        rec.log("csi/amplitude_plot", &rerun::Points2D::new(points))?;
-        for (i, &amp) in amplitudes.iter().enumerate().step_by(8) {
-            rec.log(
-                format!("csi/subcarrier_{}/amplitude", i),
-                &rerun::Scalars::new([amp as f64]),
-            )?;
-        }
-    }
-    let phases = packet.get_phases();
+        if with_tensors && entities.amplitude_tensor {
+            let amp_array = ndarray::Array::from_vec(amplitudes.clone())
+                .into_shape_with_order((1, num_subcarriers))?;
+            rec.log("csi/amplitude_tensor", &rerun::Tensor::try_from(amp_array)?)?;
+        }
+        if with_tensors && entities.amplitude_plot {
+            let points: Vec<rerun::Position2D> = amplitudes
+                .iter()
+                .enumerate()
+                .map(|(i, &amp)| rerun::Position2D::new(i as f32, amp))
+                .collect();
+            rec.log("csi/amplitude_plot", &rerun::Points2D::new(points))?;
+        }
+        if entities.subcarrier_scalars {
+            for (i, &amp) in amplitudes.iter().enumerate().step_by(8) {
+                rec.log(
+                    format!("csi/subcarrier_{}/amplitude", i),
+                    &rerun::Scalars::new([amp as f64]),
+                )?;
+            }
+        }
+    }
+    let phases = if with_tensors && entities.phase_tensor { packet.get_phases() } else { Vec::new() };
     if !phases.is_empty() {
         let num_subcarriers = phases.len();
         let phase_array =
@@ -61,22 +357,723 @@ pub fn log_csi_frame(
     Ok(())
 }
 
+/// Settings for one capture. Start from [`RecordingConfig::new`] and chain
+/// the setters, like `serialport::new(..)`; everything else has a default
+/// (sniffer mode, 10 s, subcarrier 20, no live channels, no trigger).
+#[derive(Debug, Clone)]
+pub struct RecordingConfig {
+    /// Serial port of the board; only a label with [`InputSource::Udp`].
+    pub port_name: String,
+    /// Read from the serial port (default) or from UDP datagrams.
+    pub source: InputSource,
+    /// CSV written by [`record_csi_to_file`]; unused by [`record_csi`].
+    /// With [`CaptureFormat::Parquet`] the capture goes to the same path
+    /// with a `.parquet` extension instead; see [`RecordingConfig::output_filename`].
+    pub csv_filename: String,
+    /// File format [`record_csi_to_file`] writes.
+    pub format: CaptureFormat,
+    /// Used with [`RerunSink::File`]; defaults to the CSV path with `.rrd`.
+    pub rrd_filename: String,
+    /// Copy of every byte read from the serial port, before parsing. Off
+    /// (`None`) by default; see [`RecordingConfig::raw_log`].
+    pub raw_log_filename: Option<String>,
+    pub wifi_mode: WifiMode,
+    pub ssid: String,
+    pub password: String,
+    pub duration_secs: u64,
+    /// Keep only every Nth parsed packet (1 keeps all).
+    pub decimation: usize,
+    /// Flush the CSV to disk every N rows, so a crash or power loss costs
+    /// at most N rows. 0 flushes only at the end.
+    pub flush_every: u64,
+    /// Subcarrier fed to the live plot.
+    pub subcarrier: usize,
+    /// Only frames from this RX antenna feed the live plot/heatmap.
+    pub antenna: Option<u8>,
+    /// Quantity sent to the live plot.
+    pub quantity: Quantity,
+    /// How the live plot derives amplitude from I/Q.
+    pub metric: AmplitudeMetric,
+    /// Receives `(seconds, value)` points, timed by the ESP timestamp from
+    /// the first plotted packet, as when the capture is loaded.
+    pub plot_tx: Option<mpsc::Sender<(f64, f64)>>,
+    /// Minimum time between live plot points.
+    pub plot_interval: Duration,
+    /// Receives the heatmap rows (one per shown packet, its subcarriers per
+    /// `metric`) added since the previous send; the receiver keeps the
+    /// rolling window and scales it.
+    pub heatmap_tx: Option<mpsc::Sender<Vec<Vec<f32>>>>,
+    /// Minimum time between heatmap sends, independent of packet rate.
+    pub heatmap_interval: Duration,
+    /// Inclusive subcarrier range shown in the live heatmap; `None` shows all.
+    pub heatmap_subcarriers: Option<(usize, usize)>,
+    /// Amplitude correction for the live plot and heatmap. The capture
+    /// itself keeps the raw I/Q values.
+    pub calibration: Option<Calibration>,
+    pub rerun_sink: RerunSink,
+    pub rerun_entities: RerunEntities,
+    /// Log the heavy Rerun entities (tensors, point cloud) only on every
+    /// Nth written frame, to bound the `.rrd` size.
+    pub rerun_tensor_every: u64,
+    pub stats_tx: Option<mpsc::Sender<RecordingStats>>,
+    /// Receives every capture warning, e.g. for each malformed line the
+    /// parser drops.
+    pub log_tx: Option<mpsc::Sender<String>>,
+    /// Also print capture warnings to stderr (headless use; never in the TUI).
+    pub warnings_to_stderr: bool,
+    pub csi_config: CsiConfig,
+    /// How decimal CSI arrays are read.
+    pub value_format: ValueFormat,
+    /// Stamp each packet with the host wall-clock time it was parsed at,
+    /// written as a leading `host_time_ms` CSV column.
+    pub host_time: bool,
+    /// End the capture early when no packet has been parsed for this long,
+    /// e.g. sniffing a quiet channel. `None` records the full duration.
+    pub idle_timeout: Option<Duration>,
+    /// Parse but discard this many packets at the start, while the board's
+    /// AGC settles and it associates, before anything is written or shown.
+    pub warmup_packets: u64,
+    /// Assert DTR after opening the port, which resets most ESP dev boards.
+    /// Off for native-USB boards or a board already streaming.
+    pub assert_dtr: bool,
+    /// Drive RTS low after opening the port; on auto-reset circuits an
+    /// asserted RTS holds the chip in reset.
+    pub release_rts: bool,
+    /// Send `restart` after `stop` once the capture ends, rebooting the
+    /// board into a clean idle state.
+    pub restart_after: bool,
+    /// Wall-clock time to send `start` at, to line up several boards.
+    pub start_at: Option<SystemTime>,
+    pub trigger_mode: TriggerMode,
+    /// Set from another thread to stop the capture early.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl RecordingConfig {
+    pub fn new(port_name: impl Into<String>, csv_filename: impl Into<String>) -> Self {
+        let csv_filename = csv_filename.into();
+        let rrd_filename = match csv_filename.strip_suffix(".csv") {
+            Some(stem) => format!("{}.rrd", stem),
+            None => format!("{}.rrd", csv_filename),
+        };
+        Self {
+            port_name: port_name.into(),
+            source: InputSource::default(),
+            csv_filename,
+            format: CaptureFormat::default(),
+            rrd_filename,
+            raw_log_filename: None,
+            wifi_mode: WifiMode::Sniffer,
+            ssid: String::new(),
+            password: String::new(),
+            duration_secs: 10,
+            decimation: 1,
+            flush_every: DEFAULT_FLUSH_EVERY,
+            subcarrier: 20,
+            antenna: None,
+            quantity: Quantity::default(),
+            metric: AmplitudeMetric::default(),
+            plot_tx: None,
+            plot_interval: DEFAULT_PLOT_INTERVAL,
+            heatmap_tx: None,
+            heatmap_interval: DEFAULT_HEATMAP_INTERVAL,
+            heatmap_subcarriers: None,
+            calibration: None,
+            rerun_sink: RerunSink::default(),
+            rerun_entities: RerunEntities::ALL,
+            rerun_tensor_every: 1,
+            stats_tx: None,
+            log_tx: None,
+            warnings_to_stderr: false,
+            csi_config: CsiConfig::default(),
+            value_format: ValueFormat::default(),
+            host_time: false,
+            idle_timeout: None,
+            warmup_packets: 0,
+            assert_dtr: true,
+            release_rts: false,
+            restart_after: false,
+            start_at: None,
+            trigger_mode: TriggerMode::Off,
+            cancel: None,
+        }
+    }
+
+    pub fn format(mut self, format: CaptureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Path the capture is written to in the configured format.
+    pub fn output_filename(&self) -> String {
+        self.format.path_for(&self.csv_filename)
+    }
+
+    pub fn rrd_filename(mut self, rrd_filename: impl Into<String>) -> Self {
+        self.rrd_filename = rrd_filename.into();
+        self
+    }
+
+    pub fn wifi_mode(mut self, wifi_mode: WifiMode) -> Self {
+        self.wifi_mode = wifi_mode;
+        self
+    }
+
+    /// Network to join in station mode.
+    pub fn credentials(mut self, ssid: impl Into<String>, password: impl Into<String>) -> Self {
+        self.ssid = ssid.into();
+        self.password = password.into();
+        self
+    }
+
+    pub fn duration_secs(mut self, duration_secs: u64) -> Self {
+        self.duration_secs = duration_secs;
+        self
+    }
+
+    pub fn decimation(mut self, decimation: usize) -> Self {
+        self.decimation = decimation.max(1);
+        self
+    }
+
+    pub fn flush_every(mut self, flush_every: u64) -> Self {
+        self.flush_every = flush_every;
+        self
+    }
+
+    pub fn subcarrier(mut self, subcarrier: usize) -> Self {
+        self.subcarrier = subcarrier;
+        self
+    }
+
+    pub fn antenna(mut self, antenna: Option<u8>) -> Self {
+        self.antenna = antenna;
+        self
+    }
+
+    pub fn quantity(mut self, quantity: Quantity) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    pub fn metric(mut self, metric: AmplitudeMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn plot_tx(mut self, plot_tx: mpsc::Sender<(f64, f64)>) -> Self {
+        self.plot_tx = Some(plot_tx);
+        self
+    }
+
+    pub fn plot_interval(mut self, plot_interval: Duration) -> Self {
+        self.plot_interval = plot_interval;
+        self
+    }
+
+    pub fn heatmap_tx(mut self, heatmap_tx: mpsc::Sender<Vec<Vec<f32>>>) -> Self {
+        self.heatmap_tx = Some(heatmap_tx);
+        self
+    }
+
+    pub fn heatmap_interval(mut self, heatmap_interval: Duration) -> Self {
+        self.heatmap_interval = heatmap_interval;
+        self
+    }
+
+    pub fn heatmap_subcarriers(mut self, heatmap_subcarriers: Option<(usize, usize)>) -> Self {
+        self.heatmap_subcarriers = heatmap_subcarriers;
+        self
+    }
+
+    pub fn calibration(mut self, calibration: Option<Calibration>) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    pub fn rerun_sink(mut self, rerun_sink: RerunSink) -> Self {
+        self.rerun_sink = rerun_sink;
+        self
+    }
+
+    pub fn rerun_entities(mut self, rerun_entities: RerunEntities) -> Self {
+        self.rerun_entities = rerun_entities;
+        self
+    }
+
+    pub fn rerun_tensor_every(mut self, rerun_tensor_every: u64) -> Self {
+        self.rerun_tensor_every = rerun_tensor_every.max(1);
+        self
+    }
+
+    pub fn stats_tx(mut self, stats_tx: mpsc::Sender<RecordingStats>) -> Self {
+        self.stats_tx = Some(stats_tx);
+        self
+    }
+
+    pub fn log_tx(mut self, log_tx: mpsc::Sender<String>) -> Self {
+        self.log_tx = Some(log_tx);
+        self
+    }
+
+    /// Tee the raw serial stream into `<name>.raw.log` next to the CSV.
+    pub fn raw_log(mut self, enabled: bool) -> Self {
+        self.raw_log_filename = enabled.then(|| raw_log_path_for(&self.csv_filename));
+        self
+    }
+
+    pub fn warnings_to_stderr(mut self, warnings_to_stderr: bool) -> Self {
+        self.warnings_to_stderr = warnings_to_stderr;
+        self
+    }
+
+    /// Report a capture warning to the debug log, stderr (when
+    /// `warnings_to_stderr`) and the `log_tx` receiver.
+    fn emit_warning(&self, warning: String) {
+        tracing::warn!("{}", warning);
+        if self.warnings_to_stderr {
+            eprintln!("{}", warning);
+        }
+        if let Some(tx) = &self.log_tx {
+            let _ = tx.send(warning);
+        }
+    }
+
+    pub fn csi_config(mut self, csi_config: CsiConfig) -> Self {
+        self.csi_config = csi_config;
+        self
+    }
+
+    pub fn value_format(mut self, value_format: ValueFormat) -> Self {
+        self.value_format = value_format;
+        self
+    }
+
+    pub fn host_time(mut self, host_time: bool) -> Self {
+        self.host_time = host_time;
+        self
+    }
+
+    /// Assert DTR after opening the port, resetting most ESP dev boards
+    /// (default). Turn off for boards that must keep running.
+    pub fn assert_dtr(mut self, assert_dtr: bool) -> Self {
+        self.assert_dtr = assert_dtr;
+        self
+    }
+
+    /// Drive RTS low after opening the port, instead of leaving it as the
+    /// OS set it (usually asserted).
+    pub fn release_rts(mut self, release_rts: bool) -> Self {
+        self.release_rts = release_rts;
+        self
+    }
+
+    /// Also send `restart` when the capture ends (`stop` is always sent).
+    pub fn restart_after(mut self, restart_after: bool) -> Self {
+        self.restart_after = restart_after;
+        self
+    }
+
+    /// Read CSI from UDP datagrams instead of the serial port. The board is
+    /// then neither reset, configured nor started by the recorder.
+    pub fn source(mut self, source: InputSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn warmup_packets(mut self, warmup_packets: u64) -> Self {
+        self.warmup_packets = warmup_packets;
+        self
+    }
+
+    pub fn start_at(mut self, start_at: Option<SystemTime>) -> Self {
+        self.start_at = start_at;
+        self
+    }
+
+    pub fn trigger_mode(mut self, trigger_mode: TriggerMode) -> Self {
+        self.trigger_mode = trigger_mode;
+        self
+    }
+
+    /// Stop the capture once `cancel` is set; [`record_csi_to_file`] then
+    /// saves what it has and returns [`RecordError::Cancelled`].
+    pub fn cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
 /// Blocking worker: open serial port, read lines for `seconds`, write to CSV and RRD files.
-pub fn record_csi_to_file(
-    port_name: &str,
-    csv_filename: &str,
-    rrd_filename: &str,
-    wifi_mode: WifiMode,
-    ssid: String,
-    password: String,
-    duration_secs: u64,
-    subcarrier: usize,
-    plot_tx: Option<mpsc::Sender<(f64, f64)>>,
-    heatmap_tx: Option<mpsc::Sender<Vec<Vec<u8>>>>, // Add this parameter
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Initialize Rerun recording stream
-    let rec = rerun::RecordingStreamBuilder::new("esp-csi-tui-rs").save(rrd_filename)?;
+///
+/// Thin wrapper over [`record_csi`] that appends every packet to
+/// `config.csv_filename` (or its Parquet counterpart) and saves the
+/// capture manifest next to it.
+pub fn record_csi_to_file(config: RecordingConfig) -> Result<RecordingStats, RecordError> {
+    let (stats, manifest) = write_capture(&config, |on_packet| record_csi(&config, on_packet))?;
+    // The capture itself succeeded; a missing manifest shouldn't fail it.
+    let _ = manifest.write(&manifest_path_for(&config.csv_filename));
+    if stats.cancelled {
+        return Err(RecordError::Cancelled);
+    }
+    if stats.trigger_timed_out {
+        return Err(RecordError::TriggerTimedOut);
+    }
+    if stats.frames_logged == 0 {
+        return Err(RecordError::NoPacketsReceived);
+    }
+    Ok(stats)
+}
+
+/// Re-parse a `.raw.log` saved with [`RecordingConfig::raw_log`] into
+/// `config.csv_filename` (and its manifest), through the same capture loop
+/// as a live recording. Parsing settings such as decimation come from
+/// `config`; its port, duration and Rerun settings are ignored.
+pub fn replay_raw_log(
+    raw_path: &str,
+    config: RecordingConfig,
+) -> Result<RecordingStats, RecordError> {
+    let mut raw = BufReader::new(File::open(raw_path).map_err(RecordError::Read)?);
+    // Read to the end of the log, however long it is.
+    let config = config.duration_secs(u64::MAX);
+    let capture = write_capture(&config, |on_packet| capture_from_reader(&mut raw, &config, None, on_packet))?;
+    let mut manifest = Manifest::new();
+    manifest.set("replayed_from", raw_path);
+    manifest.set("format", config.format.label());
+    manifest.set("decimation", config.decimation);
+    manifest.set("csi_values", config.value_format.label());
+    set_capture_stats(&mut manifest, &capture);
+    let _ = manifest.write(&manifest_path_for(&config.csv_filename));
+    Ok(capture.stats)
+}
+
+/// Open `config.output_filename()` in the configured format, run `capture`
+/// with a packet callback writing to it, then finish the file.
+fn write_capture<T>(
+    config: &RecordingConfig,
+    capture: impl FnOnce(
+        &mut dyn FnMut(&csi_packet::CsiPacket) -> io::Result<()>,
+    ) -> Result<T, RecordError>,
+) -> Result<T, RecordError> {
+    let file = File::create(config.output_filename()).map_err(RecordError::CsvWrite)?;
+    match config.format {
+        CaptureFormat::Csv => {
+            let mut csv_out = BufWriter::new(file);
+            let result = capture(&mut csv_packet_writer(&mut csv_out, config.flush_every));
+            // Keep the rows of a cancelled capture too.
+            csv_out.flush().map_err(RecordError::CsvWrite)?;
+            result
+        }
+        CaptureFormat::Parquet => {
+            let mut out = ParquetPacketWriter::new(file);
+            let result = capture(&mut |packet| out.write(packet));
+            if out.dropped > 0 {
+                tracing::warn!(dropped = out.dropped, "parquet: frames with a different I/Q count were dropped");
+            }
+            // Write the footer after a failed capture too, so its rows stay readable.
+            let finished = out.finish().map_err(RecordError::CsvWrite);
+            let result = result?;
+            finished?;
+            Ok(result)
+        }
+    }
+}
 
+/// Packet callback appending to a capture CSV, writing the header first
+/// and flushing every `flush_every` rows (never, if 0).
+fn csv_packet_writer<W: Write>(
+    csv_out: &mut W,
+    flush_every: u64,
+) -> impl FnMut(&csi_packet::CsiPacket) -> io::Result<()> + '_ {
+    let mut header_written = false;
+    let mut rows: u64 = 0;
+    move |packet| {
+        if !header_written {
+            // The antenna and host time columns are decided by the first frame:
+            // firmware either reports antennas for every frame or for none, and
+            // host time is stamped on all frames or none.
+            let header = csv_utils::generate_csv_header(
+                packet.csi_values.len(),
+                packet.antenna.is_some(),
+                packet.host_time_ms.is_some(),
+            );
+            writeln!(csv_out, "{}", header)?;
+            header_written = true;
+        }
+        csv_utils::write_csv_line(csv_out, packet)?;
+        rows += 1;
+        if flush_every > 0 && rows.is_multiple_of(flush_every) {
+            csv_out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Library entry point for embedding the capture loop in other tools.
+///
+/// Configures the board (or, with [`InputSource::Udp`], binds the socket
+/// and leaves the board alone), then hands every parsed packet (after the trigger,
+/// if armed) to `on_packet` until the duration elapses. An error from
+/// `on_packet` aborts the capture. Rerun logging, the live plot/heatmap
+/// channels and progress stats work as in [`record_csi_to_file`]. At most
+/// one plot point per `config.plot_interval` is sent (the newest), so fast captures
+/// don't flood the UI; `on_packet` and Rerun still see every packet. Pass
+/// `None`/[`RerunSink::Disabled`] to skip them. Returns the final stats and
+/// the capture metadata, for the caller to store as it sees fit.
+pub fn record_csi<F>(
+    config: &RecordingConfig,
+    on_packet: F,
+) -> Result<(RecordingStats, Manifest), RecordError>
+where
+    F: FnMut(&csi_packet::CsiPacket) -> io::Result<()>,
+{
+    let RecordingConfig {
+        rrd_filename,
+        wifi_mode,
+        duration_secs,
+        decimation,
+        rerun_sink,
+        csi_config,
+        start_at,
+        trigger_mode,
+        ..
+    } = config;
+    // Initialize Rerun recording stream (if enabled)
+    #[cfg(feature = "rerun")]
+    let rec = open_recording_stream(rerun_sink, rrd_filename)?;
+    #[cfg(not(feature = "rerun"))]
+    let rec: Option<RerunStream> = if *rerun_sink != RerunSink::Disabled {
+        let _ = rrd_filename;
+        return Err(RecordError::Rerun("built without Rerun support; use --no-rrd".into()));
+    } else {
+        None
+    };
+
+    let mut manifest = Manifest::new();
+    manifest.set("started", chrono::Local::now().to_rfc3339());
+    manifest.set("source", config.source);
+    let mut input = match config.source {
+        InputSource::Serial => OpenInput::Serial(open_board(config, &mut manifest)?),
+        InputSource::Udp(addr) => OpenInput::Udp(
+            UdpReader::bind(addr, Duration::from_millis(100)).map_err(|source| RecordError::OpenPort {
+                port: config.source.to_string(),
+                source,
+            })?,
+        ),
+    };
+    manifest.set("wifi_mode", format!("{:?}", wifi_mode));
+    manifest.set("csi_config", *csi_config);
+    manifest.set("csi_values", config.value_format.label());
+    manifest.set("format", config.format.label());
+    manifest.set("host_time_column", config.host_time);
+    if let Some(idle) = config.idle_timeout {
+        manifest.set("idle_timeout_secs", idle.as_secs_f64());
+    }
+    if config.warmup_packets > 0 {
+        manifest.set("warmup_packets", config.warmup_packets);
+    }
+    manifest.set("duration_secs", *duration_secs);
+    manifest.set("decimation", *decimation);
+    manifest.set("trigger", format!("{:?}", trigger_mode));
+    if *rerun_sink != RerunSink::Disabled {
+        manifest.set("rerun_entities", config.rerun_entities.names());
+        manifest.set("rerun_tensor_every", config.rerun_tensor_every);
+    }
+
+    // Wait for a shared wall-clock start so multiple boards begin together.
+    if let Some(start_at) = start_at {
+        match start_at.duration_since(SystemTime::now()) {
+            Ok(wait) => std::thread::sleep(wait),
+            Err(late) => {
+                let late = late.duration();
+                let warning = format!(
+                    "Board setup overran the shared start by {} ms; boards are out of step.",
+                    late.as_millis()
+                );
+                config.emit_warning(warning);
+                manifest.set("start_late_ms", late.as_millis());
+            }
+        }
+    }
+    if let Ok(since_epoch) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        manifest.set("wall_clock_start_unix_ms", since_epoch.as_millis());
+    }
+    // A UDP board streams on its own; a serial one is told to start.
+    if let OpenInput::Serial(port) = &mut input {
+        std::thread::sleep(Duration::from_millis(200));
+        if *trigger_mode == TriggerMode::Off {
+            send_cli_command(&mut **port, &format!("start --duration={}", duration_secs))
+                .map_err(RecordError::ConfigWrite)?;
+        } else {
+            // The trigger may fire at any time, so let the ESP stream until we stop reading.
+            send_cli_command(&mut **port, "start").map_err(RecordError::ConfigWrite)?;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        //port.write_all(b"start\r\n")?;
+        //port.flush()?;
+    }
+    let capture = match &config.raw_log_filename {
+        Some(path) => {
+            manifest.set("raw_log", path.as_str());
+            let raw_log = File::create(path).map_err(RecordError::CsvWrite)?;
+            let mut tee = TeeReader::new(&mut input, BufWriter::new(raw_log));
+            let capture = capture_from_reader(&mut tee, config, rec.as_ref(), on_packet);
+            tee.copy.flush().map_err(RecordError::CsvWrite)?;
+            capture
+        }
+        None => capture_from_reader(&mut input, config, rec.as_ref(), on_packet),
+    };
+    // Leave the board idle rather than streaming into the port until the
+    // next connection, even if the capture failed.
+    if let OpenInput::Serial(port) = &mut input {
+        let mut commands = vec!["stop"];
+        if config.restart_after {
+            commands.push("restart");
+        }
+        for command in commands {
+            match send_cli_command(&mut **port, command) {
+                Ok(()) => manifest.set(&format!("{}_sent", command), true),
+                Err(e) => {
+                    // The capture is on disk already; this is only hygiene.
+                    let warning = format!("Could not send '{}' to the ESP: {}", command, e);
+                    config.emit_warning(warning);
+                    manifest.set(&format!("{}_sent", command), false);
+                }
+            }
+        }
+    }
+    let capture = capture?;
+    if let OpenInput::Udp(udp) = &input {
+        manifest.set("udp_datagrams", udp.datagrams);
+    }
+    // Closing the stream finishes the `.rrd`, so its size is final.
+    #[cfg(feature = "rerun")]
+    drop(rec);
+    if *rerun_sink == RerunSink::File
+        && let Ok(metadata) = std::fs::metadata(rrd_filename)
+    {
+        manifest.set("rrd_bytes", metadata.len());
+    }
+    tracing::info!(
+        lines_written = capture.stats.lines_written,
+        frames_logged = capture.stats.frames_logged,
+        "recording complete"
+    );
+    set_capture_stats(&mut manifest, &capture);
+    Ok((capture.stats, manifest))
+}
+
+/// Packets [`probe_subcarriers`] reads before reporting.
+pub const PROBE_PACKETS: usize = 5;
+/// Longest [`probe_subcarriers`] waits for them.
+pub const PROBE_SECS: u64 = 5;
+
+/// Frame size found by [`probe_subcarriers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    /// CSI values per frame, two (I and Q) per subcarrier.
+    pub csi_values: usize,
+    pub subcarriers: usize,
+    /// Packets read; fewer than [`PROBE_PACKETS`] if the board went quiet.
+    pub packets: usize,
+}
+
+impl Probe {
+    /// The most common of the frame `lengths` (CSI values per packet);
+    /// ties go to the longer one.
+    fn from_lengths(lengths: &[usize]) -> Option<Self> {
+        let count = |len: usize| lengths.iter().filter(|&&l| l == len).count();
+        let csi_values = lengths.iter().copied().max_by_key(|&len| (count(len), len))?;
+        Some(Self {
+            csi_values,
+            subcarriers: csi_values / 2,
+            packets: lengths.len(),
+        })
+    }
+}
+
+/// Set the board up as `config` would for a recording and read up to
+/// [`PROBE_PACKETS`] packets, to learn how many subcarriers its frames
+/// carry. Nothing is written: the output files, Rerun sink, live channels,
+/// trigger and warm-up of `config` are ignored.
+pub fn probe_subcarriers(config: &RecordingConfig) -> Result<Probe, RecordError> {
+    probe_with(config, |probe, on_packet| record_csi(probe, on_packet))
+}
+
+/// Run `capture` with a probe version of `config` and a packet callback
+/// that stops it once [`PROBE_PACKETS`] have arrived.
+fn probe_with<T>(
+    config: &RecordingConfig,
+    capture: impl FnOnce(
+        &RecordingConfig,
+        &mut dyn FnMut(&csi_packet::CsiPacket) -> io::Result<()>,
+    ) -> Result<T, RecordError>,
+) -> Result<Probe, RecordError> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let probe = RecordingConfig {
+        raw_log_filename: None,
+        duration_secs: PROBE_SECS,
+        decimation: 1,
+        plot_tx: None,
+        heatmap_tx: None,
+        rerun_sink: RerunSink::Disabled,
+        stats_tx: None,
+        idle_timeout: None,
+        warmup_packets: 0,
+        restart_after: false,
+        start_at: None,
+        trigger_mode: TriggerMode::Off,
+        cancel: Some(Arc::clone(&cancel)),
+        ..config.clone()
+    };
+    let mut lengths = Vec::new();
+    capture(&probe, &mut |packet| {
+        if lengths.len() < PROBE_PACKETS {
+            lengths.push(packet.csi_values.len());
+        }
+        if lengths.len() == PROBE_PACKETS {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    })?;
+    Probe::from_lengths(&lengths).ok_or(RecordError::NoPacketsReceived)
+}
+
+/// The input [`record_csi`] reads from once it is open.
+enum OpenInput {
+    Serial(Box<dyn SerialPort>),
+    Udp(UdpReader),
+}
+
+impl Read for OpenInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            OpenInput::Serial(port) => port.read(buf),
+            OpenInput::Udp(udp) => udp.read(buf),
+        }
+    }
+}
+
+/// Open `config.port_name`, reset the board as configured, wait for its
+/// console and apply the WiFi/CSI settings, noting the port setup in
+/// `manifest`.
+fn open_board(
+    config: &RecordingConfig,
+    manifest: &mut Manifest,
+) -> Result<Box<dyn SerialPort>, RecordError> {
+    let RecordingConfig {
+        port_name,
+        wifi_mode,
+        ssid,
+        password,
+        csi_config,
+        ..
+    } = config;
     // Open serial port with explicit settings
     let mut port = serialport::new(port_name, 115_200)
         .data_bits(DataBits::Eight)
@@ -84,42 +1081,242 @@ pub fn record_csi_to_file(
         .parity(Parity::None)
         .stop_bits(StopBits::One)
         .timeout(Duration::from_millis(100))
-        .open()?;
+        .open()
+        .map_err(|e| RecordError::OpenPort {
+            port: port_name.clone(),
+            source: e.into(),
+        })?;
+    let setup_failed = |e: serialport::Error| RecordError::OpenPort {
+        port: port_name.clone(),
+        source: e.into(),
+    };
 
+    if config.release_rts {
+        port.write_request_to_send(false).map_err(setup_failed)?;
+    }
     // Set DTR to trigger ESP reset/start (important for many ESP boards)
-    port.write_data_terminal_ready(true)?;
-    std::thread::sleep(Duration::from_millis(100));
-    // Small delay to let the ESP initialize
+    if config.assert_dtr {
+        port.write_data_terminal_ready(true).map_err(setup_failed)?;
+    }
+    // Wait for the console so config commands are not dropped during boot;
+    // boards that never show a prompt get the old fixed delay.
+    let console_ready = esp_port::wait_for_prompt(&mut *port, esp_port::PROMPT_TIMEOUT);
+    if !console_ready {
+        let warning = format!(
+            "No ESP CLI prompt within {} s; sending commands anyway.",
+            esp_port::PROMPT_TIMEOUT.as_secs()
+        );
+        config.emit_warning(warning);
+        std::thread::sleep(Duration::from_millis(100));
+    }
     // Clear any pending data in the buffer
-    port.clear(serialport::ClearBuffer::All)?;
+    port.clear(serialport::ClearBuffer::All).map_err(setup_failed)?;
     //send_cli_command(&mut *port, wifi_mode.to_cli_command())?;
-    apply_wifi_config(&mut *port, wifi_mode, &ssid, &password)?;
-    std::thread::sleep(Duration::from_millis(200));
-    send_cli_command(&mut *port, &format!("start --duration={}", duration_secs))?;
-    std::thread::sleep(Duration::from_millis(100));
-    //port.write_all(b"start\r\n")?;
-    //port.flush()?;
-    let mut csv_out = File::create(csv_filename)?;
-    let mut header_written = false;
+    apply_wifi_config(&mut *port, *wifi_mode, ssid, password, csi_config).map_err(RecordError::ConfigWrite)?;
+
+    manifest.set("port", port_name.as_str());
+    manifest.set("console_prompt_seen", console_ready);
+    manifest.set("assert_dtr", config.assert_dtr);
+    manifest.set("release_rts", config.release_rts);
+    Ok(port)
+}
+
+/// Record the outcome of a capture, and its quality score, in `manifest`.
+fn set_capture_stats(manifest: &mut Manifest, capture: &Capture) {
+    if let Some(after) = capture.triggered_after {
+        manifest.set("triggered_after_secs", after.as_secs_f64());
+    }
+    let stats = capture.stats;
+    manifest.set("lines_written", stats.lines_written);
+    manifest.set("frames_logged", stats.frames_logged);
+    manifest.set("packets_received", stats.packets_received);
+    manifest.set("gaps_detected", stats.gaps_detected);
+    manifest.set("dropped_packets", stats.dropped_packets);
+    manifest.set("stuck_runs", stats.stuck_runs);
+    manifest.set("malformed_lines", stats.malformed_lines);
+    if stats.stopped_idle {
+        manifest.set("stopped_idle", true);
+    }
+    if stats.cancelled {
+        manifest.set("cancelled", true);
+    }
+    if stats.trigger_timed_out {
+        manifest.set("trigger_timed_out", true);
+    }
+    if stats.warmup_skipped > 0 {
+        manifest.set("warmup_skipped", stats.warmup_skipped);
+    }
+    if let Some(rssi) = stats.mean_rssi {
+        manifest.set("mean_rssi", format!("{:.1}", rssi));
+    }
+    if let Some(cv) = stats.interval_cv {
+        manifest.set("interval_cv", format!("{:.3}", cv));
+    }
+    let quality = QualityScore::from_stats(&stats);
+    manifest.set("quality_score", quality.score);
+    for (key, penalty) in quality.components() {
+        manifest.set(key, format!("{:.1}", penalty));
+    }
+}
+
+/// `saved_data/foo.csv` -> `saved_data/foo.raw.log`.
+pub fn raw_log_path_for(csv_filename: &str) -> String {
+    let base = csv_filename.strip_suffix(".csv").unwrap_or(csv_filename);
+    format!("{}.raw.log", base)
+}
+
+/// Reader that copies every byte it returns into `copy`.
+struct TeeReader<R, W> {
+    inner: R,
+    copy: W,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+    fn new(inner: R, copy: W) -> Self {
+        Self { inner, copy }
+    }
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Outcome of [`capture_from_reader`].
+#[derive(Debug, Clone, Copy)]
+pub struct Capture {
+    pub stats: RecordingStats,
+    /// Time from the start of reading until an armed trigger fired.
+    pub triggered_after: Option<Duration>,
+}
+
+/// Capture loop shared by the serial recorder and tests: parse ESP CLI
+/// output from `reader` and hand each packet to `on_packet`, feeding the
+/// live channels and Rerun along the way. Stops when the duration elapses,
+/// on end of stream, or on a read error.
+pub fn capture_from_reader<R, F>(
+    reader: &mut R,
+    config: &RecordingConfig,
+    rec: Option<&RerunStream>,
+    mut on_packet: F,
+) -> Result<Capture, RecordError>
+where
+    R: Read + ?Sized,
+    F: FnMut(&csi_packet::CsiPacket) -> io::Result<()>,
+{
+    let RecordingConfig {
+        duration_secs,
+        decimation,
+        subcarrier,
+        antenna,
+        quantity,
+        metric,
+        plot_interval,
+        heatmap_interval,
+        heatmap_subcarriers,
+        trigger_mode,
+        host_time,
+        idle_timeout,
+        warmup_packets,
+        ..
+    } = *config;
+    let _capture = tracing::debug_span!("capture", duration_secs, decimation).entered();
+    let plot_tx = config.plot_tx.as_ref();
+    let heatmap_tx = config.heatmap_tx.as_ref();
+    let calibration = config.calibration.as_ref();
+    let stats_tx = config.stats_tx.as_ref();
+    let log_tx = config.log_tx.as_ref();
     let start = Instant::now();
+    // Every packet past warm-up and the trigger; Rerun's frame timeline.
     let mut frame_idx: u64 = 0;
+    let mut frames_logged: u64 = 0;
     let mut line_buffer = String::new();
     let mut read_buffer = [0u8; 2048];
     let mut lines_written: u64 = 0;
-    let mut parser = CsiCliParser::new();
+    let mut parser = CsiCliParser::with_value_format(config.value_format);
+    let mut gaps = GapDetector::new();
+    let mut stuck = StuckFrameDetector::new();
+    let mut quality = QualityTracker::new();
+    let mut last_rssi: Option<i32> = None;
+    // Fixed by the first frame, which shows the real subcarrier count.
+    let mut plot_subcarrier: Option<usize> = None;
+    let mut trigger = Trigger::new(trigger_mode);
+    // Newest plot point not yet sent, and when the last one went out.
+    let mut pending_point: Option<(f64, f64)> = None;
+    let mut last_plot_send: Option<Instant> = None;
+    // ESP timestamp of the first plotted packet, the live plot's t = 0.
+    let mut first_plot_ts: Option<u64> = None;
+    let mut variance = RollingVariance::new(VARIANCE_WINDOW);
+    // Previous frame's amplitude, for the delta view.
+    let mut prev_amp: Option<f64> = None;
 
-    // Add a buffer to collect CSI data for heatmap
-    let mut csi_buffer: Vec<Vec<u8>> = vec![];
-    let heatmap_update_interval = 100; // Send heatmap every N packets
-    let mut packet_counter = 0;
+    // Heatmap rows not yet sent, and when the last batch went out.
+    let mut heatmap_rows: Vec<Vec<f32>> = vec![];
+    let mut last_heatmap_send: Option<Instant> = None;
+    // When a packet was last parsed, for the idle auto-stop.
+    let mut last_packet = start;
+    let mut stopped_idle = false;
+    let mut warmup_skipped: u64 = 0;
+    let mut cancelled = false;
+    let mut trigger_timed_out = false;
+    // The stats so far, for live progress and the final result alike. The
+    // end-of-run flags stay false until the loop exits.
+    macro_rules! current_stats {
+        () => {
+            RecordingStats {
+                lines_written,
+                frames_logged,
+                packets_received: frame_idx,
+                gaps_detected: gaps.gaps,
+                dropped_packets: gaps.dropped,
+                stuck_runs: stuck.stuck_runs,
+                malformed_lines: parser.malformed_lines(),
+                last_rssi,
+                mean_rssi: quality.mean_rssi(),
+                interval_cv: quality.interval_cv(),
+                plot_subcarrier,
+                stopped_idle,
+                cancelled,
+                trigger_timed_out,
+                warmup_skipped,
+            }
+        };
+    }
 
-    while start.elapsed() < Duration::from_secs(duration_secs) {
-        match port.read(&mut read_buffer) {
+    // With an armed trigger, the duration counts from when it fires.
+    while trigger.is_waiting()
+        || trigger.fired_at().unwrap_or(start).elapsed() < Duration::from_secs(duration_secs)
+    {
+        if config.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            tracing::info!("capture cancelled");
+            cancelled = true;
+            break;
+        }
+        if let TriggerMode::Armed { timeout: Some(timeout), .. } = trigger_mode
+            && trigger.timed_out(Instant::now())
+        {
+            let message = format!("Trigger didn't fire within {} s; stopping.", timeout.as_secs_f64());
+            config.emit_warning(message);
+            trigger_timed_out = true;
+            break;
+        }
+        if let Some(idle) = idle_timeout
+            && last_packet.elapsed() >= idle
+        {
+            let message = format!("No packets for {} s; stopping early.", idle.as_secs_f64());
+            config.emit_warning(message);
+            stopped_idle = true;
+            break;
+        }
+        let read = tracing::trace_span!("serial_read").in_scope(|| reader.read(&mut read_buffer));
+        match read {
             Ok(bytes_read) if bytes_read > 0 => {
-                //println!("read_buffer: {}\n", read_buffer);
+                tracing::trace!(bytes_read, "serial chunk");
                 // Convert bytes to string and append to line buffer
                 if let Ok(chunk) = std::str::from_utf8(&read_buffer[..bytes_read]) {
-                    //println!("{}", chunk);
                     line_buffer.push_str(chunk);
 
                     // Process complete lines
@@ -130,91 +1327,517 @@ pub fn record_csi_to_file(
                         if trimmed.is_empty() {
                             continue;
                         }
-                        if let Some(packet) = parser.feed_line(trimmed) {
-                            if !header_written {
-                                let header =
-                                    csv_utils::generate_csv_header(packet.csi_values.len());
-                                writeln!(csv_out, "{}", header)?;
-                                header_written = true;
-                            }
-                            // println!("ts:{}, rssi:{}", packet.esp_timestamp, packet.rssi);
-                            csv_utils::write_csv_line(&mut csv_out, &packet)?;
-                            lines_written += 1;
-                            if let Err(e) = log_csi_frame(&rec, frame_idx, &packet) {
-                                // eprintln!("Rerun log error: {}", e);
-                            }
-                            // Send live point for requested subcarrier (time in seconds, amplitude)
-                            if let Some(tx) = &plot_tx {
-                                let amplitudes = packet.get_amplitudes();
-                                if subcarrier < amplitudes.len() {
-                                    let t = start.elapsed().as_secs_f64();
-                                    let _ = tx.send((t, amplitudes[subcarrier] as f64));
+                        let packet = tracing::trace_span!("parse_line").in_scope(|| parser.feed_line(trimmed));
+                        for warning in parser.take_warnings() {
+                            config.emit_warning(warning);
+                        }
+                        if let Some(mut packet) = packet {
+                            last_packet = Instant::now();
+                            if warmup_skipped < warmup_packets {
+                                warmup_skipped += 1;
+                                if warmup_skipped == warmup_packets {
+                                    let message = format!("Discarded the first {} packets as warm-up.", warmup_skipped);
+                                    tracing::info!("{}", message);
+                                    if let Some(tx) = log_tx {
+                                        let _ = tx.send(message);
+                                    }
                                 }
+                                continue;
                             }
-
-                            // After parsing a packet and extracting CSI data:
-                            // Assuming you have access to the full CSI amplitude array for this packet
-                            // Convert CSI amplitudes to 0-100 range
-                            let mut row: Vec<u8> = vec![];
-                            for subcarrier_idx in 0..64 {
-                                // Assuming 64 subcarriers
-                                // Get amplitude for this subcarrier
-                                let amplitude = packet.get_amplitudes()[subcarrier_idx];
-                                // Normalize to 0-100 range
-                                let normalized = ((amplitude / 100.0) * 100.0).min(100.0) as u8;
-                                row.push(normalized);
+                            // Stamped before the trigger so pre-trigger packets keep
+                            // the time they actually arrived.
+                            if host_time {
+                                packet.host_time_ms = SystemTime::now()
+                                    .duration_since(UNIX_EPOCH)
+                                    .ok()
+                                    .map(|d| d.as_millis() as u64);
                             }
+                            for packet in trigger.feed(packet, Instant::now()) {
+                                let gap = gaps.observe(packet.esp_timestamp);
+                                let went_stuck = stuck.observe(&packet.csi_values);
+                                quality.observe(packet.esp_timestamp, packet.rssi, gap.is_some());
+                                // Gaps and stuck frames are judged on every packet;
+                                // frame_idx counts raw packets so Rerun time stays true.
+                                if !frame_idx.is_multiple_of(decimation as u64) {
+                                    frame_idx += 1;
+                                    continue;
+                                }
+                                last_rssi = Some(packet.rssi);
+                                let subcarrier = *plot_subcarrier.get_or_insert_with(|| {
+                                    let available = packet.csi_values.len() / 2;
+                                    if available == 0 || subcarrier < available {
+                                        return subcarrier;
+                                    }
+                                    let warning = format!(
+                                        "Subcarrier {} is out of range for {}-subcarrier frames; plotting {} instead.",
+                                        subcarrier,
+                                        available,
+                                        available - 1
+                                    );
+                                    config.emit_warning(warning);
+                                    available - 1
+                                });
+                                tracing::trace!(ts = packet.esp_timestamp, rssi = packet.rssi, "packet");
+                                on_packet(&packet).map_err(RecordError::CsvWrite)?;
+                                lines_written += 1;
+                                frames_logged += 1;
+                                #[cfg(feature = "rerun")]
+                                if let Some(rec) = rec {
+                                    // Counted in written frames, so the first one always has tensors.
+                                    let with_tensors = (lines_written - 1).is_multiple_of(config.rerun_tensor_every);
+                                    let entities = &config.rerun_entities;
+                                    if let Err(e) = log_csi_frame(rec, frame_idx, &packet, entities, with_tensors) {
+                                        tracing::warn!("Rerun log error: {}", e);
+                                    }
+                                }
+                                // The live views follow one antenna; frames without an
+                                // antenna index always count.
+                                let shown = antenna.is_none()
+                                    || packet.antenna.is_none()
+                                    || packet.antenna == antenna;
+                                // Send live point for requested subcarrier (time in seconds, selected quantity)
+                                if let (true, Some(tx)) = (shown, &plot_tx) {
+                                    if subcarrier < packet.csi_values.len() / 2 {
+                                        let ts0 = *first_plot_ts.get_or_insert(packet.esp_timestamp);
+                                        let t = packet.esp_timestamp.saturating_sub(ts0) as f64 / 1e6;
+                                        let mut value = packet_sample(&packet, subcarrier, quantity, metric, calibration);
+                                        match quantity {
+                                            Quantity::Delta => {
+                                                let amp = value;
+                                                value = prev_amp.map_or(0.0, |p| (amp - p).abs());
+                                                prev_amp = Some(amp);
+                                            }
+                                            Quantity::Variance => value = variance.push(value),
+                                            _ => {}
+                                        }
+                                        pending_point = Some((t, value));
+                                    }
+                                    let due = last_plot_send.is_none_or(|at| at.elapsed() >= plot_interval);
+                                    if let Some(point) = pending_point.take_if(|_| due) {
+                                        let _ = tx.send(point);
+                                        last_plot_send = Some(Instant::now());
+                                    }
+                                }
 
-                            // Add row to buffer
-                            csi_buffer.push(row);
+                                // Send only the new rows, at most once per interval.
+                                if let (true, Some(tx)) = (shown, heatmap_tx) {
+                                    // The whole frame, so SNR sees all of it for its noise floor.
+                                    let values = packet_metric_values(&packet, metric, calibration);
+                                    let range = match heatmap_subcarriers {
+                                        Some((lo, hi)) => clamp_subcarrier_range(lo, hi, values.len()),
+                                        None => 0..values.len(),
+                                    };
+                                    // Every column, like a reloaded capture; the heatmap clips to its area.
+                                    heatmap_rows.push(values[range].iter().map(|&v| v as f32).collect());
+                                    if last_heatmap_send.is_none_or(|at| at.elapsed() >= heatmap_interval) {
+                                        let _ = tx.send(std::mem::take(&mut heatmap_rows));
+                                        last_heatmap_send = Some(Instant::now());
+                                    }
+                                }
 
-                            // Keep buffer size limited (e.g., last 50 packets)
-                            if csi_buffer.len() > 50 {
-                                csi_buffer.remove(0);
-                            }
+                                frame_idx += 1;
 
-                            // Send heatmap data periodically
-                            packet_counter += 1;
-                            if packet_counter % heatmap_update_interval == 0 {
-                                if let Some(ref tx) = heatmap_tx {
-                                    let _ = tx.send(csi_buffer.clone());
+                                // Report progress on every gap or stuck run and every 10 logged frames.
+                                if (gap.is_some() || went_stuck || frames_logged.is_multiple_of(10))
+                                    && let Some(tx) = &stats_tx
+                                {
+                                    let _ = tx.send(current_stats!());
                                 }
                             }
-
-                            frame_idx += 1;
                         }
                     }
                 }
             }
             Ok(_) => {
-                // println!("No data read");
-                // No data read, continue
+                // End of stream (serial ports time out instead of returning 0).
+                break;
             }
             Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {
                 // Timeout is expected, just continue
-                // println!("TimeOut");
+                tracing::trace!("serial read timed out");
                 continue;
             }
             Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
                 // Would block, sleep a bit and continue
-                // println!("Wouldblock");
+                tracing::trace!("serial read would block");
                 thread::sleep(Duration::from_millis(10));
                 continue;
             }
             Err(e) => {
-                // e!("Serial read error: {}", e);
+                tracing::error!("Serial read error: {}", e);
                 break;
             }
         }
     }
-    csv_out.flush()?;
-    let _ = rec.flush_blocking();
-    // eprintln!(
-    //     "Recording complete. Lines written: {}, Frames logged: {}",
-    //     lines_written, frame_idx
-    // );
-    // port.write_all(&[0x12])?;
-    // port.flush()?;
-    // std::thread::sleep(Duration::from_millis(100));
-    Ok(())
+    // Don't leave the last throttled point off the plot.
+    if let (Some(tx), Some(point)) = (&plot_tx, pending_point) {
+        let _ = tx.send(point);
+    }
+    // Nor the rows gathered since the last heatmap send.
+    if let (Some(tx), false) = (heatmap_tx, heatmap_rows.is_empty()) {
+        let _ = tx.send(heatmap_rows);
+    }
+    #[cfg(feature = "rerun")]
+    if let Some(rec) = rec {
+        let _ = rec.flush_blocking();
+    }
+    #[cfg(not(feature = "rerun"))]
+    let _ = rec;
+    Ok(Capture {
+        stats: current_stats!(),
+        triggered_after: trigger.fired_at().map(|at| at.duration_since(start)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_data::{load_csv_power_matrix, normalize_heatmap};
+
+    #[test]
+    fn output_estimate_counts_the_rrd_only_when_saved_to_file() {
+        // 532 CSV bytes per frame, plus 1408 for the .rrd.
+        assert_eq!(estimate_output_bytes(10, 100, &RerunSink::Disabled), 532_000);
+        assert_eq!(estimate_output_bytes(10, 100, &RerunSink::Spawn), 532_000);
+        assert_eq!(estimate_output_bytes(10, 100, &RerunSink::File), 1_940_000);
+        assert_eq!(estimate_output_bytes(0, 100, &RerunSink::File), 0);
+        assert_eq!(estimate_output_bytes(u64::MAX, 100, &RerunSink::File), u64::MAX);
+    }
+
+    /// ESP CLI console output: boot noise, echoed commands, five frames
+    /// (one without an `rssi:` line) and one truncated frame. Subcarrier `s`
+    /// of frame `p` has I = s % 4, Q = p.
+    const FIXTURE: &str = include_str!("../tests/fixtures/esp_cli_capture.log");
+
+    #[test]
+    fn rerun_entities_parse_and_list() {
+        let only_amp = RerunEntities::parse("amplitude_tensor, rssi").unwrap();
+        assert!(only_amp.amplitude_tensor && only_amp.rssi);
+        assert!(!only_amp.raw_iq && !only_amp.phase_tensor);
+        assert_eq!(only_amp.names(), "rssi,amplitude_tensor");
+        assert_eq!(RerunEntities::parse(&RerunEntities::ALL.names()), Ok(RerunEntities::ALL));
+        assert!(RerunEntities::parse("amplitude").is_err());
+    }
+
+    #[test]
+    fn raw_log_tee_keeps_exact_stream() {
+        let config = RecordingConfig::new("fixture", "saved_data/run.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .raw_log(true);
+        assert_eq!(config.raw_log_filename.as_deref(), Some("saved_data/run.raw.log"));
+
+        let mut copy = Vec::new();
+        let mut tee = TeeReader::new(FIXTURE.as_bytes(), &mut copy);
+        let capture = capture_from_reader(&mut tee, &config, None, |_| Ok(())).unwrap();
+        assert_eq!(capture.stats.lines_written, 5);
+        assert_eq!(copy, FIXTURE.as_bytes());
+    }
+
+    #[test]
+    fn replay_matches_live_capture() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let live_csv = dir.join("live.csv");
+        let replay_csv = dir.join("replay.csv");
+        let raw_log = dir.join("live.raw.log");
+        let (live_csv, replay_csv, raw_log) =
+            (live_csv.to_str().unwrap(), replay_csv.to_str().unwrap(), raw_log.to_str().unwrap());
+
+        let mut csv_out = Vec::new();
+        let config = RecordingConfig::new("fixture", live_csv).rerun_sink(RerunSink::Disabled);
+        let live = capture_from_reader(
+            &mut FIXTURE.as_bytes(),
+            &config,
+            None,
+            csv_packet_writer(&mut csv_out, DEFAULT_FLUSH_EVERY),
+        )
+        .unwrap();
+        std::fs::write(raw_log, FIXTURE).unwrap();
+
+        let stats = replay_raw_log(raw_log, RecordingConfig::new("replay", replay_csv)).unwrap();
+        assert_eq!(stats.lines_written, live.stats.lines_written);
+        assert_eq!(std::fs::read(replay_csv).unwrap(), csv_out);
+        let manifest = Manifest::read(&manifest_path_for(replay_csv)).unwrap();
+        assert_eq!(manifest.get("replayed_from"), Some(raw_log));
+        assert_eq!(manifest.get("lines_written"), Some("5"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn idle_timeout_stops_a_quiet_capture() {
+        /// A port that never sends anything, like a sniffer on an empty channel.
+        struct QuietPort;
+        impl Read for QuietPort {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                thread::sleep(Duration::from_millis(5));
+                Err(io::ErrorKind::TimedOut.into())
+            }
+        }
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .duration_secs(60)
+            .idle_timeout(Some(Duration::from_millis(100)));
+        let started = Instant::now();
+        let capture =
+            capture_from_reader(&mut FIXTURE.as_bytes().chain(QuietPort), &config, None, |_| Ok(())).unwrap();
+        assert!(capture.stats.stopped_idle);
+        assert_eq!(capture.stats.lines_written, 5);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn armed_trigger_gives_up_after_its_timeout() {
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .trigger_mode(TriggerMode::Armed {
+                subcarrier: 0,
+                threshold: 1e6,
+                pre_trigger: Duration::from_secs(1),
+                timeout: Some(Duration::from_millis(100)),
+            });
+        let started = Instant::now();
+        let capture =
+            capture_from_reader(&mut FIXTURE.as_bytes().chain(io::repeat(b'\n')), &config, None, |_| Ok(()))
+                .unwrap();
+        assert!(capture.stats.trigger_timed_out);
+        assert_eq!(capture.stats.lines_written, 0);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn cancel_stops_the_capture() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .cancel(cancel);
+        let capture = capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| Ok(())).unwrap();
+        assert!(capture.stats.cancelled);
+        assert_eq!(capture.stats.lines_written, 0);
+        // A failing packet sink surfaces as a write error.
+        let config = RecordingConfig::new("fixture", "unused.csv").rerun_sink(RerunSink::Disabled);
+        let result = capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| {
+            Err(io::ErrorKind::StorageFull.into())
+        });
+        assert!(matches!(result, Err(RecordError::CsvWrite(_))));
+    }
+
+    #[test]
+    fn decimation_counts_logged_frames_and_received_packets_apart() {
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .decimation(2);
+        let mut kept = Vec::new();
+        let capture = capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |packet| {
+            kept.push(packet.csi_values[1]);
+            Ok(())
+        })
+        .unwrap();
+        // Frames 0, 2 and 4 of 5; frame `p` has Q = p.
+        assert_eq!(kept, vec![0, 2, 4]);
+        assert_eq!(capture.stats.frames_logged, 3);
+        assert_eq!(capture.stats.lines_written, 3);
+        assert_eq!(capture.stats.packets_received, 5);
+    }
+
+    #[test]
+    fn cancelled_parquet_capture_stays_readable() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-cancel-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("capture.csv");
+        let cancel = Arc::new(AtomicBool::new(false));
+        let config = RecordingConfig::new("fixture", csv.to_str().unwrap())
+            .rerun_sink(RerunSink::Disabled)
+            .format(CaptureFormat::Parquet)
+            .cancel(Arc::clone(&cancel));
+        let capture = write_capture(&config, |on_packet| {
+            capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |packet| {
+                on_packet(packet)?;
+                cancel.store(true, Ordering::Relaxed);
+                Ok(())
+            })
+        })
+        .unwrap();
+        assert!(capture.stats.cancelled);
+        // The rest of the read chunk is parsed before the flag is seen.
+        let written = crate::parquet_capture::load_packets(&config.output_filename()).unwrap();
+        assert_eq!(written.len() as u64, capture.stats.lines_written);
+        assert!(!written.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn probe_reports_the_usual_frame_length() {
+        // Long enough that only the packet count ends the probe.
+        let config = RecordingConfig::new("fixture", "unused.csv").duration_secs(60);
+        let probe = probe_with(&config, |probe, on_packet| {
+            assert_eq!(probe.rerun_sink, RerunSink::Disabled);
+            capture_from_reader(&mut FIXTURE.as_bytes().chain(io::repeat(b'\n')), probe, None, on_packet)
+        })
+        .unwrap();
+        // One of the five frames is a short 32-subcarrier one.
+        assert_eq!(
+            probe,
+            Probe {
+                csi_values: 128,
+                subcarriers: 64,
+                packets: PROBE_PACKETS
+            }
+        );
+        let quiet = probe_with(&config, |probe, on_packet| {
+            capture_from_reader(&mut "boot\n".as_bytes(), probe, None, on_packet)
+        });
+        assert!(matches!(quiet, Err(RecordError::NoPacketsReceived)));
+    }
+
+    #[test]
+    fn warmup_packets_are_parsed_but_not_written() {
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .warmup_packets(2);
+        let mut written = Vec::new();
+        let capture = capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |packet| {
+            written.push(packet.csi_values[1]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(capture.stats.warmup_skipped, 2);
+        assert_eq!(capture.stats.lines_written, 3);
+        // Q of subcarrier 0 is the frame number: frames 0 and 1 were dropped.
+        assert_eq!(written, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn out_of_range_subcarrier_is_clamped_on_first_frame() {
+        let (plot_tx, plot_rx) = mpsc::channel();
+        let (log_tx, log_rx) = mpsc::channel();
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .subcarrier(500)
+            .plot_tx(plot_tx)
+            .log_tx(log_tx);
+        let capture = capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| Ok(())).unwrap();
+        drop(config);
+        assert_eq!(capture.stats.plot_subcarrier, Some(63));
+        assert!(plot_rx.iter().count() > 0);
+        // Once, on the first frame (the fixture's malformed line warns too).
+        let clamped = log_rx.iter().filter(|w| w.contains("Subcarrier 500")).count();
+        assert_eq!(clamped, 1);
+    }
+
+    #[test]
+    fn fixture_log_to_csv_and_heatmap() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("capture.csv");
+        let csv_path = csv_path.to_str().unwrap();
+
+        let mut csv_out = File::create(csv_path).unwrap();
+        let config = RecordingConfig::new("fixture", csv_path).rerun_sink(RerunSink::Disabled);
+        let capture = capture_from_reader(
+            &mut FIXTURE.as_bytes(),
+            &config,
+            None,
+            csv_packet_writer(&mut csv_out, DEFAULT_FLUSH_EVERY),
+        )
+        .unwrap();
+        csv_out.flush().unwrap();
+        assert_eq!(capture.stats.lines_written, 5);
+        assert_eq!(capture.stats.last_rssi, Some(-44));
+
+        let csv = std::fs::read_to_string(csv_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], csv_utils::generate_csv_header(128, false, false));
+        assert!(lines[1].starts_with("1000000,-40,0,0,1,0,"));
+        // Frame 2 had no rssi line.
+        assert!(lines[3].starts_with("1020000,0,"));
+
+        // Power I² + Q² spans 0 (frame 0, s % 4 == 0) to 25 (frame 4, s % 4 == 3).
+        let heatmap = normalize_heatmap(&load_csv_power_matrix(csv_path, None).unwrap());
+        assert_eq!(heatmap.len(), 5);
+        assert_eq!(heatmap[0].len(), 64);
+        assert_eq!(heatmap[0][0], 0);
+        assert_eq!(heatmap[2][1], 20);
+        assert_eq!(heatmap[4][3], 100);
+        assert_eq!(heatmap[4][7], 100);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn live_plot_uses_esp_time() {
+        let (plot_tx, plot_rx) = mpsc::channel();
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .subcarrier(1)
+            .plot_interval(Duration::ZERO)
+            .plot_tx(plot_tx);
+        capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| Ok(())).unwrap();
+        drop(config);
+
+        // Frames are 10 ms apart on the ESP clock, however fast they were read.
+        let times: Vec<f64> = plot_rx.iter().map(|(t, _)| t).collect();
+        assert_eq!(times.len(), 5);
+        for (n, t) in times.iter().enumerate() {
+            assert!((t - n as f64 * 0.01).abs() < 1e-9, "{:?}", times);
+        }
+    }
+
+    #[test]
+    fn live_heatmap_sends_each_row_once() {
+        let (heatmap_tx, heatmap_rx) = mpsc::channel();
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .heatmap_tx(heatmap_tx);
+        capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| Ok(())).unwrap();
+        drop(config);
+
+        let batches: Vec<Vec<Vec<f32>>> = heatmap_rx.iter().collect();
+        assert!(batches.iter().all(|batch| !batch.is_empty()));
+        let rows: Vec<Vec<f32>> = batches.into_iter().flatten().collect();
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|row| row.len() == 64));
+        // Magnitude by default: subcarrier 1 of frame 0 is I = 1, Q = 0.
+        assert_eq!(rows[0][1], 1.0);
+
+        // Rows follow the metric, like a loaded capture's heatmap.
+        let (heatmap_tx, heatmap_rx) = mpsc::channel();
+        let config = RecordingConfig::new("fixture", "unused.csv")
+            .rerun_sink(RerunSink::Disabled)
+            .metric(AmplitudeMetric::Db)
+            .heatmap_tx(heatmap_tx);
+        capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |_| Ok(())).unwrap();
+        drop(config);
+        let rows: Vec<Vec<f32>> = heatmap_rx.iter().flatten().collect();
+        assert!((rows[0][2] - AmplitudeMetric::Db.from_power(4.0) as f32).abs() < 1e-6);
+    }
+
+    /// Per-frame Rerun logging cost for all entities against the amplitude
+    /// tensor alone, into an in-memory sink. A timing run, not a check:
+    /// `cargo test --release log_csi_frame_throughput -- --ignored --nocapture`.
+    #[cfg(feature = "rerun")]
+    #[test]
+    #[ignore]
+    fn log_csi_frame_throughput() {
+        let config = RecordingConfig::new("fixture", "unused.csv").rerun_sink(RerunSink::Disabled);
+        let mut packets = Vec::new();
+        capture_from_reader(&mut FIXTURE.as_bytes(), &config, None, |packet| {
+            packets.push(packet.clone());
+            Ok(())
+        })
+        .unwrap();
+        const FRAMES: u64 = 20_000;
+        let only_amp = RerunEntities::parse("amplitude_tensor").unwrap();
+        for (name, entities) in [("all", RerunEntities::ALL), ("amplitude_tensor", only_amp)] {
+            let (rec, _storage) = rerun::RecordingStreamBuilder::new("throughput").memory().unwrap();
+            let start = Instant::now();
+            for frame in 0..FRAMES {
+                log_csi_frame(&rec, frame, &packets[frame as usize % packets.len()], &entities, true).unwrap();
+            }
+            rec.flush_blocking().unwrap();
+            let secs = start.elapsed().as_secs_f64();
+            println!("{name}: {FRAMES} frames in {secs:.3} s, {:.0} frames/s", FRAMES as f64 / secs);
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use std::{fs, io, path::Path};
+
+use crate::manifest::Manifest;
+use crate::read_data::{AmplitudeMetric, Quantity};
+use crate::wifi_mode::WifiMode;
+
+/// Subfolder of the data directory presets are stored in, one
+/// `<name>.preset` file of `key=value` lines each.
+pub const PRESETS_DIR: &str = "presets";
+const PRESET_EXT: &str = ".preset";
+
+/// A named set of experiment controls. The WiFi password is left out so it
+/// never ends up in a plain-text file.
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub wifi_mode: WifiMode,
+    pub ssid: String,
+    pub duration_secs: u64,
+    pub subcarrier: usize,
+    /// Plot quantity and amplitude metric.
+    pub view: Quantity,
+    pub metric: AmplitudeMetric,
+}
+
+fn preset_path(data_dir: &str, name: &str) -> String {
+    format!("{}/{}/{}{}", data_dir, PRESETS_DIR, name, PRESET_EXT)
+}
+
+/// Names of the presets in `<data_dir>/presets`, sorted.
+pub fn list_presets(data_dir: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(Path::new(data_dir).join(PRESETS_DIR)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_suffix(PRESET_EXT).map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Write `preset` as `<data_dir>/presets/<name>.preset`, replacing any
+/// preset of the same name.
+pub fn save_preset(data_dir: &str, name: &str, preset: &Preset) -> io::Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid preset name"));
+    }
+    fs::create_dir_all(Path::new(data_dir).join(PRESETS_DIR))?;
+    let mut manifest = Manifest::new();
+    manifest.set("wifi_mode", format!("{:?}", preset.wifi_mode));
+    manifest.set("ssid", &preset.ssid);
+    manifest.set("duration_secs", preset.duration_secs);
+    manifest.set("subcarrier", preset.subcarrier);
+    manifest.set("view", preset.view.label());
+    manifest.set("metric", preset.metric.label());
+    manifest.write(&preset_path(data_dir, name))
+}
+
+/// Read `<data_dir>/presets/<name>.preset`. Missing or unrecognised keys
+/// keep `defaults`' values.
+pub fn load_preset(data_dir: &str, name: &str, defaults: &Preset) -> io::Result<Preset> {
+    let manifest = Manifest::read(&preset_path(data_dir, name))?;
+    let mut preset = defaults.clone();
+    match manifest.get("wifi_mode") {
+        Some("Sniffer") => preset.wifi_mode = WifiMode::Sniffer,
+        Some("Station") => preset.wifi_mode = WifiMode::Station,
+        _ => {}
+    }
+    if let Some(ssid) = manifest.get("ssid") {
+        preset.ssid = ssid.to_string();
+    }
+    if let Some(secs) = manifest.get("duration_secs").and_then(|v| v.parse().ok()) {
+        preset.duration_secs = secs;
+    }
+    if let Some(sc) = manifest.get("subcarrier").and_then(|v| v.parse().ok()) {
+        preset.subcarrier = sc;
+    }
+    if let Some(label) = manifest.get("view")
+        && let Some(view) = cycle_find(Quantity::default(), Quantity::next, |q| q.label() == label)
+    {
+        preset.view = view;
+    }
+    if let Some(label) = manifest.get("metric")
+        && let Some(metric) = cycle_find(AmplitudeMetric::default(), AmplitudeMetric::next, |m| m.label() == label)
+    {
+        preset.metric = metric;
+    }
+    Ok(preset)
+}
+
+/// The first value in the cycle starting at `start` matching `pred`.
+fn cycle_find<T: Copy + PartialEq>(start: T, next: fn(T) -> T, pred: impl Fn(T) -> bool) -> Option<T> {
+    let mut value = start;
+    loop {
+        if pred(value) {
+            return Some(value);
+        }
+        value = next(value);
+        if value == start {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_round_trips_and_lists() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-presets-{}", std::process::id()));
+        let data_dir = dir.to_str().unwrap();
+        assert!(list_presets(data_dir).is_empty());
+
+        let defaults = Preset {
+            wifi_mode: WifiMode::Sniffer,
+            ssid: String::new(),
+            duration_secs: 10,
+            subcarrier: 20,
+            view: Quantity::Amplitude,
+            metric: AmplitudeMetric::Magnitude,
+        };
+        let hallway = Preset {
+            wifi_mode: WifiMode::Station,
+            ssid: "lab net".to_string(),
+            duration_secs: 120,
+            subcarrier: 7,
+            view: Quantity::Variance,
+            metric: AmplitudeMetric::Db,
+        };
+        save_preset(data_dir, "hallway", &hallway).unwrap();
+        save_preset(data_dir, "bench", &defaults).unwrap();
+        assert!(save_preset(data_dir, "../escape", &defaults).is_err());
+
+        assert_eq!(list_presets(data_dir), vec!["bench", "hallway"]);
+        let loaded = load_preset(data_dir, "hallway", &defaults).unwrap();
+        assert_eq!(loaded.ssid, hallway.ssid);
+        assert_eq!(loaded.duration_secs, 120);
+        assert_eq!(loaded.subcarrier, 7);
+        assert_eq!(loaded.view, Quantity::Variance);
+        assert_eq!(loaded.metric, AmplitudeMetric::Db);
+        assert!(matches!(loaded.wifi_mode, WifiMode::Station));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+
+use crate::parse_data::RecordingStats;
+
+/// Maximum points each signal can take off the 100-point score.
+const PARSE_WEIGHT: f64 = 25.0;
+const DROP_WEIGHT: f64 = 30.0;
+const RATE_WEIGHT: f64 = 15.0;
+const STUCK_WEIGHT: f64 = 20.0;
+const RSSI_WEIGHT: f64 = 10.0;
+/// Malformed-line fraction that costs the full parse weight.
+const PARSE_FAIL_LIMIT: f64 = 0.05;
+/// Dropped-packet fraction that costs the full drop weight.
+const DROP_LIMIT: f64 = 0.10;
+/// Inter-packet interval coefficient of variation that costs the full rate weight.
+const RATE_CV_LIMIT: f64 = 1.0;
+/// Points off per stuck run, up to the stuck weight.
+const STUCK_RUN_PENALTY: f64 = 10.0;
+/// Mean RSSI (dBm) at or above which no points are taken off, and at or
+/// below which the full RSSI weight is.
+const RSSI_GOOD_DBM: f64 = -60.0;
+const RSSI_POOR_DBM: f64 = -80.0;
+
+/// Running packet-rate and RSSI statistics for the quality score.
+#[derive(Debug, Default)]
+pub struct QualityTracker {
+    last_ts: Option<u64>,
+    intervals: u64,
+    interval_mean: f64,
+    /// Sum of squared deviations from `interval_mean` (Welford).
+    interval_m2: f64,
+    rssi_sum: i64,
+    rssi_count: u64,
+}
+
+impl QualityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next packet. Intervals across a detected `gap` are left to
+    /// the drop estimate rather than counted as rate jitter.
+    pub fn observe(&mut self, ts: u64, rssi: i32, gap: bool) {
+        self.rssi_sum += rssi as i64;
+        self.rssi_count += 1;
+        if let Some(prev) = self.last_ts.replace(ts)
+            && ts > prev
+            && !gap
+        {
+            let delta = (ts - prev) as f64;
+            self.intervals += 1;
+            let diff = delta - self.interval_mean;
+            self.interval_mean += diff / self.intervals as f64;
+            self.interval_m2 += diff * (delta - self.interval_mean);
+        }
+    }
+
+    pub fn mean_rssi(&self) -> Option<f64> {
+        (self.rssi_count > 0).then(|| self.rssi_sum as f64 / self.rssi_count as f64)
+    }
+
+    /// Standard deviation over mean of the inter-packet intervals.
+    pub fn interval_cv(&self) -> Option<f64> {
+        if self.intervals < 2 || self.interval_mean <= 0.0 {
+            return None;
+        }
+        let std = (self.interval_m2 / self.intervals as f64).sqrt();
+        Some(std / self.interval_mean)
+    }
+}
+
+/// 0–100 capture quality with the points each signal took off.
+///
+/// Starting from 100, each signal subtracts up to its weight, scaling
+/// linearly up to its limit:
+///
+/// | Signal | Measure | Full penalty at | Weight |
+/// |---|---|---|---|
+/// | parse | malformed / (malformed + written) lines | 5% | 25 |
+/// | dropped | dropped / (dropped + received) packets | 10% | 30 |
+/// | rate | interval std / mean, gaps excluded | 1.0 | 15 |
+/// | stuck | 10 per stuck run | 2 runs | 20 |
+/// | rssi | mean RSSI between -60 and -80 dBm | -80 dBm | 10 |
+///
+/// Signals that could not be measured cost nothing; a capture with no
+/// packets scores 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityScore {
+    pub score: u8,
+    pub parse_penalty: f64,
+    pub drop_penalty: f64,
+    pub rate_penalty: f64,
+    pub stuck_penalty: f64,
+    pub rssi_penalty: f64,
+}
+
+impl QualityScore {
+    pub fn from_stats(stats: &RecordingStats) -> Self {
+        let fraction = |part: u64, rest: u64| {
+            let total = part + rest;
+            if total == 0 { 0.0 } else { part as f64 / total as f64 }
+        };
+        let scaled = |value: f64, limit: f64, weight: f64| (value / limit).clamp(0.0, 1.0) * weight;
+
+        let parse_penalty = scaled(
+            fraction(stats.malformed_lines, stats.lines_written),
+            PARSE_FAIL_LIMIT,
+            PARSE_WEIGHT,
+        );
+        let drop_penalty = scaled(
+            fraction(stats.dropped_packets, stats.packets_received),
+            DROP_LIMIT,
+            DROP_WEIGHT,
+        );
+        let rate_penalty = stats.interval_cv.map_or(0.0, |cv| scaled(cv, RATE_CV_LIMIT, RATE_WEIGHT));
+        let stuck_penalty = (stats.stuck_runs as f64 * STUCK_RUN_PENALTY).min(STUCK_WEIGHT);
+        let rssi_penalty = stats.mean_rssi.map_or(0.0, |rssi| {
+            scaled(RSSI_GOOD_DBM - rssi, RSSI_GOOD_DBM - RSSI_POOR_DBM, RSSI_WEIGHT)
+        });
+
+        let score = if stats.frames_logged == 0 {
+            0
+        } else {
+            let total = parse_penalty + drop_penalty + rate_penalty + stuck_penalty + rssi_penalty;
+            (100.0 - total).clamp(0.0, 100.0).round() as u8
+        };
+        Self {
+            score,
+            parse_penalty,
+            drop_penalty,
+            rate_penalty,
+            stuck_penalty,
+            rssi_penalty,
+        }
+    }
+
+    /// One-word summary: good (80+), fair (50+) or poor.
+    pub fn verdict(&self) -> &'static str {
+        match self.score {
+            80.. => "good",
+            50.. => "fair",
+            _ => "poor",
+        }
+    }
+
+    /// `(manifest key, points taken off)` for each signal.
+    pub fn components(&self) -> [(&'static str, f64); 5] {
+        [
+            ("quality_parse_penalty", self.parse_penalty),
+            ("quality_drop_penalty", self.drop_penalty),
+            ("quality_rate_penalty", self.rate_penalty),
+            ("quality_stuck_penalty", self.stuck_penalty),
+            ("quality_rssi_penalty", self.rssi_penalty),
+        ]
+    }
+}
+
+impl fmt::Display for QualityScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/100 ({})", self.score, self.verdict())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_capture_scores_high_and_bad_one_low() {
+        let mut tracker = QualityTracker::new();
+        for i in 0..100u64 {
+            tracker.observe(i * 10_000, -50, false);
+        }
+        let clean = RecordingStats {
+            lines_written: 100,
+            frames_logged: 100,
+            packets_received: 100,
+            mean_rssi: tracker.mean_rssi(),
+            interval_cv: tracker.interval_cv(),
+            ..Default::default()
+        };
+        assert_eq!(tracker.interval_cv(), Some(0.0));
+        assert_eq!(QualityScore::from_stats(&clean).score, 100);
+
+        let bad = RecordingStats {
+            malformed_lines: 10,
+            dropped_packets: 50,
+            stuck_runs: 3,
+            mean_rssi: Some(-90.0),
+            ..clean
+        };
+        let score = QualityScore::from_stats(&bad);
+        assert_eq!(score.stuck_penalty, STUCK_WEIGHT);
+        assert_eq!(score.rssi_penalty, RSSI_WEIGHT);
+        assert_eq!(score.score, 15);
+        assert_eq!(score.verdict(), "poor");
+        assert_eq!(QualityScore::from_stats(&RecordingStats::default()).score, 0);
+    }
+}
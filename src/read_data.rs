@@ -1,20 +1,301 @@
 use core::f32;
-use std::{error::Error, fs};
+use std::error::Error;
 use color_eyre::Result;
 use csv;
 //use rerun::external::arrow::csv;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Range;
+use flate2::read::GzDecoder;
 
-pub fn load_csv_amplitude_series(
+use crate::calibration::Calibration;
+use crate::csi_packet::CsiPacket;
+use crate::csv_utils::{ANTENNA_COLUMN, HOST_TIME_COLUMN};
+use crate::dsp::{self, RollingVariance, abs_delta_series};
+use crate::parquet_capture;
+
+/// Byte-order mark some Windows tools put at the start of UTF-8 files.
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Open a capture file, decompressing it on the fly if it ends in `.gz`.
+fn open_capture(path: &str) -> io::Result<Box<dyn Read>> {
+    let file = File::open(path)?;
+    if path.ends_with(".gz") {
+        Ok(Box::new(GzDecoder::new(file)))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Open a capture CSV for the `csv` crate, skipping a leading BOM so it
+/// doesn't stick to the first header. The reader's default terminator
+/// already accepts both `\n` and `\r\n`.
+fn open_csv(path: &str) -> Result<csv::Reader<BufReader<Box<dyn Read>>>> {
+    let mut reader = BufReader::new(open_capture(path)?);
+    if reader.fill_buf()?.starts_with(UTF8_BOM.as_bytes()) {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(csv::Reader::from_reader(reader))
+}
+
+/// A record from the `csv` reader, or `None` for a row with the wrong
+/// number of fields, such as a final line cut short by a crash.
+fn complete_record(result: csv::Result<csv::StringRecord>) -> Result<Option<csv::StringRecord>> {
+    match result {
+        Ok(record) => Ok(Some(record)),
+        Err(e) if matches!(e.kind(), csv::ErrorKind::UnequalLengths { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Column positions of a capture CSV, laid out as
+/// `[host_time_ms,]esp_timestamp_us,rssi[,antenna],i0,q0,i1,q1,...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvLayout {
+    pub host_time: Option<usize>,
+    pub timestamp: usize,
+    pub rssi: usize,
+    pub antenna: Option<usize>,
+    /// First I/Q column.
+    pub iq_start: usize,
+}
+
+impl CsvLayout {
+    pub fn from_header<'a>(header: impl Iterator<Item = &'a str>) -> Self {
+        let names: Vec<&str> = header.take(4).map(str::trim).collect();
+        let host_time = (names.first() == Some(&HOST_TIME_COLUMN)).then_some(0);
+        let timestamp = host_time.map_or(0, |_| 1);
+        let antenna = (names.get(timestamp + 2) == Some(&ANTENNA_COLUMN)).then_some(timestamp + 2);
+        Self {
+            host_time,
+            timestamp,
+            rssi: timestamp + 1,
+            antenna,
+            iq_start: antenna.map_or(timestamp + 2, |a| a + 1),
+        }
+    }
+}
+
+/// Whether a row belongs to the requested antenna. Rows always match when
+/// there's no filter or the capture has no antenna column.
+fn antenna_matches(filter: Option<u8>, has_antenna: bool, value: Option<&str>) -> bool {
+    match filter {
+        Some(wanted) if has_antenna => value.and_then(|v| v.trim().parse::<u8>().ok()) == Some(wanted),
+        _ => true,
+    }
+}
+
+/// How a subcarrier's amplitude is derived from its I/Q sample. The plot,
+/// heatmap and frame view all use the same metric.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmplitudeMetric {
+    /// `sqrt(I² + Q²)`.
+    #[default]
+    Magnitude,
+    /// `I² + Q²`.
+    Power,
+    /// `10·log10(I² + Q² + 1)`; the `+ 1` keeps a zero sample at 0 dB
+    /// instead of minus infinity.
+    Db,
+    /// `10·log10((I² + Q²) / noise)`, with the noise power estimated per
+    /// frame from its quietest subcarriers (see [`dsp::noise_floor_estimate`]).
+    Snr,
+}
+
+impl AmplitudeMetric {
+    pub fn next(self) -> Self {
+        match self {
+            AmplitudeMetric::Magnitude => AmplitudeMetric::Power,
+            AmplitudeMetric::Power => AmplitudeMetric::Db,
+            AmplitudeMetric::Db => AmplitudeMetric::Snr,
+            AmplitudeMetric::Snr => AmplitudeMetric::Magnitude,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AmplitudeMetric::Magnitude => "magnitude",
+            AmplitudeMetric::Power => "power",
+            AmplitudeMetric::Db => "dB",
+            AmplitudeMetric::Snr => "SNR",
+        }
+    }
+
+    pub fn units(self) -> &'static str {
+        match self {
+            AmplitudeMetric::Magnitude => "a.u.",
+            AmplitudeMetric::Power => "a.u.²",
+            AmplitudeMetric::Db => "dB",
+            AmplitudeMetric::Snr => "dB SNR",
+        }
+    }
+
+    /// The metric's value for a sample of power `I² + Q²`. SNR needs the
+    /// rest of the frame for its noise estimate, so it is left as power
+    /// here; see [`AmplitudeMetric::frame_values`].
+    pub fn from_power(self, power: f64) -> f64 {
+        match self {
+            AmplitudeMetric::Magnitude => power.sqrt(),
+            AmplitudeMetric::Power | AmplitudeMetric::Snr => power,
+            AmplitudeMetric::Db => 10.0 * (power + 1.0).log10(),
+        }
+    }
+
+    /// The metric for every subcarrier of a frame, given their powers.
+    pub fn frame_values(self, powers: &[f32]) -> Vec<f64> {
+        match (self, dsp::noise_floor_estimate(powers)) {
+            (AmplitudeMetric::Snr, Some(noise)) => powers.iter().map(|&p| dsp::snr_db(p as f64, noise)).collect(),
+            (AmplitudeMetric::Snr, None) => vec![dsp::SNR_FLOOR_DB; powers.len()],
+            _ => powers.iter().map(|&p| self.from_power(p as f64)).collect(),
+        }
+    }
+}
+
+/// Samples in the rolling window of [`Quantity::Variance`].
+pub const VARIANCE_WINDOW: usize = 20;
+
+/// Per-subcarrier quantity shown in the plot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quantity {
+    #[default]
+    Amplitude,
+    /// `|amp(t) - amp(t-1)|` between consecutive frames; near zero for a
+    /// static scene, spiking on motion.
+    Delta,
+    Phase,
+    Rssi,
+    /// Rolling variance of the amplitude over [`VARIANCE_WINDOW`] samples.
+    Variance,
+}
+
+impl Quantity {
+    pub fn next(self) -> Self {
+        match self {
+            Quantity::Amplitude => Quantity::Delta,
+            Quantity::Delta => Quantity::Phase,
+            Quantity::Phase => Quantity::Rssi,
+            Quantity::Rssi => Quantity::Variance,
+            Quantity::Variance => Quantity::Amplitude,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quantity::Amplitude => "Amplitude",
+            Quantity::Delta => "Amplitude delta",
+            Quantity::Phase => "Phase",
+            Quantity::Rssi => "RSSI",
+            Quantity::Variance => "Amplitude variance",
+        }
+    }
+
+    pub fn units(self, metric: AmplitudeMetric) -> String {
+        match self {
+            Quantity::Amplitude | Quantity::Delta => metric.units().to_string(),
+            Quantity::Phase => "rad".to_string(),
+            Quantity::Rssi => "dBm".to_string(),
+            Quantity::Variance => format!("({})²", metric.units()),
+        }
+    }
+
+    /// Whether the quantity is derived from the amplitude metric.
+    pub fn uses_metric(self) -> bool {
+        matches!(self, Quantity::Amplitude | Quantity::Delta | Quantity::Variance)
+    }
+
+    /// Per-sample value from one frame's RSSI and subcarrier I/Q. Delta and
+    /// variance yield the amplitude (per `metric`); the caller applies the
+    /// transform. For [`AmplitudeMetric::Snr`] this is the power, which the
+    /// caller turns into SNR against the frame's noise estimate.
+    pub fn sample(self, metric: AmplitudeMetric, rssi: i32, i: f64, q: f64) -> f64 {
+        match self {
+            Quantity::Amplitude | Quantity::Delta | Quantity::Variance => metric.from_power(i * i + q * q),
+            Quantity::Phase => q.atan2(i),
+            Quantity::Rssi => rssi as f64,
+        }
+    }
+}
+
+/// Errors returned by [`load_csv_series`].
+#[derive(Debug)]
+pub enum ReadError {
+    Io(std::io::Error),
+    EmptyFile,
+    SubcarrierOutOfRange { requested: usize, available: usize },
+    NoValidRows,
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::EmptyFile => write!(f, "CSV file is empty"),
+            ReadError::SubcarrierOutOfRange {
+                requested,
+                available,
+            } => write!(
+                f,
+                "subcarrier {} out of range for this {}-subcarrier file",
+                requested, available
+            ),
+            ReadError::NoValidRows => write!(f, "CSV file contained no valid data rows"),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(e: std::io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+/// `quantity` of `subcarrier` over time, with amplitudes per `metric`.
+/// `.csv.gz` files are decompressed and `.parquet` captures are read too.
+/// With `antenna` set, only rows from that RX antenna are used (if the
+/// capture records antennas).
+pub fn load_csv_series(
     path: &str,
     subcarrier: usize,
-) -> Result<Vec<(f64, f64)>, Box<dyn Error + Send + Sync>> {
-    let content = fs::read_to_string(path)?;
-    let mut lines = content.lines();
-    let _header = lines.next().ok_or("CSV file is empty")?;
-    let i_col = 2 + 2 * subcarrier;
-    let q_col = 3 + 2 * subcarrier;
+    antenna: Option<u8>,
+    quantity: Quantity,
+    metric: AmplitudeMetric,
+) -> Result<Vec<(f64, f64)>, ReadError> {
+    if parquet_capture::is_parquet(path) {
+        let packets = parquet_capture::load_packets(path)?;
+        return series_from_packets(&packets, subcarrier, antenna, quantity, metric, None);
+    }
+    let mut content = String::new();
+    open_capture(path)?.read_to_string(&mut content)?;
+    let content = content.strip_prefix(UTF8_BOM).unwrap_or(&content);
+    // Split on `\n` and drop a trailing `\r`, so CRLF files parse the same.
+    let mut lines = content
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line));
+    let header = lines.next().ok_or(ReadError::EmptyFile)?;
+    let layout = CsvLayout::from_header(header.split(','));
+    let iq_start = layout.iq_start;
+    let header_cols = header.split(',').count();
+    let available = header_cols.saturating_sub(iq_start) / 2;
+    if available == 0 {
+        return Err(ReadError::NoValidRows);
+    }
+    if subcarrier >= available {
+        return Err(ReadError::SubcarrierOutOfRange {
+            requested: subcarrier,
+            available,
+        });
+    }
+    let i_col = iq_start + 2 * subcarrier;
+    let q_col = iq_start + 1 + 2 * subcarrier;
     let mut first_ts: Option<u64> = None;
     let mut out = Vec::new();
 
@@ -24,13 +305,19 @@ pub fn load_csv_amplitude_series(
             continue;
         }
         let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
-        if parts.len() <=  q_col {
+        // Rows are written whole; a short one was cut off mid-write.
+        if parts.len() < header_cols {
             continue;
         }
-        let ts: u64 = match parts[0].parse() {
+        let row_antenna = layout.antenna.and_then(|c| parts.get(c).copied());
+        if !antenna_matches(antenna, layout.antenna.is_some(), row_antenna) {
+            continue;
+        }
+        let ts: u64 = match parts[layout.timestamp].parse() {
             Ok(v) => v,
             Err(_) => continue,
         };
+        let rssi: i32 = parts[layout.rssi].parse().unwrap_or(0);
         let i: f64 = match parts[i_col].parse::<i32>() {
             Ok(v) => v as f64,
             Err(_) => continue,
@@ -39,37 +326,185 @@ pub fn load_csv_amplitude_series(
             Ok(v) => v as f64,
             Err(_) => continue,
         };
-        let amp: f64 = (i * i + q * q).sqrt();
+        let mut value = quantity.sample(metric, rssi, i, q);
+        if metric == AmplitudeMetric::Snr && quantity.uses_metric() {
+            let powers: Vec<f32> = parts[iq_start..header_cols]
+                .chunks_exact(2)
+                .map(|iq| {
+                    let (i, q) = (iq[0].parse().unwrap_or(0.0f32), iq[1].parse().unwrap_or(0.0f32));
+                    i * i + q * q
+                })
+                .collect();
+            value = dsp::noise_floor_estimate(&powers).map_or(dsp::SNR_FLOOR_DB, |noise| dsp::snr_db(value, noise));
+        }
         let t: f64 = if let Some(ts0) = first_ts {
             (ts - ts0) as f64 / 1e6
         } else {
             first_ts = Some(ts);
             0.0
         };
-        out.push((t, amp));
+        out.push((t, value));
+    }
+    finish_series(out, quantity)
+}
+
+/// [`load_csv_series`] for packets already in memory, e.g. from
+/// [`load_csv_packets`], with amplitudes corrected by `calibration` if set.
+pub fn series_from_packets(
+    packets: &[CsiPacket],
+    subcarrier: usize,
+    antenna: Option<u8>,
+    quantity: Quantity,
+    metric: AmplitudeMetric,
+    calibration: Option<&Calibration>,
+) -> Result<Vec<(f64, f64)>, ReadError> {
+    let available = packets.first().ok_or(ReadError::NoValidRows)?.csi_values.len() / 2;
+    if available == 0 {
+        return Err(ReadError::NoValidRows);
+    }
+    if subcarrier >= available {
+        return Err(ReadError::SubcarrierOutOfRange {
+            requested: subcarrier,
+            available,
+        });
+    }
+    let ts0 = packets[0].esp_timestamp;
+    let out = packets
+        .iter()
+        .filter(|p| antenna.is_none() || p.antenna.is_none() || p.antenna == antenna)
+        .map(|p| {
+            let t = p.esp_timestamp.saturating_sub(ts0) as f64 / 1e6;
+            (t, packet_sample(p, subcarrier, quantity, metric, calibration))
+        })
+        .collect();
+    finish_series(out, quantity)
+}
+
+/// Turn per-row samples into the requested `quantity` series.
+fn finish_series(mut out: Vec<(f64, f64)>, quantity: Quantity) -> Result<Vec<(f64, f64)>, ReadError> {
+    match quantity {
+        Quantity::Delta => abs_delta_series(&mut out),
+        Quantity::Variance => {
+            let mut variance = RollingVariance::new(VARIANCE_WINDOW);
+            for (_, v) in out.iter_mut() {
+                *v = variance.push(*v);
+            }
+        }
+        _ => {}
+    }
+    if out.is_empty() {
+        return Err(ReadError::NoValidRows);
     }
     Ok(out)
 }
 
-pub fn load_csv_heatmap(path: &str) -> Result<Vec<Vec<u8>>> {
-    let file = File::open(path)?;
-    let mut rdr = csv::Reader::from_reader(BufReader::new(file));
+pub fn load_csv_heatmap(
+    path: &str,
+    metric: AmplitudeMetric,
+    subcarriers: Option<(usize, usize)>,
+) -> Result<Vec<Vec<u8>>> {
+    let mut raw_amp_rows = load_csv_amplitude_matrix(path, None, metric)?;
+    slice_subcarriers(&mut raw_amp_rows, subcarriers);
+    Ok(normalize_heatmap(&raw_amp_rows))
+}
+
+/// [`load_csv_power_matrix`] converted to `metric`.
+pub fn load_csv_amplitude_matrix(
+    path: &str,
+    antenna: Option<u8>,
+    metric: AmplitudeMetric,
+) -> Result<Vec<Vec<f32>>> {
+    let rows = load_csv_power_matrix(path, antenna)?;
+    Ok(rows
+        .iter()
+        .map(|powers| metric.frame_values(powers).into_iter().map(|v| v as f32).collect())
+        .collect())
+}
+
+/// Per-frame rows of per-subcarrier series (as from
+/// [`all_amplitudes_from_packets`]), for the heatmap and frame view.
+pub fn frame_rows(series: &[Vec<f64>]) -> Vec<Vec<f32>> {
+    let frames = series.first().map_or(0, Vec::len);
+    (0..frames)
+        .map(|f| series.iter().map(|values| values[f] as f32).collect())
+        .collect()
+}
+
+/// Every subcarrier of one packet under `metric`, after `calibration` if
+/// set; SNR is estimated against the packet's own noise floor.
+pub fn packet_metric_values(
+    packet: &CsiPacket,
+    metric: AmplitudeMetric,
+    calibration: Option<&Calibration>,
+) -> Vec<f64> {
+    let powers = packet_powers(packet);
+    match calibration {
+        Some(calibration) => metric.frame_values(&calibration.apply_to_powers(&powers)),
+        None => metric.frame_values(&powers),
+    }
+}
+
+/// `quantity` of `subcarrier` (which must be in range) in one packet, like
+/// [`Quantity::sample`] but with the amplitude after `calibration` and SNR
+/// against the packet's noise floor.
+pub fn packet_sample(
+    packet: &CsiPacket,
+    subcarrier: usize,
+    quantity: Quantity,
+    metric: AmplitudeMetric,
+    calibration: Option<&Calibration>,
+) -> f64 {
+    let (i, q) = (packet.csi_values[2 * subcarrier] as f64, packet.csi_values[2 * subcarrier + 1] as f64);
+    if !quantity.uses_metric() {
+        return quantity.sample(metric, packet.rssi, i, q);
+    }
+    match (metric, calibration) {
+        // SNR needs the whole frame's noise estimate; other metrics just this sample.
+        (AmplitudeMetric::Snr, _) => packet_metric_values(packet, metric, calibration)[subcarrier],
+        (_, Some(calibration)) => metric.from_power(calibration.apply_power(subcarrier, (i * i + q * q) as f32) as f64),
+        (_, None) => quantity.sample(metric, packet.rssi, i, q),
+    }
+}
+
+/// A packet's per-subcarrier power, `I² + Q²`.
+fn packet_powers(packet: &CsiPacket) -> Vec<f32> {
+    packet
+        .csi_values
+        .chunks_exact(2)
+        .map(|iq| (iq[0] as f32).powi(2) + (iq[1] as f32).powi(2))
+        .collect()
+}
+
+/// Read every row's per-subcarrier power (`I² + Q²`) from a capture CSV,
+/// optionally keeping only rows from one RX antenna.
+pub fn load_csv_power_matrix(path: &str, antenna: Option<u8>) -> Result<Vec<Vec<f32>>> {
+    if parquet_capture::is_parquet(path) {
+        let packets = parquet_capture::load_packets(path)?;
+        return Ok(packets
+            .iter()
+            .filter(|p| antenna.is_none() || p.antenna.is_none() || p.antenna == antenna)
+            .map(packet_powers)
+            .collect());
+    }
+    let mut rdr = open_csv(path)?;
 
     let headers = rdr.headers()?.clone();
     let total_cols = headers.len();
+    let layout = CsvLayout::from_header(headers.iter());
+    let iq_start = layout.iq_start;
 
-    // We expect at least: timestamp, rssi, i0, q0
-    if total_cols < 4 {
+    // We expect at least: [host time,] timestamp, rssi, [antenna,] i0, q0
+    if total_cols < iq_start + 2 {
         return Ok(Vec::new());
     }
 
-    // After the first two columns (timestamp, rssi), all remaining columns are interleaved I/Q:
+    // After the host time, timestamp, rssi and antenna, all remaining columns are interleaved I/Q:
     // i0,q0,i1,q1,..., so there should be an even number of them.
-    let num_iq_cols = total_cols - 2;
+    let num_iq_cols = total_cols - iq_start;
     let mut num_subcarriers = num_iq_cols / 2;
 
     // If odd (shouldn't happen), drop the last stray column.
-    if num_iq_cols % 2 != 0 {
+    if !num_iq_cols.is_multiple_of(2) {
         num_subcarriers -= 1;
     }
 
@@ -77,19 +512,22 @@ pub fn load_csv_heatmap(path: &str) -> Result<Vec<Vec<u8>>> {
         return Ok(Vec::new());
     }
 
-    // First pass: compute raw amplitudes and track global min/max.
     let mut raw_amp_rows: Vec<Vec<f32>> = Vec::new();
-    let mut global_min = f32::INFINITY;
-    let mut global_max = f32::NEG_INFINITY;
 
     for result in rdr.records() {
-        let record = result?;
+        let Some(record) = complete_record(result)? else {
+            continue;
+        };
+        let row_antenna = layout.antenna.and_then(|c| record.get(c));
+        if !antenna_matches(antenna, layout.antenna.is_some(), row_antenna) {
+            continue;
+        }
 
         let mut amps_for_row = Vec::with_capacity(num_subcarriers);
         for sc in 0..num_subcarriers {
-            // Column layout: 0: ts, 1: rssi, 2: i0, 3: q0, 4: i1, 5: q1, ...
-            let i_idx = 2 + 2 * sc;
-            let q_idx = 2 + 2 * sc + 1;
+            // I/Q columns are interleaved from `iq_start`: i0, q0, i1, q1, ...
+            let i_idx = iq_start + 2 * sc;
+            let q_idx = iq_start + 2 * sc + 1;
 
             let i_val: f32 = record
                 .get(i_idx)
@@ -106,33 +544,103 @@ pub fn load_csv_heatmap(path: &str) -> Result<Vec<Vec<u8>>> {
 
             // Your equation (no sqrt): A_k(t_i) = I_k^2 + Q_k^2
             let a_sq = i_val * i_val + q_val * q_val;
-
-            global_min = global_min.min(a_sq);
-            global_max = global_max.max(a_sq);
             amps_for_row.push(a_sq);
         }
 
         raw_amp_rows.push(amps_for_row);
     }
 
+    Ok(raw_amp_rows)
+}
+
+/// Every subcarrier's amplitude series (per `metric`) from one read of a
+/// capture: the row times in seconds from the first row, and one series per
+/// subcarrier over those times. [`load_csv_series`] reads the whole file for
+/// a single subcarrier; use this when many are needed. With `antenna` set,
+/// only rows from that RX antenna are used.
+pub fn load_csv_all_amplitudes(
+    path: &str,
+    antenna: Option<u8>,
+    metric: AmplitudeMetric,
+) -> Result<(Vec<f64>, Vec<Vec<f64>>)> {
+    Ok(all_amplitudes_from_packets(&load_csv_packets(path)?, antenna, metric, None))
+}
+
+/// [`load_csv_all_amplitudes`] for packets already in memory, with
+/// amplitudes corrected by `calibration` if set.
+pub fn all_amplitudes_from_packets(
+    packets: &[CsiPacket],
+    antenna: Option<u8>,
+    metric: AmplitudeMetric,
+    calibration: Option<&Calibration>,
+) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let subcarriers = packets.first().map_or(0, |p| p.csi_values.len() / 2);
+    let mut times = Vec::with_capacity(packets.len());
+    let mut series = vec![Vec::with_capacity(packets.len()); subcarriers];
+    let mut first_ts: Option<u64> = None;
+    for packet in packets
+        .iter()
+        .filter(|p| antenna.is_none() || p.antenna.is_none() || p.antenna == antenna)
+    {
+        let ts0 = *first_ts.get_or_insert(packet.esp_timestamp);
+        times.push(packet.esp_timestamp.saturating_sub(ts0) as f64 / 1e6);
+        let frame = packet_metric_values(packet, metric, calibration);
+        for (sc, values) in series.iter_mut().enumerate() {
+            values.push(frame.get(sc).copied().unwrap_or(0.0));
+        }
+    }
+    (times, series)
+}
+
+/// Index range for the inclusive subcarrier selection `[lo, hi]` out of
+/// `available`: the ends are swapped if `lo > hi` and clamped to valid
+/// indices. Empty only when `available` is 0.
+pub fn clamp_subcarrier_range(lo: usize, hi: usize, available: usize) -> Range<usize> {
+    if available == 0 {
+        return 0..0;
+    }
+    let (lo, hi) = if lo > hi { (hi, lo) } else { (lo, hi) };
+    let hi = hi.min(available - 1);
+    lo.min(hi)..hi + 1
+}
+
+/// Keep only the selected subcarrier columns of each row (all if `None`).
+pub fn slice_subcarriers<T>(rows: &mut [Vec<T>], subcarriers: Option<(usize, usize)>) {
+    let Some((lo, hi)) = subcarriers else {
+        return;
+    };
+    for row in rows {
+        let range = clamp_subcarrier_range(lo, hi, row.len());
+        row.truncate(range.end);
+        row.drain(..range.start);
+    }
+}
+
+/// Scale a matrix of values to 0–100 using its global min/max.
+pub fn normalize_heatmap(raw_amp_rows: &[Vec<f32>]) -> Vec<Vec<u8>> {
     if raw_amp_rows.is_empty() {
-        return Ok(Vec::new());
+        return Vec::new();
+    }
+
+    let mut global_min = f32::INFINITY;
+    let mut global_max = f32::NEG_INFINITY;
+    for &v in raw_amp_rows.iter().flatten() {
+        global_min = global_min.min(v);
+        global_max = global_max.max(v);
     }
 
     // Guard against degenerate case (all amplitudes identical, NaN, etc.)
     if !global_min.is_finite() || !global_max.is_finite() || global_max <= global_min {
-        let rows = raw_amp_rows.len();
-        let cols = num_subcarriers;
-        return Ok(vec![vec![0u8; cols]; rows]);
+        return raw_amp_rows.iter().map(|row| vec![0u8; row.len()]).collect();
     }
 
-    // Second pass: normalize to 0–100.
+    // Normalize to 0–100.
     let range = global_max - global_min;
     let mut heatmap: Vec<Vec<u8>> = Vec::with_capacity(raw_amp_rows.len());
 
-    for row in raw_amp_rows.into_iter() {
+    for row in raw_amp_rows {
         let mut out_row = Vec::with_capacity(row.len());
-        for a_sq in row.into_iter() {
+        for &a_sq in row {
             let norm = (a_sq - global_min) / range; // 0.0 .. 1.0
             let clamped = norm.clamp(0.0, 1.0);
             let scaled = (clamped * 100.0).round() as u8; // 0 .. 100
@@ -141,5 +649,369 @@ pub fn load_csv_heatmap(path: &str) -> Result<Vec<Vec<u8>>> {
         heatmap.push(out_row);
     }
 
-    Ok(heatmap)
-}
\ No newline at end of file
+    heatmap
+}
+
+/// Map a matrix centred on zero (e.g. after
+/// [`crate::dsp::subtract_column_means`]) to 0–100 with 0 at 50, scaled
+/// symmetrically by the largest magnitude so equal deviations above and
+/// below the mean get equal weight.
+pub fn normalize_heatmap_centered(rows: &[Vec<f32>]) -> Vec<Vec<u8>> {
+    let max_abs = rows
+        .iter()
+        .flatten()
+        .filter(|v| v.is_finite())
+        .fold(0.0f32, |mx, v| mx.max(v.abs()));
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|&v| {
+                    let norm = if max_abs > 0.0 { (v / max_abs).clamp(-1.0, 1.0) } else { 0.0 };
+                    (50.0 + norm * 50.0).round() as u8
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Read a raw I/Q capture back into packets. Rows with an unparsable
+/// timestamp or RSSI are skipped; unparsable I/Q values read as 0.
+pub fn load_csv_packets(path: &str) -> Result<Vec<CsiPacket>> {
+    if parquet_capture::is_parquet(path) {
+        return Ok(parquet_capture::load_packets(path)?);
+    }
+    let mut rdr = open_csv(path)?;
+    let headers = rdr.headers()?.clone();
+    let layout = CsvLayout::from_header(headers.iter());
+    let iq_start = layout.iq_start;
+    let num_iq_cols = headers.len().saturating_sub(iq_start) / 2 * 2;
+
+    let mut packets = Vec::new();
+    for result in rdr.records() {
+        let Some(record) = complete_record(result)? else {
+            continue;
+        };
+        let ts = record.get(layout.timestamp).and_then(|v| v.trim().parse::<u64>().ok());
+        let rssi = record.get(layout.rssi).and_then(|v| v.trim().parse::<i32>().ok());
+        let (Some(esp_timestamp), Some(rssi)) = (ts, rssi) else {
+            continue;
+        };
+        let antenna = layout
+            .antenna
+            .and_then(|c| record.get(c))
+            .and_then(|v| v.trim().parse::<u8>().ok());
+        let host_time_ms = layout
+            .host_time
+            .and_then(|c| record.get(c))
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        let csi_values = (0..num_iq_cols)
+            .map(|c| {
+                record
+                    .get(iq_start + c)
+                    .and_then(|v| v.trim().parse::<i32>().ok())
+                    .unwrap_or(0)
+            })
+            .collect();
+        packets.push(CsiPacket {
+            esp_timestamp,
+            rssi,
+            csi_values,
+            antenna,
+            host_time_ms,
+        });
+    }
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn heatmap_is_scaled_by_the_global_range() {
+        // z-scored columns: the heatmap scale is shared, not per column.
+        let rows = vec![vec![-1.0, 0.0, 3.0], vec![1.0, 0.0, -1.0]];
+        assert_eq!(normalize_heatmap(&rows), vec![vec![0, 25, 100], vec![50, 25, 0]]);
+        assert_eq!(normalize_heatmap(&[vec![2.0, 2.0]]), vec![vec![0, 0]]);
+        assert!(normalize_heatmap(&[]).is_empty());
+    }
+
+    #[test]
+    fn bom_and_crlf_load_like_plain_lf() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-bom-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let rows = [
+            "esp_timestamp_us,rssi,i0,q0,i1,q1",
+            "1000000,-40,3,4,1,0",
+            "1010000,-41,6,8,0,2",
+            "1020000,-42,0,5,2,2",
+        ];
+        let plain = dir.join("plain.csv");
+        let windows = dir.join("windows.csv");
+        fs::write(&plain, rows.join("\n") + "\n").unwrap();
+        fs::write(&windows, format!("{UTF8_BOM}{}\r\n", rows.join("\r\n"))).unwrap();
+        let (plain, windows) = (plain.to_str().unwrap(), windows.to_str().unwrap());
+
+        for quantity in [Quantity::Amplitude, Quantity::Rssi] {
+            let expected = load_csv_series(plain, 1, None, quantity, AmplitudeMetric::Magnitude).unwrap();
+            assert_eq!(expected.len(), 3);
+            assert_eq!(
+                load_csv_series(windows, 1, None, quantity, AmplitudeMetric::Magnitude).unwrap(),
+                expected
+            );
+        }
+        let expected = load_csv_power_matrix(plain, None).unwrap();
+        assert_eq!(expected[0], vec![25.0, 1.0]);
+        assert_eq!(load_csv_power_matrix(windows, None).unwrap(), expected);
+        let packets = load_csv_packets(windows).unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].esp_timestamp, 1000000);
+        assert_eq!(packets[2].csi_values, vec![0, 5, 2, 2]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn gzip_capture_loads_like_plain() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-gz-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv = "esp_timestamp_us,rssi,i0,q0\n1000000,-40,3,4\n1010000,-41,6,8\n";
+        let plain = dir.join("plain.csv");
+        let gz = dir.join("plain.csv.gz");
+        fs::write(&plain, csv).unwrap();
+        let mut encoder = GzEncoder::new(File::create(&gz).unwrap(), Compression::default());
+        encoder.write_all(csv.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        let (plain, gz) = (plain.to_str().unwrap(), gz.to_str().unwrap());
+
+        let series = load_csv_series(gz, 0, None, Quantity::Amplitude, AmplitudeMetric::Magnitude).unwrap();
+        assert_eq!(
+            series,
+            load_csv_series(plain, 0, None, Quantity::Amplitude, AmplitudeMetric::Magnitude).unwrap()
+        );
+        assert_eq!(series[1], (0.01, 10.0));
+        assert_eq!(load_csv_power_matrix(gz, None).unwrap(), vec![vec![25.0], vec![100.0]]);
+        // Plot and heatmap agree on what amplitude means.
+        for metric in [AmplitudeMetric::Magnitude, AmplitudeMetric::Power, AmplitudeMetric::Db, AmplitudeMetric::Snr] {
+            let series = load_csv_series(plain, 0, None, Quantity::Amplitude, metric).unwrap();
+            let matrix = load_csv_amplitude_matrix(plain, None, metric).unwrap();
+            for (row, (_, v)) in matrix.iter().zip(&series) {
+                assert!((row[0] as f64 - v).abs() < 1e-4, "{metric:?}: {} vs {v}", row[0]);
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_final_row_is_skipped() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-trunc-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cut.csv");
+        // Last row was cut off mid-write, without a newline.
+        fs::write(&path, "esp_timestamp_us,rssi,i0,q0,i1,q1\n1000000,-40,3,4,1,0\n1010000,-41,6,8,0,2\n1020000,-42,0").unwrap();
+        let path = path.to_str().unwrap();
+
+        let series = load_csv_series(path, 1, None, Quantity::Amplitude, AmplitudeMetric::Magnitude).unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(load_csv_power_matrix(path, None).unwrap().len(), 2);
+        assert_eq!(load_csv_packets(path).unwrap().len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn all_amplitudes_match_per_subcarrier_series() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-all-amp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("all.csv");
+        fs::write(
+            &path,
+            "esp_timestamp_us,rssi,antenna,i0,q0,i1,q1,i2,q2\n\
+             1000000,-40,0,3,4,1,0,0,0\n\
+             1005000,-40,1,9,9,9,9,9,9\n\
+             1010000,-41,0,6,8,0,2,1,1\n\
+             1020000,-42,0,0,5,2,2,4,3\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        for metric in [AmplitudeMetric::Magnitude, AmplitudeMetric::Power, AmplitudeMetric::Db, AmplitudeMetric::Snr] {
+            let (times, series) = load_csv_all_amplitudes(path, Some(0), metric).unwrap();
+            assert_eq!(series.len(), 3);
+            for (sc, values) in series.iter().enumerate() {
+                let expected = load_csv_series(path, sc, Some(0), Quantity::Amplitude, metric).unwrap();
+                let one_pass: Vec<(f64, f64)> = times.iter().copied().zip(values.iter().copied()).collect();
+                assert_eq!(one_pass, expected, "{metric:?} subcarrier {sc}");
+            }
+        }
+        let (times, series) = load_csv_all_amplitudes(path, None, AmplitudeMetric::Magnitude).unwrap();
+        assert_eq!(times.len(), 4);
+        assert_eq!(series[0][1], (162.0f64).sqrt());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn packet_loaders_match_file_loaders() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-packets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("packets.csv");
+        fs::write(
+            &path,
+            "esp_timestamp_us,rssi,antenna,i0,q0,i1,q1\n\
+             1000000,-40,0,3,4,1,0\n\
+             1005000,-40,1,9,9,9,9\n\
+             1010000,-41,0,6,8,0,2\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let packets = load_csv_packets(path).unwrap();
+
+        for metric in [AmplitudeMetric::Magnitude, AmplitudeMetric::Db, AmplitudeMetric::Snr] {
+            for quantity in [Quantity::Amplitude, Quantity::Phase, Quantity::Rssi] {
+                assert_eq!(
+                    series_from_packets(&packets, 1, Some(0), quantity, metric, None).unwrap(),
+                    load_csv_series(path, 1, Some(0), quantity, metric).unwrap(),
+                    "{quantity:?} {metric:?}"
+                );
+            }
+            assert_eq!(
+                frame_rows(&all_amplitudes_from_packets(&packets, Some(0), metric, None).1),
+                load_csv_amplitude_matrix(path, Some(0), metric).unwrap()
+            );
+        }
+        assert!(matches!(
+            series_from_packets(&packets, 2, None, Quantity::Amplitude, AmplitudeMetric::Magnitude, None),
+            Err(ReadError::SubcarrierOutOfRange { requested: 2, available: 2 })
+        ));
+        assert!(matches!(
+            series_from_packets(&[], 0, None, Quantity::Amplitude, AmplitudeMetric::Magnitude, None),
+            Err(ReadError::NoValidRows)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn quantities_cycle_and_transform_the_series() {
+        let mut quantity = Quantity::default();
+        let mut seen = vec![quantity];
+        while quantity.next() != Quantity::default() {
+            quantity = quantity.next();
+            seen.push(quantity);
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(!Quantity::Phase.uses_metric() && !Quantity::Rssi.uses_metric());
+
+        let magnitude = AmplitudeMetric::Magnitude;
+        assert_eq!(Quantity::Amplitude.sample(magnitude, -40, 3.0, 4.0), 5.0);
+        assert_eq!(Quantity::Phase.sample(magnitude, -40, 0.0, 2.0), std::f64::consts::FRAC_PI_2);
+        assert_eq!(Quantity::Rssi.sample(magnitude, -40, 3.0, 4.0), -40.0);
+
+        // Amplitudes 5, 10, 5 on subcarrier 0, 10 ms apart.
+        let packets: Vec<CsiPacket> = [(3, 4), (6, 8), (4, 3)]
+            .into_iter()
+            .enumerate()
+            .map(|(n, (i, q))| CsiPacket {
+                esp_timestamp: 1000000 + n as u64 * 10000,
+                rssi: -40,
+                csi_values: vec![i, q],
+                antenna: None,
+                host_time_ms: None,
+            })
+            .collect();
+        let values = |quantity| -> Vec<f64> {
+            series_from_packets(&packets, 0, None, quantity, magnitude, None)
+                .unwrap()
+                .into_iter()
+                .map(|(_, v)| v)
+                .collect()
+        };
+        assert_eq!(values(Quantity::Amplitude), vec![5.0, 10.0, 5.0]);
+        assert_eq!(values(Quantity::Delta), vec![0.0, 5.0, 5.0]);
+        // Variance over everything so far, while the window isn't full.
+        let variance = values(Quantity::Variance);
+        assert_eq!(variance[..2], [0.0, 6.25]);
+        assert!((variance[2] - 50.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calibration_applies_before_the_metric() {
+        let packets = [CsiPacket {
+            esp_timestamp: 1000000,
+            rssi: -40,
+            csi_values: vec![3, 4, 6, 8],
+            antenna: None,
+            host_time_ms: None,
+        }];
+        let cal = Calibration {
+            scale: vec![2.0, 0.5],
+            offset: vec![0.0, 0.0],
+        };
+        let series = |quantity, metric| series_from_packets(&packets, 0, None, quantity, metric, Some(&cal)).unwrap();
+        assert_eq!(series(Quantity::Amplitude, AmplitudeMetric::Magnitude), vec![(0.0, 10.0)]);
+        assert_eq!(series(Quantity::Amplitude, AmplitudeMetric::Power), vec![(0.0, 100.0)]);
+        // Phase and RSSI aren't amplitudes and stay raw.
+        assert_eq!(series(Quantity::Rssi, AmplitudeMetric::Magnitude), vec![(0.0, -40.0)]);
+        let (_, all) = all_amplitudes_from_packets(&packets, None, AmplitudeMetric::Magnitude, Some(&cal));
+        assert_eq!(all, vec![vec![10.0], vec![5.0]]);
+        let snr = series_from_packets(&packets, 0, None, Quantity::Amplitude, AmplitudeMetric::Snr, Some(&cal)).unwrap();
+        assert_eq!(snr[0].1, all_amplitudes_from_packets(&packets, None, AmplitudeMetric::Snr, Some(&cal)).1[0][0]);
+    }
+
+    #[test]
+    fn host_time_column_is_skipped_by_loaders() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-host-time-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stamped.csv");
+        let mut file = File::create(&path).unwrap();
+        let packets = [(1_700_000_000_000, 1000000, Some(1)), (1_700_000_000_010, 1010000, Some(0))];
+        let with_antenna = packets.map(|(host, ts, antenna)| CsiPacket {
+            esp_timestamp: ts,
+            rssi: -40,
+            csi_values: vec![3, 4, 6, 8],
+            antenna,
+            host_time_ms: Some(host),
+        });
+        use std::io::Write;
+        writeln!(file, "{}", crate::csv_utils::generate_csv_header(4, true, true)).unwrap();
+        for packet in &with_antenna {
+            crate::csv_utils::write_csv_line(&mut file, packet).unwrap();
+        }
+        drop(file);
+        let path = path.to_str().unwrap();
+
+        let header = fs::read_to_string(path).unwrap();
+        assert!(header.starts_with("host_time_ms,esp_timestamp_us,rssi,antenna,i0,q0"));
+        let loaded = load_csv_packets(path).unwrap();
+        assert_eq!(loaded[1].host_time_ms, Some(1_700_000_000_010));
+        assert_eq!(loaded[1].esp_timestamp, 1010000);
+        assert_eq!(loaded[1].antenna, Some(0));
+        assert_eq!(loaded[1].csi_values, vec![3, 4, 6, 8]);
+        let series = load_csv_series(path, 1, Some(0), Quantity::Amplitude, AmplitudeMetric::Magnitude).unwrap();
+        assert_eq!(series, vec![(0.0, 10.0)]);
+        assert_eq!(load_csv_power_matrix(path, None).unwrap()[0], vec![25.0, 100.0]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn subcarrier_range_is_clamped_and_ordered() {
+        assert_eq!(clamp_subcarrier_range(4, 10, 64), 4..11);
+        assert_eq!(clamp_subcarrier_range(10, 4, 64), 4..11);
+        assert_eq!(clamp_subcarrier_range(60, 200, 64), 60..64);
+        assert_eq!(clamp_subcarrier_range(100, 200, 64), 63..64);
+        assert_eq!(clamp_subcarrier_range(0, 5, 0), 0..0);
+
+        let mut rows = vec![vec![0, 1, 2, 3, 4], vec![5, 6, 7, 8, 9]];
+        slice_subcarriers(&mut rows, Some((3, 1)));
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![6, 7, 8]]);
+        slice_subcarriers(&mut rows, None);
+        assert_eq!(rows[0].len(), 3);
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs, io,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::manifest::Manifest;
+
+const SCHEDULE_EXT: &str = ".schedule";
+
+/// Repeated recordings: `on_secs` long, `off_secs` apart, `count` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub on_secs: u64,
+    pub off_secs: u64,
+    pub count: u32,
+}
+
+impl Schedule {
+    /// `--schedule` argument: `<on_s>,<off_s>,<count>`, e.g. `30,570,144`
+    /// for 30 s every 10 min for a day.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split(',').map(str::trim);
+        let schedule = Schedule {
+            on_secs: parts.next()?.parse().ok().filter(|&s| s > 0)?,
+            off_secs: parts.next()?.parse().ok()?,
+            count: parts.next()?.parse().ok().filter(|&n| n > 0)?,
+        };
+        parts.next().is_none().then_some(schedule)
+    }
+
+    /// Start-to-start time of consecutive recordings.
+    pub fn period(&self) -> Duration {
+        Duration::from_secs(self.on_secs + self.off_secs)
+    }
+}
+
+/// Name of one scheduled recording: `<name>_<YYYYmmdd-HHMMSS>`.
+pub fn run_name(name: &str, started: SystemTime) -> String {
+    let started: DateTime<Local> = started.into();
+    format!("{}_{}", name, started.format("%Y%m%d-%H%M%S"))
+}
+
+/// How far a schedule has got, saved as `<data_dir>/<name>.schedule` after
+/// every recording so a restarted run picks up where it stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleProgress {
+    pub name: String,
+    pub schedule: Schedule,
+    /// Recordings finished so far.
+    pub completed: u32,
+    /// When the next recording is due.
+    pub next_start: SystemTime,
+}
+
+impl ScheduleProgress {
+    fn path(data_dir: &str, name: &str) -> String {
+        format!("{}/{}{}", data_dir, name, SCHEDULE_EXT)
+    }
+
+    /// Resume the unfinished run of `schedule` saved for `name`, or start a
+    /// new one now if there is none (or it had different settings).
+    pub fn resume_or_start(data_dir: &str, name: &str, schedule: Schedule) -> Self {
+        let fresh = Self {
+            name: name.to_string(),
+            schedule,
+            completed: 0,
+            next_start: SystemTime::now(),
+        };
+        let Ok(manifest) = Manifest::read(&Self::path(data_dir, name)) else {
+            return fresh;
+        };
+        let number = |key: &str| manifest.get(key).and_then(|v| v.parse::<u64>().ok());
+        let saved = Schedule {
+            on_secs: number("on_secs").unwrap_or(0),
+            off_secs: number("off_secs").unwrap_or(0),
+            count: number("count").unwrap_or(0) as u32,
+        };
+        match (number("completed"), number("next_start_unix_ms")) {
+            (Some(completed), Some(next_ms)) if saved == schedule && completed < schedule.count as u64 => Self {
+                completed: completed as u32,
+                next_start: UNIX_EPOCH + Duration::from_millis(next_ms),
+                ..fresh
+            },
+            _ => fresh,
+        }
+    }
+
+    pub fn save(&self, data_dir: &str) -> io::Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let mut manifest = Manifest::new();
+        manifest.set("on_secs", self.schedule.on_secs);
+        manifest.set("off_secs", self.schedule.off_secs);
+        manifest.set("count", self.schedule.count);
+        manifest.set("completed", self.completed);
+        let next_ms = self.next_start.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        manifest.set("next_start_unix_ms", next_ms);
+        manifest.write(&Self::path(data_dir, &self.name))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.completed >= self.schedule.count
+    }
+
+    /// Count a recording that started at `started`. The next one is due a
+    /// period later, or right away if that has already passed.
+    pub fn record_done(&mut self, started: SystemTime) {
+        self.completed += 1;
+        self.next_start = (started + self.schedule.period()).max(SystemTime::now());
+    }
+
+    /// Time until the next recording is due; zero once it is.
+    pub fn wait(&self) -> Duration {
+        self.next_start.duration_since(SystemTime::now()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_survives_a_restart() {
+        let dir = std::env::temp_dir().join(format!("esp-csi-tui-schedule-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        let schedule = Schedule::parse("30, 570, 3").unwrap();
+        assert_eq!(schedule.period(), Duration::from_secs(600));
+        assert_eq!(Schedule::parse("0,10,3"), None);
+        assert_eq!(Schedule::parse("30,10"), None);
+
+        let mut progress = ScheduleProgress::resume_or_start(dir, "walk", schedule);
+        assert_eq!(progress.completed, 0);
+        progress.record_done(SystemTime::now());
+        progress.save(dir).unwrap();
+        assert!(progress.wait() > Duration::from_secs(590));
+
+        let resumed = ScheduleProgress::resume_or_start(dir, "walk", schedule);
+        assert_eq!(resumed.completed, 1);
+        assert_eq!(
+            resumed.next_start.duration_since(UNIX_EPOCH).unwrap().as_millis(),
+            progress.next_start.duration_since(UNIX_EPOCH).unwrap().as_millis()
+        );
+        // Different settings start over.
+        let other = Schedule { count: 5, ..schedule };
+        assert_eq!(ScheduleProgress::resume_or_start(dir, "walk", other).completed, 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+
+use crate::dsp::magnitude_spectrum;
+use crate::heatmap::Heatmap;
+use crate::read_data::normalize_heatmap;
+
+/// Samples per FFT window.
+pub const DEFAULT_WINDOW: usize = 64;
+/// New samples between consecutive windows.
+pub const DEFAULT_HOP: usize = 8;
+/// Spectrum columns kept for display.
+pub const DEFAULT_COLUMNS: usize = 256;
+
+/// Scrolling spectrogram of a live sample stream: one magnitude spectrum
+/// per `hop` samples over the last `window` samples.
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    window: usize,
+    hop: usize,
+    max_columns: usize,
+    samples: VecDeque<f64>,
+    since_last: usize,
+    columns: VecDeque<Vec<f32>>,
+}
+
+impl Default for Spectrogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW, DEFAULT_HOP, DEFAULT_COLUMNS)
+    }
+}
+
+impl Spectrogram {
+    pub fn new(window: usize, hop: usize, max_columns: usize) -> Self {
+        Self {
+            window: window.max(2),
+            hop: hop.max(1),
+            max_columns,
+            samples: VecDeque::new(),
+            since_last: 0,
+            columns: VecDeque::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.since_last = 0;
+        self.columns.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Add a sample; computes a new column once the window is full and
+    /// `hop` samples have arrived since the previous one.
+    pub fn push(&mut self, sample: f64) {
+        self.samples.push_back(sample);
+        if self.samples.len() > self.window {
+            self.samples.pop_front();
+        }
+        self.since_last += 1;
+        if self.samples.len() == self.window && self.since_last >= self.hop {
+            self.since_last = 0;
+            let window: Vec<f64> = self.samples.iter().copied().collect();
+            self.columns.push_back(magnitude_spectrum(&window));
+            if self.columns.len() > self.max_columns {
+                self.columns.pop_front();
+            }
+        }
+    }
+
+    /// The newest `width` columns and lowest `height` bins as a heatmap: time
+    /// left to right, highest frequency at the top. The DC bin is dropped (the
+    /// window's mean is removed, so it carries nothing).
+    pub fn to_heatmap(&self, width: usize, height: usize) -> Heatmap {
+        let skip = self.columns.len().saturating_sub(width);
+        let cols: Vec<&Vec<f32>> = self.columns.iter().skip(skip).collect();
+        let bins = cols.iter().map(|c| c.len()).min().unwrap_or(0).min(height + 1);
+        let rows: Vec<Vec<f32>> = (1..bins)
+            .rev()
+            .map(|bin| cols.iter().map(|c| c[bin]).collect())
+            .collect();
+        // One column per window keeps as much history on screen as possible.
+        Heatmap::new(normalize_heatmap(&rows)).with_cell_width(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_follow_the_window_and_hop() {
+        let mut spectrogram = Spectrogram::new(8, 2, 3);
+        for i in 0..7 {
+            spectrogram.push(i as f64);
+        }
+        assert!(spectrogram.is_empty());
+        spectrogram.push(7.0);
+        assert_eq!(spectrogram.columns.len(), 1);
+        // One more sample is short of a hop; the next completes it.
+        spectrogram.push(0.0);
+        assert_eq!(spectrogram.columns.len(), 1);
+        spectrogram.push(0.0);
+        assert_eq!(spectrogram.columns.len(), 2);
+        // Only `max_columns` are kept.
+        for _ in 0..10 {
+            spectrogram.push(1.0);
+        }
+        assert_eq!(spectrogram.columns.len(), 3);
+        spectrogram.clear();
+        assert!(spectrogram.is_empty());
+    }
+
+    #[test]
+    fn heatmap_puts_high_frequencies_on_top_without_dc() {
+        // Alternating samples: all energy in the Nyquist bin.
+        let mut spectrogram = Spectrogram::new(8, 8, 4);
+        for i in 0..16 {
+            spectrogram.push(if i % 2 == 0 { 1.0 } else { -1.0 });
+        }
+        let heatmap = spectrogram.to_heatmap(10, 10);
+        // Bins 4..=1 of a 5-bin spectrum, two columns; the Hann window leaks
+        // half the Nyquist magnitude into bin 3.
+        assert_eq!(heatmap.values, vec![vec![100, 100], vec![50, 50], vec![0, 0], vec![0, 0]]);
+        assert_eq!(heatmap.cell_width, 1);
+
+        // Limited to the newest column and the two lowest non-DC bins.
+        let heatmap = spectrogram.to_heatmap(1, 2);
+        assert_eq!(heatmap.values.len(), 2);
+        assert!(heatmap.values.iter().all(|row| row.len() == 1));
+    }
+}
@@ -0,0 +1,64 @@
+/// Consecutive repeats of the same CSI array before it counts as stuck.
+/// Real CSI is never bit-identical frame to frame, so even a short run
+/// means the firmware or link has wedged.
+pub const STUCK_THRESHOLD: u64 = 5;
+
+/// Detects runs of bit-identical `csi_values` between consecutive frames,
+/// which otherwise show up as a clean-looking flat heatmap.
+#[derive(Debug, Default)]
+pub struct StuckFrameDetector {
+    last: Option<Vec<i32>>,
+    /// Repeats of `last` seen so far in the current run.
+    repeats: u64,
+    /// Runs that went past [`STUCK_THRESHOLD`] repeats.
+    pub stuck_runs: u64,
+    /// Longest run of repeats seen.
+    pub longest_run: u64,
+}
+
+impl StuckFrameDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next frame's CSI array. Returns `true` once per run, on the
+    /// frame that takes it past [`STUCK_THRESHOLD`] repeats.
+    pub fn observe(&mut self, csi_values: &[i32]) -> bool {
+        if self.last.as_deref() == Some(csi_values) {
+            self.repeats += 1;
+        } else {
+            self.last = Some(csi_values.to_vec());
+            self.repeats = 0;
+        }
+        self.longest_run = self.longest_run.max(self.repeats);
+        if self.repeats == STUCK_THRESHOLD + 1 {
+            self.stuck_runs += 1;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::{Pattern, SyntheticCsi};
+
+    #[test]
+    fn constant_frames_are_stuck_noisy_ones_are_not() {
+        let mut stuck = StuckFrameDetector::new();
+        let fired = SyntheticCsi::new(64, 20.0, Pattern::Constant)
+            .take(20)
+            .filter(|p| stuck.observe(&p.csi_values))
+            .count();
+        assert_eq!(fired, 1);
+        assert_eq!(stuck.stuck_runs, 1);
+        assert_eq!(stuck.longest_run, 19);
+
+        let mut live = StuckFrameDetector::new();
+        for packet in SyntheticCsi::new(64, 20.0, Pattern::Noisy { spread: 5.0, seed: 7 }).take(200) {
+            live.observe(&packet.csi_values);
+        }
+        assert_eq!(live.stuck_runs, 0);
+    }
+}
@@ -0,0 +1,68 @@
+/// Subcarriers in the LLTF block that starts every ESP CSI frame.
+pub const LLTF_SUBCARRIERS: usize = 64;
+/// Highest |subcarrier number| carrying data in a 20 MHz legacy symbol;
+/// those beyond are guard bands.
+const LAST_USED: i32 = 26;
+/// Subcarrier numbers of the four pilots.
+const PILOTS: [i32; 4] = [-21, -7, 7, 21];
+
+/// What a subcarrier carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubcarrierKind {
+    Data,
+    /// Known symbols for phase tracking; steady rather than channel-driven.
+    Pilot,
+    /// DC or a guard band: nothing is sent, so the amplitude is ~0 or noise.
+    Null,
+}
+
+/// OFDM subcarrier number of LLTF value `index`: ESP-IDF orders them
+/// 0..=31, then -32..=-1.
+pub fn subcarrier_number(index: usize) -> i32 {
+    let index = (index % LLTF_SUBCARRIERS) as i32;
+    if index < 32 { index } else { index - LLTF_SUBCARRIERS as i32 }
+}
+
+/// Kind of the subcarrier at `index` in a frame. Only the LLTF block is
+/// classified; subcarriers after it count as data.
+pub fn subcarrier_kind(index: usize) -> SubcarrierKind {
+    if index >= LLTF_SUBCARRIERS {
+        return SubcarrierKind::Data;
+    }
+    let number = subcarrier_number(index);
+    if number == 0 || number.abs() > LAST_USED {
+        SubcarrierKind::Null
+    } else if PILOTS.contains(&number) {
+        SubcarrierKind::Pilot
+    } else {
+        SubcarrierKind::Data
+    }
+}
+
+/// Kinds of `columns` consecutive subcarriers starting at index `first`,
+/// e.g. for the columns of a heatmap limited to a subcarrier range.
+pub fn column_kinds(first: usize, columns: usize) -> Vec<SubcarrierKind> {
+    (first..first + columns).map(subcarrier_kind).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lltf_layout_has_12_nulls_and_4_pilots() {
+        let kinds: Vec<SubcarrierKind> = (0..LLTF_SUBCARRIERS).map(subcarrier_kind).collect();
+        let count = |kind| kinds.iter().filter(|k| **k == kind).count();
+        assert_eq!(count(SubcarrierKind::Null), 12);
+        assert_eq!(count(SubcarrierKind::Pilot), 4);
+        assert_eq!(count(SubcarrierKind::Data), 48);
+        assert_eq!(subcarrier_number(63), -1);
+        assert_eq!(subcarrier_kind(0), SubcarrierKind::Null);
+        assert_eq!(subcarrier_kind(7), SubcarrierKind::Pilot);
+        assert_eq!(subcarrier_kind(43), SubcarrierKind::Pilot);
+        assert_eq!(subcarrier_kind(26), SubcarrierKind::Data);
+        assert_eq!(subcarrier_kind(27), SubcarrierKind::Null);
+        assert_eq!(subcarrier_kind(38), SubcarrierKind::Data);
+        assert_eq!(subcarrier_kind(100), SubcarrierKind::Data);
+    }
+}
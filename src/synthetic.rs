@@ -0,0 +1,95 @@
+use std::f64::consts::TAU;
+
+use crate::csi_packet::CsiPacket;
+
+/// How the amplitude of every subcarrier evolves from frame to frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// Every frame carries the same amplitude (a stuck link, as far as the
+    /// stuck-frame detector is concerned).
+    Constant,
+    /// Amplitude swings by ±50% with the given period in frames.
+    Sine { period_frames: f64 },
+    /// Amplitude plus uniform noise of up to `±spread`, from a fixed seed.
+    Noisy { spread: f64, seed: u64 },
+}
+
+/// Deterministic stream of synthetic [`CsiPacket`]s for tests.
+///
+/// Subcarrier `k` has `I = round(amplitude)` and `Q = 0`, so its amplitude
+/// reads back as the pattern value. Timestamps start at `start_us` and
+/// advance by `interval_us` per frame.
+#[derive(Debug, Clone)]
+pub struct SyntheticCsi {
+    pub subcarriers: usize,
+    pub amplitude: f64,
+    pub pattern: Pattern,
+    pub start_us: u64,
+    pub interval_us: u64,
+    pub rssi: i32,
+    frame: u64,
+    rng: u64,
+}
+
+impl SyntheticCsi {
+    pub fn new(subcarriers: usize, amplitude: f64, pattern: Pattern) -> Self {
+        let rng = match pattern {
+            Pattern::Noisy { seed, .. } => seed.max(1),
+            _ => 1,
+        };
+        Self {
+            subcarriers,
+            amplitude,
+            pattern,
+            start_us: 1_000_000,
+            interval_us: 10_000,
+            rssi: -40,
+            frame: 0,
+            rng,
+        }
+    }
+
+    pub fn interval_us(mut self, interval_us: u64) -> Self {
+        self.interval_us = interval_us;
+        self
+    }
+
+    /// xorshift64, uniform in `[-1, 1)`.
+    fn noise(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+
+    fn sample(&mut self) -> f64 {
+        match self.pattern {
+            Pattern::Constant => self.amplitude,
+            Pattern::Sine { period_frames } => {
+                self.amplitude * (1.0 + 0.5 * (TAU * self.frame as f64 / period_frames).sin())
+            }
+            Pattern::Noisy { spread, .. } => self.amplitude + spread * self.noise(),
+        }
+    }
+}
+
+impl Iterator for SyntheticCsi {
+    type Item = CsiPacket;
+
+    fn next(&mut self) -> Option<CsiPacket> {
+        let mut csi_values = Vec::with_capacity(2 * self.subcarriers);
+        for _ in 0..self.subcarriers {
+            csi_values.push(self.sample().round() as i32);
+            csi_values.push(0);
+        }
+        let packet = CsiPacket {
+            esp_timestamp: self.start_us + self.frame * self.interval_us,
+            rssi: self.rssi,
+            csi_values,
+            antenna: None,
+            host_time_ms: None,
+        };
+        self.frame += 1;
+        Some(packet)
+    }
+}
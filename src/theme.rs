@@ -0,0 +1,104 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::heatmap::Colormap;
+
+/// The handful of colours the UI draws with, picked as a preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Focused pane borders, the focus highlight and the main plot series.
+    pub accent: Color,
+    /// Text drawn on the `accent` highlight.
+    pub on_accent: Color,
+    /// Unfocused list items.
+    pub text: Color,
+    /// Hints and secondary text.
+    pub muted: Color,
+    /// Newest-sample marker, the LIVE indicator and invalid Options fields.
+    pub alert: Color,
+    /// Second capture overlaid on the plot for comparison.
+    pub compare: Color,
+    /// Shading of the region of interest marked on the plot.
+    pub region: Color,
+    /// Annotation markers, cycled by label.
+    pub events: [Color; 5],
+    /// Heatmap, spectrum strip and spectrogram colours.
+    pub colormap: Colormap,
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        name: "default",
+        accent: Color::Cyan,
+        on_accent: Color::Black,
+        text: Color::White,
+        muted: Color::DarkGray,
+        alert: Color::LightRed,
+        compare: Color::LightGreen,
+        region: Color::DarkGray,
+        events: [Color::Yellow, Color::Magenta, Color::Green, Color::Red, Color::LightBlue],
+        colormap: Colormap::BlueOrange,
+    };
+
+    /// Bright yellow focus and a grayscale heatmap for low-colour or
+    /// washed-out displays.
+    pub const HIGH_CONTRAST: Theme = Theme {
+        name: "high-contrast",
+        accent: Color::Yellow,
+        on_accent: Color::Black,
+        text: Color::White,
+        muted: Color::Gray,
+        alert: Color::LightMagenta,
+        compare: Color::White,
+        region: Color::Gray,
+        events: [Color::LightCyan, Color::LightGreen, Color::LightMagenta, Color::White, Color::LightYellow],
+        colormap: Colormap::Grayscale,
+    };
+
+    /// Okabe–Ito colours and the Cividis heatmap, distinguishable with the
+    /// common forms of colour blindness.
+    pub const COLOR_BLIND: Theme = Theme {
+        name: "color-blind",
+        accent: Color::Rgb(86, 180, 233),
+        on_accent: Color::Black,
+        text: Color::White,
+        muted: Color::DarkGray,
+        alert: Color::Rgb(230, 159, 0),
+        compare: Color::Rgb(213, 94, 0),
+        region: Color::Rgb(204, 121, 167),
+        events: [
+            Color::Rgb(240, 228, 66),
+            Color::Rgb(0, 158, 115),
+            Color::Rgb(213, 94, 0),
+            Color::Rgb(204, 121, 167),
+            Color::Rgb(0, 114, 178),
+        ],
+        colormap: Colormap::Cividis,
+    };
+
+    pub const ALL: [Theme; 3] = [Theme::DEFAULT, Theme::HIGH_CONTRAST, Theme::COLOR_BLIND];
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Theme::ALL.into_iter().find(|t| t.name == name)
+    }
+
+    /// The preset after this one, wrapping around.
+    pub fn next(&self) -> Theme {
+        let i = Theme::ALL.iter().position(|t| t.name == self.name).unwrap_or(0);
+        Theme::ALL[(i + 1) % Theme::ALL.len()]
+    }
+
+    /// Highlight for the focused control or list item.
+    pub fn focused(&self) -> Style {
+        Style::default()
+            .fg(self.on_accent)
+            .bg(self.accent)
+            .add_modifier(Modifier::BOLD)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DEFAULT
+    }
+}
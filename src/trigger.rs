@@ -0,0 +1,160 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::csi_packet::CsiPacket;
+use crate::detect_motion::amplitude_for_subcarrier;
+
+/// Scope-style capture trigger.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TriggerMode {
+    /// Record from the start (default).
+    #[default]
+    Off,
+    /// Hold packets until `subcarrier`'s amplitude reaches `threshold`, then
+    /// record the last `pre_trigger` worth of packets plus the configured duration.
+    /// Give up after `timeout` without firing; `None` waits indefinitely.
+    Armed {
+        subcarrier: usize,
+        threshold: f32,
+        pre_trigger: Duration,
+        timeout: Option<Duration>,
+    },
+}
+
+/// Pre-trigger ring buffer and trigger state for one capture.
+#[derive(Debug)]
+pub struct Trigger {
+    mode: TriggerMode,
+    buffer: VecDeque<(Instant, CsiPacket)>,
+    armed_at: Instant,
+    fired_at: Option<Instant>,
+}
+
+impl Trigger {
+    pub fn new(mode: TriggerMode) -> Self {
+        Self {
+            mode,
+            buffer: VecDeque::new(),
+            armed_at: Instant::now(),
+            fired_at: None,
+        }
+    }
+
+    /// Armed and still waiting for the condition.
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.mode, TriggerMode::Armed { .. }) && self.fired_at.is_none()
+    }
+
+    pub fn fired_at(&self) -> Option<Instant> {
+        self.fired_at
+    }
+
+    /// Still waiting at `now`, longer than the mode's timeout.
+    pub fn timed_out(&self, now: Instant) -> bool {
+        match self.mode {
+            TriggerMode::Armed { timeout: Some(timeout), .. } => {
+                self.fired_at.is_none() && now.duration_since(self.armed_at) >= timeout
+            }
+            _ => false,
+        }
+    }
+
+    /// Feed a parsed packet; returns the packets that should be recorded now.
+    /// While waiting this buffers and returns nothing; when the trigger fires
+    /// it returns the whole pre-trigger buffer (oldest first).
+    pub fn feed(&mut self, packet: CsiPacket, now: Instant) -> Vec<CsiPacket> {
+        let TriggerMode::Armed {
+            subcarrier,
+            threshold,
+            pre_trigger,
+            ..
+        } = self.mode
+        else {
+            return vec![packet];
+        };
+        if self.fired_at.is_some() {
+            return vec![packet];
+        }
+
+        let fires = amplitude_for_subcarrier(&packet, subcarrier).is_some_and(|amp| amp >= threshold);
+        self.buffer.push_back((now, packet));
+        while self
+            .buffer
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > pre_trigger)
+        {
+            self.buffer.pop_front();
+        }
+
+        if fires {
+            self.fired_at = Some(now);
+            self.buffer.drain(..).map(|(_, p)| p).collect()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-subcarrier packet with amplitude `amp` (I = amp, Q = 0).
+    fn packet(ts: u64, amp: i32) -> CsiPacket {
+        CsiPacket {
+            esp_timestamp: ts,
+            rssi: -40,
+            csi_values: vec![amp, 0],
+            antenna: None,
+            host_time_ms: None,
+        }
+    }
+
+    fn armed(timeout: Option<Duration>) -> TriggerMode {
+        TriggerMode::Armed {
+            subcarrier: 0,
+            threshold: 10.0,
+            pre_trigger: Duration::from_millis(250),
+            timeout,
+        }
+    }
+
+    #[test]
+    fn crossing_the_threshold_releases_the_pre_trigger_buffer() {
+        let mut trigger = Trigger::new(armed(None));
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        assert!(trigger.feed(packet(0, 3), at(0)).is_empty());
+        assert!(trigger.feed(packet(1, 5), at(200)).is_empty());
+        assert!(trigger.feed(packet(2, 9), at(400)).is_empty());
+        assert!(trigger.is_waiting());
+        // Packet 0 is older than the 250 ms pre-trigger window by now.
+        let released = trigger.feed(packet(3, 10), at(500));
+        let released: Vec<u64> = released.iter().map(|p| p.esp_timestamp).collect();
+        assert_eq!(released, vec![2, 3]);
+        assert_eq!(trigger.fired_at(), Some(at(500)));
+        assert!(!trigger.is_waiting());
+        // From then on every packet passes straight through.
+        assert_eq!(trigger.feed(packet(4, 0), at(600)).len(), 1);
+        assert!(!trigger.timed_out(at(1_000_000)));
+    }
+
+    #[test]
+    fn below_the_threshold_nothing_is_released_until_the_timeout() {
+        let mut trigger = Trigger::new(armed(Some(Duration::from_secs(5))));
+        let t0 = Instant::now();
+        for n in 0..50 {
+            assert!(trigger.feed(packet(n, 9), t0 + Duration::from_millis(n * 10)).is_empty());
+        }
+        assert!(trigger.is_waiting());
+        assert!(!trigger.timed_out(t0));
+        assert!(trigger.timed_out(t0 + Duration::from_secs(6)));
+        // Without a timeout it waits for good; off, it never holds packets.
+        assert!(!Trigger::new(armed(None)).timed_out(t0 + Duration::from_secs(3600)));
+        let mut off = Trigger::new(TriggerMode::Off);
+        assert!(!off.is_waiting());
+        assert_eq!(off.feed(packet(0, 0), t0).len(), 1);
+    }
+}
@@ -17,6 +17,47 @@ pub enum WifiMode {
 //     }
 // }
 
+/// CSI acquisition flags sent with `set-csi`, applied identically in every
+/// WiFi mode so a board always yields the same CSI layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsiConfig {
+    pub disable_htltf: bool,
+    pub disable_stbc_htltf: bool,
+}
+
+impl Default for CsiConfig {
+    fn default() -> Self {
+        Self {
+            disable_htltf: true,
+            disable_stbc_htltf: true,
+        }
+    }
+}
+
+impl CsiConfig {
+    /// The `set-csi` command for this config, or `None` if no flags are set
+    /// (firmware defaults apply).
+    pub fn to_cli_command(&self) -> Option<String> {
+        let mut cmd = String::from("set-csi");
+        if self.disable_htltf {
+            cmd.push_str(" --disable-htltf");
+        }
+        if self.disable_stbc_htltf {
+            cmd.push_str(" --disable-stbc-htltf");
+        }
+        (cmd != "set-csi").then_some(cmd)
+    }
+}
+
+impl std::fmt::Display for CsiConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_cli_command() {
+            Some(cmd) => write!(f, "{}", cmd),
+            None => write!(f, "firmware defaults"),
+        }
+    }
+}
+
 // pub struct WifiConfig {
 //     pub mode:WifiMode,
 //     pub station_ssid:Option<String>,
@@ -31,7 +72,8 @@ pub fn apply_wifi_config(
     port: &mut dyn SerialPort, 
     mode: WifiMode,
     ssid: &str,
-    password: &str
+    password: &str,
+    csi: &CsiConfig,
 ) -> io::Result<()> {
     match mode {
         WifiMode::Sniffer => {
@@ -49,11 +91,10 @@ pub fn apply_wifi_config(
                 port,
                 &format!("set-wifi --sta-password={}", pass_escaped),
             )?;
-            send_cli_command(
-                port,
-                &format!("set-csi --disable-htltf --disable-stbc-htltf"),
-            )?;
         }
     }
+    if let Some(cmd) = csi.to_cli_command() {
+        send_cli_command(port, &cmd)?;
+    }
     Ok(())
 }
\ No newline at end of file